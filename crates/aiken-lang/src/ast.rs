@@ -6,6 +6,7 @@ use crate::{
 };
 use miette::Diagnostic;
 use owo_colors::{OwoColorize, Stream::Stdout};
+use serde::{Deserialize, Serialize};
 use std::{fmt, ops::Range, sync::Arc};
 use vec1::Vec1;
 
@@ -17,7 +18,7 @@ pub const TRY_VARIABLE: &str = "_try";
 pub type TypedModule = Module<TypeInfo, TypedDefinition>;
 pub type UntypedModule = Module<(), UntypedDefinition>;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ModuleKind {
     Lib,
     Validator,
@@ -33,7 +34,7 @@ impl ModuleKind {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Module<Info, Definitions> {
     pub name: String,
     pub docs: Vec<String>,
@@ -125,7 +126,7 @@ fn str_to_keyword(word: &str) -> Option<Token> {
 pub type TypedFunction = Function<Arc<Type>, TypedExpr>;
 pub type UntypedFunction = Function<(), UntypedExpr>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Function<T, Expr> {
     pub arguments: Vec<Arg<T>>,
     pub body: Expr,
@@ -187,7 +188,7 @@ pub fn do_test_hint(body: &TypedExpr) -> Option<(BinOp, Box<TypedExpr>, Box<Type
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TypeAlias<T> {
     pub alias: String,
     pub annotation: Annotation,
@@ -276,7 +277,7 @@ impl TypedDataType {
 
 pub type UntypedDataType = DataType<()>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DataType<T> {
     pub constructors: Vec<RecordConstructor<T>>,
     pub doc: Option<String>,
@@ -291,7 +292,7 @@ pub struct DataType<T> {
 pub type TypedUse = Use<String>;
 pub type UntypedUse = Use<()>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Use<PackageName> {
     pub as_name: Option<String>,
     pub location: Span,
@@ -303,7 +304,7 @@ pub struct Use<PackageName> {
 pub type TypedModuleConstant = ModuleConstant<Arc<Type>>;
 pub type UntypedModuleConstant = ModuleConstant<()>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModuleConstant<T> {
     pub doc: Option<String>,
     pub location: Span,
@@ -317,7 +318,7 @@ pub struct ModuleConstant<T> {
 pub type TypedValidator = Validator<Arc<Type>, TypedExpr>;
 pub type UntypedValidator = Validator<(), UntypedExpr>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Validator<T, Expr> {
     pub doc: Option<String>,
     pub end_position: usize,
@@ -330,7 +331,7 @@ pub struct Validator<T, Expr> {
 pub type TypedDefinition = Definition<Arc<Type>, TypedExpr, String>;
 pub type UntypedDefinition = Definition<(), UntypedExpr, ()>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Definition<T, Expr, PackageName> {
     Fn(Function<T, Expr>),
 
@@ -435,7 +436,7 @@ pub struct DefinitionLocation<'module> {
     pub span: Span,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Constant {
     Int {
         location: Span,
@@ -474,7 +475,7 @@ impl Constant {
 
 pub type TypedCallArg = CallArg<TypedExpr>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CallArg<A> {
     pub label: Option<String>,
     pub location: Span,
@@ -496,7 +497,7 @@ impl TypedCallArg {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RecordConstructor<T> {
     pub location: Span,
     pub name: String,
@@ -511,7 +512,7 @@ impl<A> RecordConstructor<A> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RecordConstructorArg<T> {
     pub label: Option<String>,
     // ast
@@ -530,7 +531,7 @@ impl<T: PartialEq> RecordConstructorArg<T> {
 pub type TypedArg = Arg<Arc<Type>>;
 pub type UntypedArg = Arg<()>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Arg<T> {
     pub arg_name: ArgName,
     pub location: Span,
@@ -553,7 +554,7 @@ impl<A> Arg<A> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ArgName {
     Discarded {
         name: String,
@@ -584,7 +585,7 @@ impl ArgName {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UnqualifiedImport {
     pub location: Span,
     pub name: String,
@@ -603,7 +604,7 @@ impl UnqualifiedImport {
 }
 
 // TypeAst
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Annotation {
     Constructor {
         location: Span,
@@ -720,7 +721,7 @@ impl Annotation {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Layer {
     #[default]
     Value,
@@ -734,7 +735,7 @@ impl Layer {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BinOp {
     // Boolean logic
     And,
@@ -756,9 +757,12 @@ pub enum BinOp {
     MultInt,
     DivInt,
     ModInt,
+
+    // String and ByteArray
+    Concat,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UnOp {
     // !
     Not,
@@ -779,7 +783,7 @@ impl BinOp {
             Self::LtInt | Self::LtEqInt | Self::GtEqInt | Self::GtInt => 4,
 
             // Pipe is 5
-            Self::AddInt | Self::SubInt => 6,
+            Self::AddInt | Self::SubInt | Self::Concat => 6,
 
             Self::MultInt | Self::DivInt | Self::ModInt => 7,
         }
@@ -789,13 +793,26 @@ impl BinOp {
 pub type UntypedPattern = Pattern<(), ()>;
 pub type TypedPattern = Pattern<PatternConstructor, Arc<Type>>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Pattern<Constructor, Type> {
     Int {
         location: Span,
         value: String,
     },
 
+    /// e.g. `#"deadbeef"` or `"foo"`
+    ByteArray {
+        location: Span,
+        value: Vec<u8>,
+        preferred_format: ByteArrayFormatPreference,
+    },
+
+    /// e.g. `@"foo"`
+    String {
+        location: Span,
+        value: String,
+    },
+
     /// The creation of a variable.
     /// e.g. `expect [this_is_a_var, .._] = x`
     /// e.g. `let foo = 42`
@@ -853,6 +870,8 @@ impl<A, B> Pattern<A, B> {
         match self {
             Pattern::Assign { pattern, .. } => pattern.location(),
             Pattern::Int { location, .. }
+            | Pattern::ByteArray { location, .. }
+            | Pattern::String { location, .. }
             | Pattern::Var { location, .. }
             | Pattern::List { location, .. }
             | Pattern::Discard { location, .. }
@@ -876,14 +895,14 @@ impl<A, B> Pattern<A, B> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub enum ByteArrayFormatPreference {
     HexadecimalString,
     ArrayOfBytes,
     Utf8String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub enum AssignmentKind {
     Let,
     Expect,
@@ -911,7 +930,7 @@ pub type MultiPattern<PatternConstructor, Type> = Vec<Pattern<PatternConstructor
 pub type UntypedMultiPattern = MultiPattern<(), ()>;
 pub type TypedMultiPattern = MultiPattern<PatternConstructor, Arc<Type>>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UntypedClause {
     pub location: Span,
     pub patterns: Vec1<Pattern<(), ()>>,
@@ -919,7 +938,7 @@ pub struct UntypedClause {
     pub then: UntypedExpr,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TypedClause {
     pub location: Span,
     pub pattern: Pattern<PatternConstructor, Arc<Type>>,
@@ -943,7 +962,7 @@ impl TypedClause {
 pub type UntypedClauseGuard = ClauseGuard<()>;
 pub type TypedClauseGuard = ClauseGuard<Arc<Type>>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ClauseGuard<Type> {
     Not {
         location: Span,
@@ -1061,14 +1080,14 @@ impl TypedClauseGuard {
 pub type TypedIfBranch = IfBranch<TypedExpr>;
 pub type UntypedIfBranch = IfBranch<UntypedExpr>;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IfBranch<Expr> {
     pub condition: Expr,
     pub body: Expr,
     pub location: Span,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TypedRecordUpdateArg {
     pub label: String,
     pub location: Span,
@@ -1082,20 +1101,20 @@ impl TypedRecordUpdateArg {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UntypedRecordUpdateArg {
     pub label: String,
     pub location: Span,
     pub value: UntypedExpr,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RecordUpdateSpread {
     pub base: Box<UntypedExpr>,
     pub location: Span,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TraceKind {
     Trace,
     Todo,
@@ -1118,7 +1137,7 @@ impl From<bool> for Tracing {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
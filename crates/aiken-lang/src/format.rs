@@ -1499,6 +1499,14 @@ impl<'comments> Formatter<'comments> {
         let doc = match pattern {
             Pattern::Int { value, .. } => value.to_doc(),
 
+            Pattern::ByteArray {
+                value,
+                preferred_format,
+                ..
+            } => self.bytearray(value, preferred_format),
+
+            Pattern::String { value, .. } => self.string(value),
+
             Pattern::Var { name, .. } => name.to_doc(),
 
             Pattern::Assign { name, pattern, .. } => {
@@ -1667,6 +1675,7 @@ impl<'a> Documentable<'a> for &'a BinOp {
             BinOp::MultInt => " * ",
             BinOp::DivInt => " / ",
             BinOp::ModInt => " % ",
+            BinOp::Concat => " <> ",
         }
         .to_doc()
     }
@@ -6,7 +6,9 @@ use uplc::{
     ast::{
         Constant as UplcConstant, DeBruijn, Name, NamedDeBruijn, Program, Term, Type as UplcType,
     },
-    builder::{CONSTR_FIELDS_EXPOSER, CONSTR_GET_FIELD, CONSTR_INDEX_EXPOSER, EXPECT_ON_LIST},
+    builder::{
+        CONSTR_FIELDS_EXPOSER, CONSTR_GET_FIELD, CONSTR_INDEX_EXPOSER, EXPECT_ON_LIST, LIST_EQUALS,
+    },
     builtins::DefaultFunction,
     machine::cost_model::ExBudget,
     optimize::aiken_optimize_and_intern,
@@ -15,10 +17,10 @@ use uplc::{
 
 use crate::{
     ast::{
-        ArgName, AssignmentKind, BinOp, Pattern, Span, TypedArg, TypedClause, TypedDataType,
-        TypedFunction, TypedValidator, UnOp,
+        ArgName, AssignmentKind, BinOp, CallArg, Pattern, Span, TypedArg, TypedClause,
+        TypedDataType, TypedFunction, TypedValidator, UnOp,
     },
-    builtins::{bool, data, void},
+    builtins::{bool, byte_array, data, int, string, void},
     expr::TypedExpr,
     gen_uplc::builder::{find_and_replace_generics, get_generic_id_and_type, get_variant_name},
     tipo::{
@@ -30,22 +32,76 @@ use crate::{
 
 pub mod air;
 pub mod builder;
+pub mod error;
 pub mod scope;
 pub mod stack;
+pub mod supported;
 
 use air::Air;
 use builder::{
     AssignmentProperties, ClauseProperties, DataTypeKey, FuncComponents, FunctionAccessKey,
 };
+use error::{CodeGenError, EvalError};
+use supported::{UnsupportedFeature, UnsupportedKind};
 
 use self::{builder::replace_opaque_type, scope::Scope, stack::AirStack};
 
+/// Nesting depth of the types `expect` checks, unless configured otherwise
+/// with [`CodeGenerator::set_expect_depth_limit`].
+pub const DEFAULT_EXPECT_DEPTH_LIMIT: usize = 128;
+
+/// Version of the generated programs, unless configured otherwise with
+/// [`CodeGenerator::set_version`].
+pub const DEFAULT_VERSION: (usize, usize, usize) = (1, 0, 0);
+
+/// Which optimizations generated programs go through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OptLevel {
+    /// The raw lowering, only interned. Mostly useful for debugging.
+    None,
+    /// Constant folding and the other rewrites done while lowering, then a
+    /// single run of the UPLC optimizer.
+    #[default]
+    Basic,
+    /// Like [`OptLevel::Basic`], but the UPLC optimizer runs again for as long
+    /// as it keeps making the program smaller.
+    Full,
+}
+
 #[derive(Clone, Debug)]
 pub enum CodeGenFunction {
     Function(Vec<Air>, Vec<String>),
     Link(String),
 }
 
+/// Owned copies of the functions, data types and module types looked up during
+/// code generation. Unlike a [`CodeGenerator`], which borrows them, it can be
+/// kept around (e.g. behind an [`Rc`]) by long-lived embedders such as a
+/// language server, and lend them to a new generator whenever one is needed.
+#[derive(Debug, Clone, Default)]
+pub struct CodeGenContext {
+    pub functions: IndexMap<FunctionAccessKey, TypedFunction>,
+    pub data_types: IndexMap<DataTypeKey, TypedDataType>,
+    pub module_types: IndexMap<String, TypeInfo>,
+}
+
+impl CodeGenContext {
+    /// A new generator borrowing these definitions. Each call indexes them all
+    /// again, which takes time linear in their number, so a generator meant to
+    /// compile several programs is better kept and reused, as generation
+    /// resets it anyway.
+    pub fn generator(&self) -> CodeGenerator<'_> {
+        CodeGenerator::new(
+            self.functions.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            self.data_types
+                .iter()
+                .map(|(k, v)| (k.clone(), v))
+                .collect(),
+            self.module_types.iter().collect(),
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct CodeGenerator<'a> {
     defined_functions: IndexMap<FunctionAccessKey, ()>,
@@ -54,9 +110,19 @@ pub struct CodeGenerator<'a> {
     module_types: IndexMap<&'a String, &'a TypeInfo>,
     id_gen: Rc<IdGenerator>,
     needs_field_access: bool,
+    needs_list_equality: bool,
+    element_wise_list_equality: bool,
     code_gen_functions: IndexMap<String, CodeGenFunction>,
     zero_arg_functions: IndexMap<FunctionAccessKey, Vec<Air>>,
     uplc_to_function: IndexMap<Program<DeBruijn>, FunctionAccessKey>,
+    opt_level: OptLevel,
+    version: (usize, usize, usize),
+    error: Option<CodeGenError>,
+    expect_depth: usize,
+    expect_depth_limit: usize,
+    annotations: Option<Vec<String>>,
+    source_spans: Option<Vec<Span>>,
+    source_map: SourceMap,
 }
 
 impl<'a> CodeGenerator<'a> {
@@ -71,23 +137,143 @@ impl<'a> CodeGenerator<'a> {
             data_types,
             module_types,
             needs_field_access: false,
+            needs_list_equality: false,
+            element_wise_list_equality: false,
             id_gen: IdGenerator::new().into(),
             code_gen_functions: IndexMap::new(),
             zero_arg_functions: IndexMap::new(),
             uplc_to_function: IndexMap::new(),
+            opt_level: OptLevel::default(),
+            version: DEFAULT_VERSION,
+            error: None,
+            expect_depth: 0,
+            expect_depth_limit: DEFAULT_EXPECT_DEPTH_LIMIT,
+            annotations: None,
+            source_spans: None,
+            source_map: vec![],
         }
     }
 
+    /// Copies the definitions this generator looks up into a [`CodeGenContext`].
+    pub fn context(&self) -> CodeGenContext {
+        CodeGenContext {
+            functions: self
+                .functions
+                .iter()
+                .map(|(k, v)| (k.clone(), (*v).clone()))
+                .collect(),
+            data_types: self
+                .data_types
+                .iter()
+                .map(|(k, v)| (k.clone(), (*v).clone()))
+                .collect(),
+            module_types: self
+                .module_types
+                .iter()
+                .map(|(k, v)| ((*k).clone(), (*v).clone()))
+                .collect(),
+        }
+    }
+
+    /// Which optimizations generated programs go through.
+    pub fn with_optimizations(mut self, level: OptLevel) -> Self {
+        self.opt_level = level;
+        self
+    }
+
+    /// Whether generated programs go through the optimizer, at the default
+    /// level. When disabled, programs are only interned, which is mostly
+    /// useful for debugging.
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.opt_level = if optimize {
+            OptLevel::default()
+        } else {
+            OptLevel::None
+        };
+    }
+
+    /// The UPLC version stamped on generated programs.
+    pub fn set_version(&mut self, version: (usize, usize, usize)) {
+        self.version = version;
+    }
+
+    /// Whether lists of `Int`, `ByteArray` or `String` are compared by length
+    /// then element-wise, through a generated helper, instead of with
+    /// `EqualsData`. Off by default, as `EqualsData` is both smaller and
+    /// cheaper to run.
+    pub fn set_element_wise_list_equality(&mut self, enabled: bool) {
+        self.element_wise_list_equality = enabled;
+    }
+
+    /// How deeply nested a type checked by `expect` can be before code
+    /// generation gives up with [`CodeGenError::ExpectTooDeep`].
+    pub fn set_expect_depth_limit(&mut self, limit: usize) {
+        self.expect_depth_limit = limit;
+    }
+
+    /// Forget everything about the last generated program. This happens at
+    /// the start and end of each generation, so one generator can be reused
+    /// to compile any number of validators and tests.
     pub fn reset(&mut self) {
         self.code_gen_functions = IndexMap::new();
         self.zero_arg_functions = IndexMap::new();
         self.id_gen = IdGenerator::new().into();
         self.needs_field_access = false;
+        self.needs_list_equality = false;
         self.defined_functions = IndexMap::new();
         self.uplc_to_function = IndexMap::new();
+        self.error = None;
+    }
+
+    pub fn generate(&mut self, validator: &TypedValidator) -> Program<Name> {
+        self.try_generate(validator)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Like [`CodeGenerator::generate`], but also returns the program pretty
+    /// printed below the IR it was generated from, as comments with one node
+    /// per line indented by scope. Useful to debug code generation.
+    pub fn generate_annotated(&mut self, validator: &TypedValidator) -> (Program<Name>, String) {
+        self.annotations = Some(vec![]);
+
+        let program = self.try_generate(validator);
+
+        let annotations = self.annotations.take().unwrap_or_default();
+
+        let program = program.unwrap_or_else(|error| panic!("{error}"));
+
+        let annotated = format!("{}\n{}", annotations.join("\n"), program.to_pretty());
+
+        (program, annotated)
+    }
+
+    /// Like [`CodeGenerator::generate`], but also returns where the terms of
+    /// binary operators, traces and errors come from in the source, so that
+    /// a failure can be traced back to it. The program isn't optimized, as
+    /// optimizations move terms around. Errors are returned like
+    /// [`CodeGenerator::try_generate`] does.
+    pub fn generate_with_source_map(
+        &mut self,
+        validator: &TypedValidator,
+    ) -> Result<(Program<Name>, SourceMap), CodeGenError> {
+        let opt_level = std::mem::replace(&mut self.opt_level, OptLevel::None);
+
+        self.source_spans = Some(vec![]);
+
+        let program = self.try_generate(validator);
+
+        self.opt_level = opt_level;
+        self.source_spans = None;
+
+        let source_map = std::mem::take(&mut self.source_map);
+
+        Ok((program?, source_map))
     }
 
-    pub fn generate(
+    /// Like [`CodeGenerator::generate`], but returns an error instead of
+    /// panicking when the validator uses something code generation doesn't
+    /// support or refers to a definition the generator doesn't know about.
+    pub fn try_generate(
         &mut self,
         TypedValidator {
             fun,
@@ -95,7 +281,9 @@ impl<'a> CodeGenerator<'a> {
             params,
             ..
         }: &TypedValidator,
-    ) -> Program<Name> {
+    ) -> Result<Program<Name>, CodeGenError> {
+        self.reset();
+
         let mut ir_stack = AirStack::new(self.id_gen.clone());
 
         ir_stack.noop();
@@ -109,8 +297,10 @@ impl<'a> CodeGenerator<'a> {
 
         self.build(&fun.body, &mut body_stack);
 
+        self.check_error()?;
+
         unit_stack.void();
-        error_stack.error(void());
+        error_stack.error(void(), Span::empty());
 
         ir_stack.merge_child(args_stack);
         ir_stack.if_branch(bool(), body_stack, unit_stack);
@@ -118,15 +308,29 @@ impl<'a> CodeGenerator<'a> {
 
         let mut ir_stack = ir_stack.complete();
 
+        if self.opt_level != OptLevel::None {
+            self.fold_constants(&mut ir_stack);
+        }
+
         self.define_ir(&mut ir_stack);
 
+        self.check_error()?;
+
         self.convert_opaque_type_to_inner_ir(&mut ir_stack);
 
         let mut term = self.uplc_code_gen(&mut ir_stack);
 
+        self.check_error()?;
+
         if let Some(other) = other_fun {
+            // Both validators end up in the same program, so it needs the
+            // helpers either of them uses.
+            let needs_list_equality = self.needs_list_equality;
+
             self.reset();
 
+            self.needs_list_equality = needs_list_equality;
+
             let mut other_ir_stack = AirStack::new(self.id_gen.clone());
 
             other_ir_stack.noop();
@@ -140,8 +344,10 @@ impl<'a> CodeGenerator<'a> {
 
             self.build(&other.body, &mut body_stack);
 
+            self.check_error()?;
+
             unit_stack.void();
-            error_stack.error(void());
+            error_stack.error(void(), Span::empty());
 
             other_ir_stack.merge_child(args_stack);
             other_ir_stack.if_branch(bool(), body_stack, unit_stack);
@@ -149,12 +355,20 @@ impl<'a> CodeGenerator<'a> {
 
             let mut other_ir_stack = other_ir_stack.complete();
 
+            if self.opt_level != OptLevel::None {
+                self.fold_constants(&mut other_ir_stack);
+            }
+
             self.define_ir(&mut other_ir_stack);
 
+            self.check_error()?;
+
             self.convert_opaque_type_to_inner_ir(&mut other_ir_stack);
 
             let other_term = self.uplc_code_gen(&mut other_ir_stack);
 
+            self.check_error()?;
+
             let (spend, mint) = if other.arguments.len() > fun.arguments.len() {
                 (other_term, term)
             } else {
@@ -168,29 +382,152 @@ impl<'a> CodeGenerator<'a> {
 
         term = builder::wrap_validator_args(term, params);
 
-        self.finalize(term)
+        Ok(self.finalize(term))
     }
 
     pub fn generate_test(&mut self, test_body: &TypedExpr) -> Program<Name> {
+        self.try_generate_test(test_body)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    pub fn try_generate_test(
+        &mut self,
+        test_body: &TypedExpr,
+    ) -> Result<Program<Name>, CodeGenError> {
+        self.reset();
+
         let mut ir_stack = AirStack::new(self.id_gen.clone());
 
         ir_stack.noop();
 
         self.build(test_body, &mut ir_stack);
 
+        self.check_error()?;
+
         let mut ir_stack = ir_stack.complete();
 
+        if self.opt_level != OptLevel::None {
+            self.fold_constants(&mut ir_stack);
+        }
+
         self.define_ir(&mut ir_stack);
 
+        self.check_error()?;
+
         self.convert_opaque_type_to_inner_ir(&mut ir_stack);
 
         let term = self.uplc_code_gen(&mut ir_stack);
 
-        self.finalize(term)
+        self.check_error()?;
+
+        Ok(self.finalize(term))
+    }
+
+    /// Keeps the first error met while building, so that generation can stop
+    /// at the end of the current stage instead of panicking mid-way.
+    fn fail(&mut self, error: CodeGenError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    fn unsupported(&mut self, kind: UnsupportedKind, span: Span) {
+        self.fail(UnsupportedFeature { kind, span }.into());
+    }
+
+    /// Pushes `func` applied to `args`, unless it is one of the builtins the
+    /// generator has no lowering for.
+    fn builtin(
+        &mut self,
+        ir_stack: &mut AirStack,
+        func: DefaultFunction,
+        tipo: Arc<Type>,
+        args: Vec<AirStack>,
+        location: Span,
+    ) {
+        match UnsupportedKind::builtin(func) {
+            Some(kind) => self.unsupported(kind, location),
+            None => ir_stack.builtin(func, tipo, args),
+        }
+    }
+
+    fn check_error(&mut self) -> Result<(), CodeGenError> {
+        match self.error.take() {
+            Some(error) => {
+                self.reset();
+
+                Err(error)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Builds a call to the record constructor `constr_name`, of type `tipo`,
+    /// as the record itself. Returns `false`, building nothing, when the data
+    /// type isn't known so that it's called like any other function.
+    fn build_record(
+        &mut self,
+        ir_stack: &mut AirStack,
+        constr_name: &str,
+        tipo: &Arc<Type>,
+        fun: &TypedExpr,
+        args: &[CallArg<TypedExpr>],
+    ) -> bool {
+        let Some(data_type) = builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo)
+        else {
+            return false;
+        };
+
+        let Some(constr_index) = data_type
+            .constructors
+            .iter()
+            .position(|constr| constr.name == constr_name)
+        else {
+            self.fail(CodeGenError::MissingConstructor {
+                name: constr_name.to_string(),
+                data_type: data_type.name,
+            });
+
+            return true;
+        };
+
+        let Some(fun_arg_types) = fun.tipo().arg_types() else {
+            unreachable!()
+        };
+
+        let mut stacks = Vec::new();
+
+        for (arg, func_type) in args.iter().zip(fun_arg_types) {
+            let mut stack = ir_stack.empty_with_scope();
+
+            if func_type.is_data() && !arg.value.tipo().is_data() {
+                stack.wrap_data(arg.value.tipo());
+            }
+
+            self.build(&arg.value, &mut stack);
+
+            stacks.push(stack);
+        }
+
+        ir_stack.record(tipo.clone(), constr_index, stacks);
+
+        true
+    }
+
+    fn lookup_data_type(&mut self, tipo: &Type) -> Option<TypedDataType> {
+        let data_type = builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo);
+
+        if data_type.is_none() {
+            self.fail(CodeGenError::MissingDataType {
+                name: tipo.to_pretty(0),
+            });
+        }
+
+        data_type
     }
 
     fn finalize(&mut self, term: Term<Name>) -> Program<Name> {
-        let mut term = term;
+        let mut term = builder::stabilize_generated_names(&term, &mut vec![]);
 
         if self.needs_field_access {
             term = term
@@ -199,12 +536,44 @@ impl<'a> CodeGenerator<'a> {
                 .constr_index_exposer();
         }
 
+        if self.needs_list_equality {
+            term = term.list_equals();
+        }
+
+        if let Some(spans) = &self.source_spans {
+            self.source_map.clear();
+
+            term = builder::strip_source_markers(&term, &mut vec![], spans, &mut self.source_map);
+        }
+
         let mut program = Program {
-            version: (1, 0, 0),
+            version: self.version,
             term,
         };
 
-        program = aiken_optimize_and_intern(program);
+        match self.opt_level {
+            OptLevel::None => {
+                let mut interner = Interner::new();
+
+                interner.program(&mut program);
+            }
+            OptLevel::Basic => {
+                program = aiken_optimize_and_intern(program);
+            }
+            OptLevel::Full => {
+                program = aiken_optimize_and_intern(program);
+
+                loop {
+                    let optimized = aiken_optimize_and_intern(program.clone());
+
+                    if term_size(&optimized) >= term_size(&program) {
+                        break;
+                    }
+
+                    program = optimized;
+                }
+            }
+        }
 
         // This is very important to call here.
         // If this isn't done, re-using the same instance
@@ -235,7 +604,9 @@ impl<'a> CodeGenerator<'a> {
                 ir_stack.sequence(stacks);
             }
             TypedExpr::Var {
-                constructor, name, ..
+                constructor,
+                name,
+                location,
             } => match &constructor.variant {
                 ValueConstructorVariant::ModuleConstant { literal, .. } => {
                     builder::constants_ir(literal, ir_stack);
@@ -244,7 +615,13 @@ impl<'a> CodeGenerator<'a> {
                     builtin: Some(builtin),
                     ..
                 } => {
-                    ir_stack.builtin(*builtin, constructor.tipo.clone(), vec![]);
+                    self.builtin(
+                        ir_stack,
+                        *builtin,
+                        constructor.tipo.clone(),
+                        vec![],
+                        *location,
+                    );
                 }
                 _ => {
                     ir_stack.var(constructor.clone(), name, "");
@@ -290,43 +667,21 @@ impl<'a> CodeGenerator<'a> {
             TypedExpr::Call {
                 fun, args, tipo, ..
             } => {
+                // Labelled arguments were already put back in the order of the
+                // callee's parameters during type inference.
                 match &**fun {
                     TypedExpr::Var { constructor, .. } => match &constructor.variant {
                         ValueConstructorVariant::Record {
                             name: constr_name, ..
-                        } => {
-                            if let Some(data_type) =
-                                builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo)
-                            {
-                                let (constr_index, _) = data_type
-                                    .constructors
-                                    .iter()
-                                    .enumerate()
-                                    .find(|(_, dt)| &dt.name == constr_name)
-                                    .unwrap();
-
-                                let Some(fun_arg_types) = fun.tipo().arg_types() else {
-                                    unreachable!()
-                                };
-
-                                let mut stacks = Vec::new();
-
-                                for (arg, func_type) in args.iter().zip(fun_arg_types) {
-                                    let mut stack = ir_stack.empty_with_scope();
-
-                                    if func_type.is_data() && !arg.value.tipo().is_data() {
-                                        stack.wrap_data(arg.value.tipo());
-                                    }
-
-                                    self.build(&arg.value, &mut stack);
-
-                                    stacks.push(stack);
-                                }
-
-                                ir_stack.record(constructor.tipo.clone(), constr_index, stacks);
-
-                                return;
-                            }
+                        } if self.build_record(
+                            ir_stack,
+                            constr_name,
+                            &constructor.tipo,
+                            fun,
+                            args,
+                        ) =>
+                        {
+                            return;
                         }
                         ValueConstructorVariant::ModuleFn {
                             builtin: Some(func),
@@ -348,7 +703,7 @@ impl<'a> CodeGenerator<'a> {
                                 stacks.push(stack);
                             }
 
-                            ir_stack.builtin(*func, tipo.clone(), stacks);
+                            self.builtin(ir_stack, *func, tipo.clone(), stacks, fun.location());
 
                             return;
                         }
@@ -363,37 +718,8 @@ impl<'a> CodeGenerator<'a> {
                             name: constr_name,
                             tipo,
                             ..
-                        } => {
-                            if let Some(data_type) =
-                                builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo)
-                            {
-                                let (constr_index, _) = data_type
-                                    .constructors
-                                    .iter()
-                                    .enumerate()
-                                    .find(|(_, dt)| &dt.name == constr_name)
-                                    .unwrap();
-
-                                let Some(fun_arg_types) = fun.tipo().arg_types() else {unreachable!()};
-
-                                let mut stacks = Vec::new();
-
-                                for (arg, func_type) in args.iter().zip(fun_arg_types) {
-                                    let mut stack = ir_stack.empty_with_scope();
-
-                                    if func_type.is_data() && !arg.value.tipo().is_data() {
-                                        stack.wrap_data(arg.value.tipo());
-                                    }
-
-                                    self.build(&arg.value, &mut stack);
-
-                                    stacks.push(stack);
-                                }
-
-                                ir_stack.record(tipo.clone(), constr_index, stacks);
-
-                                return;
-                            }
+                        } if self.build_record(ir_stack, constr_name, tipo, fun, args) => {
+                            return;
                         }
                         ModuleValueConstructor::Fn { name, .. } => {
                             let type_info = self.module_types.get(module_name).unwrap();
@@ -417,7 +743,7 @@ impl<'a> CodeGenerator<'a> {
                                     stacks.push(stack);
                                 }
 
-                                ir_stack.builtin(*func, tipo.clone(), stacks);
+                                self.builtin(ir_stack, *func, tipo.clone(), stacks, fun.location());
 
                                 return;
                             }
@@ -449,7 +775,11 @@ impl<'a> CodeGenerator<'a> {
                 ir_stack.call(tipo.clone(), fun_stack, stacks);
             }
             TypedExpr::BinOp {
-                name, left, right, ..
+                name,
+                left,
+                right,
+                location,
+                ..
             } => {
                 let mut left_stack = ir_stack.empty_with_scope();
                 let mut right_stack = ir_stack.empty_with_scope();
@@ -457,7 +787,7 @@ impl<'a> CodeGenerator<'a> {
                 self.build(left, &mut left_stack);
                 self.build(right, &mut right_stack);
 
-                ir_stack.binop(*name, left.tipo(), left_stack, right_stack);
+                ir_stack.binop(*name, left.tipo(), *location, left_stack, right_stack);
             }
             TypedExpr::Assignment {
                 value,
@@ -495,7 +825,14 @@ impl<'a> CodeGenerator<'a> {
 
                 let subject_tipo = subject.tipo();
 
-                if clauses.len() <= 1 {
+                // A single clause binds the subject like a `let` would, unless it
+                // matches a literal, which must still be checked.
+                let is_literal_clause = matches!(
+                    clauses[0].pattern,
+                    Pattern::Int { .. } | Pattern::ByteArray { .. } | Pattern::String { .. }
+                );
+
+                if clauses.len() <= 1 && !is_literal_clause {
                     let mut value_stack = ir_stack.empty_with_scope();
                     let mut pattern_stack = ir_stack.empty_with_scope();
                     let mut subject_stack = ir_stack.empty_with_scope();
@@ -518,14 +855,51 @@ impl<'a> CodeGenerator<'a> {
                     pattern_stack.merge_child(value_stack);
                     ir_stack.merge(pattern_stack);
                 } else {
+                    let checks_elements =
+                        subject_tipo.is_list() && builder::list_clauses_check_elements(clauses);
+
                     // TODO: go over rearrange clauses
-                    let clauses = if subject_tipo.is_list() {
+                    let clauses = if subject_tipo.is_list() && !checks_elements {
                         builder::rearrange_clauses(clauses.clone())
                     } else {
                         clauses.clone()
                     };
 
-                    if let Some((last_clause, clauses)) = clauses.split_last() {
+                    // When the last clause has a guard, or picks one literal, or one
+                    // constructor out of many, it is checked like any other and
+                    // anything else falls through to an error, rather than being
+                    // assumed. Exhaustiveness never accounts for literals, so the
+                    // same goes for a last clause with a literal nested in it.
+                    let last_clause_has_guard = matches!(
+                        clauses.last(),
+                        Some(clause) if clause.guard.is_some()
+                            || builder::has_nested_literal(&clause.pattern)
+                    );
+
+                    let guard_last_clause = last_clause_has_guard
+                        || match clauses.last().map(|clause| &clause.pattern) {
+                            Some(
+                                Pattern::Int { .. }
+                                | Pattern::ByteArray { .. }
+                                | Pattern::String { .. },
+                            ) => true,
+                            Some(Pattern::List { .. }) => checks_elements,
+                            Some(Pattern::Assign { pattern, .. }) => {
+                                checks_elements && matches!(pattern.as_ref(), Pattern::List { .. })
+                            }
+                            Some(Pattern::Constructor { .. }) => {
+                                !subject_tipo.is_bool()
+                                    && builder::lookup_data_type_by_tipo(
+                                        self.data_types.clone(),
+                                        &subject_tipo,
+                                    )
+                                    .map(|data_type| data_type.constructors.len() > 1)
+                                    .unwrap_or(false)
+                            }
+                            _ => false,
+                        };
+
+                    if let Some((last_clause, init_clauses)) = clauses.split_last() {
                         let mut pattern_stack = ir_stack.empty_with_scope();
 
                         let mut clause_properties = ClauseProperties::init(
@@ -534,35 +908,64 @@ impl<'a> CodeGenerator<'a> {
                             subject_name.clone(),
                         );
 
-                        self.handle_each_clause(
-                            &mut pattern_stack,
-                            &mut clause_properties,
-                            clauses,
-                            &subject_tipo,
-                        );
-
-                        let last_pattern = &last_clause.pattern;
+                        if let ClauseProperties::ListClause {
+                            checks_elements: checks,
+                            ..
+                        } = &mut clause_properties
+                        {
+                            *checks = checks_elements;
+                        }
 
-                        let mut final_pattern_stack = ir_stack.empty_with_scope();
-                        let mut final_clause_stack = ir_stack.empty_with_scope();
                         let mut finally_stack = ir_stack.empty_with_scope();
 
-                        self.build(&last_clause.then, &mut final_clause_stack);
+                        if guard_last_clause {
+                            self.handle_each_clause(
+                                &mut pattern_stack,
+                                &mut clause_properties,
+                                &clauses,
+                                &subject_tipo,
+                            );
 
-                        *clause_properties.is_final_clause() = true;
+                            finally_stack.trace(last_clause.then.tipo(), Span::empty());
+                            finally_stack.string("unmatched pattern");
+                            finally_stack.error(last_clause.then.tipo(), Span::empty());
+                        } else {
+                            self.handle_each_clause(
+                                &mut pattern_stack,
+                                &mut clause_properties,
+                                init_clauses,
+                                &subject_tipo,
+                            );
 
-                        self.when_pattern(
-                            last_pattern,
-                            &mut final_pattern_stack,
-                            final_clause_stack,
-                            &subject_tipo,
-                            &mut clause_properties,
-                        );
+                            let last_pattern = &last_clause.pattern;
 
-                        if !matches!(last_pattern, Pattern::Tuple { .. }) {
-                            finally_stack.finally(final_pattern_stack);
-                        } else {
-                            finally_stack.merge(final_pattern_stack);
+                            let mut final_pattern_stack = ir_stack.empty_with_scope();
+                            let mut final_clause_stack = ir_stack.empty_with_scope();
+
+                            self.build(&last_clause.then, &mut final_clause_stack);
+
+                            *clause_properties.is_final_clause() = true;
+
+                            self.when_pattern(
+                                last_pattern,
+                                &mut final_pattern_stack,
+                                final_clause_stack,
+                                &subject_tipo,
+                                &mut clause_properties,
+                            );
+
+                            let is_tuple_pattern = match last_pattern {
+                                Pattern::Assign { pattern, .. } => {
+                                    matches!(pattern.as_ref(), Pattern::Tuple { .. })
+                                }
+                                pattern => matches!(pattern, Pattern::Tuple { .. }),
+                            };
+
+                            if !is_tuple_pattern {
+                                finally_stack.finally(final_pattern_stack);
+                            } else {
+                                finally_stack.merge(final_pattern_stack);
+                            }
                         }
 
                         if *clause_properties.needs_constr_var() {
@@ -608,11 +1011,21 @@ impl<'a> CodeGenerator<'a> {
                 }
             }
             TypedExpr::If {
+                location,
                 branches,
                 final_else,
                 tipo,
-                ..
             } => {
+                if self.opt_level != OptLevel::None {
+                    if let Some(when) =
+                        builder::if_chain_as_when(*location, branches, final_else, tipo)
+                    {
+                        self.build(&when, ir_stack);
+
+                        return;
+                    }
+                }
+
                 for branch in branches.iter() {
                     let mut condition_stack = ir_stack.empty_with_scope();
                     let mut branch_body_stack = ir_stack.empty_with_scope();
@@ -646,10 +1059,18 @@ impl<'a> CodeGenerator<'a> {
                 constructor,
                 module_name,
                 tipo,
+                location,
                 ..
             } => match constructor {
-                ModuleValueConstructor::Record { .. } => {
-                    todo!("Records from modules not yet implemented.")
+                ModuleValueConstructor::Record { name, .. } => {
+                    let type_info = self.module_types.get(module_name).unwrap();
+                    let value = type_info.values.get(name).unwrap();
+
+                    ir_stack.var(
+                        ValueConstructor::public(tipo.clone(), value.variant.clone()),
+                        name,
+                        "",
+                    );
                 }
                 ModuleValueConstructor::Fn { name, module, .. } => {
                     let func = self.functions.get(&FunctionAccessKey {
@@ -667,14 +1088,17 @@ impl<'a> CodeGenerator<'a> {
                             format!("{module}_{name}"),
                             "",
                         );
+                    } else if let ValueConstructorVariant::ModuleFn {
+                        builtin: Some(builtin),
+                        ..
+                    } = &value.variant
+                    {
+                        self.builtin(ir_stack, *builtin, tipo.clone(), vec![], *location);
                     } else {
-                        let ValueConstructorVariant::ModuleFn {
-                            builtin: Some(builtin), ..
-                        } = &value.variant else {
-                            unreachable!()
-                        };
-
-                        ir_stack.builtin(*builtin, tipo.clone(), vec![]);
+                        self.fail(CodeGenError::MissingFunction {
+                            module: module_name.clone(),
+                            name: name.clone(),
+                        });
                     }
                 }
                 ModuleValueConstructor::Constant { literal, .. } => {
@@ -727,7 +1151,10 @@ impl<'a> CodeGenerator<'a> {
             }
 
             TypedExpr::Trace {
-                tipo, then, text, ..
+                location,
+                tipo,
+                then,
+                text,
             } => {
                 let mut text_stack = ir_stack.empty_with_scope();
                 let mut then_stack = ir_stack.empty_with_scope();
@@ -735,7 +1162,7 @@ impl<'a> CodeGenerator<'a> {
                 self.build(text, &mut text_stack);
                 self.build(then, &mut then_stack);
 
-                ir_stack.trace(tipo.clone());
+                ir_stack.trace(tipo.clone(), *location);
                 ir_stack.merge_child(text_stack);
                 ir_stack.merge_child(then_stack);
             }
@@ -748,8 +1175,8 @@ impl<'a> CodeGenerator<'a> {
                 ir_stack.tuple_index(tuple.tipo(), *index, tuple_stack);
             }
 
-            TypedExpr::ErrorTerm { tipo, .. } => {
-                ir_stack.error(tipo.clone());
+            TypedExpr::ErrorTerm { location, tipo } => {
+                ir_stack.error(tipo.clone(), *location);
             }
         }
     }
@@ -798,6 +1225,27 @@ impl<'a> CodeGenerator<'a> {
                 clause_then_stack = clause_guard_stack;
             }
 
+            // A variable or a discard matches anything, so there's nothing to
+            // compare the subject against: the clause binds it and checks its
+            // guard, falling through to the next clause when the guard fails.
+            if matches!(
+                clause.pattern,
+                Pattern::Var { .. } | Pattern::Discard { .. }
+            ) && !matches!(clause_properties, ClauseProperties::ListClause { .. })
+            {
+                self.when_pattern(
+                    &clause.pattern,
+                    &mut clause_pattern_stack,
+                    clause_then_stack,
+                    subject_type,
+                    clause_properties,
+                );
+
+                ir_stack.wrap_clause(clause_pattern_stack);
+
+                continue;
+            }
+
             match clause_properties {
                 ClauseProperties::ConstrClause {
                     original_subject_name,
@@ -847,6 +1295,53 @@ impl<'a> CodeGenerator<'a> {
                         );
                     }
                 }
+                ClauseProperties::ListClause {
+                    original_subject_name,
+                    checks_elements: true,
+                    ..
+                } => {
+                    let subject_name = original_subject_name.clone();
+
+                    // Clauses stay in source order and each one checks the length
+                    // and elements of the whole list, so a clause that fails on an
+                    // element falls through to the very next one.
+                    let checked_pattern = match &clause.pattern {
+                        Pattern::Assign { pattern, .. } => pattern.as_ref(),
+                        pattern => pattern,
+                    };
+
+                    if let Pattern::List { .. } = checked_pattern {
+                        let mut checks_stack = ir_stack.empty_with_scope();
+
+                        let Some(item_name) = self.nested_pattern_ir_and_label(
+                            &clause.pattern,
+                            &mut checks_stack,
+                            subject_type,
+                            false,
+                        ) else {
+                            return;
+                        };
+
+                        let mut subject_stack = ir_stack.empty_with_scope();
+
+                        subject_stack.local_var(subject_type.clone(), subject_name);
+
+                        clause_pattern_stack.void();
+                        clause_pattern_stack.let_assignment(item_name, subject_stack);
+                        clause_pattern_stack.merge_child(checks_stack);
+                        clause_pattern_stack.merge_child(clause_then_stack);
+                    } else {
+                        self.when_pattern(
+                            &clause.pattern,
+                            &mut clause_pattern_stack,
+                            clause_then_stack,
+                            subject_type,
+                            clause_properties,
+                        );
+                    }
+
+                    ir_stack.wrap_clause(clause_pattern_stack);
+                }
                 ClauseProperties::ListClause {
                     original_subject_name,
                     current_index,
@@ -981,6 +1476,16 @@ impl<'a> CodeGenerator<'a> {
 
                 pattern_stack.merge_child(value_stack);
             }
+            Pattern::ByteArray { value, .. } => {
+                pattern_stack.byte_array(value.clone());
+
+                pattern_stack.merge_child(value_stack);
+            }
+            Pattern::String { value, .. } => {
+                pattern_stack.string(value);
+
+                pattern_stack.merge_child(value_stack);
+            }
             Pattern::Var { name, .. } => {
                 pattern_stack.void();
 
@@ -1050,8 +1555,9 @@ impl<'a> CodeGenerator<'a> {
                     }
 
                     // find data type definition
-                    let data_type =
-                        builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo).unwrap();
+                    let Some(data_type) = self.lookup_data_type(tipo) else {
+                        return;
+                    };
 
                     let (index, _) = data_type
                         .constructors
@@ -1130,9 +1636,13 @@ impl<'a> CodeGenerator<'a> {
         tipo: &Type,
     ) {
         match pattern {
-            Pattern::Int { .. } => unreachable!(),
+            Pattern::Int { .. } | Pattern::ByteArray { .. } | Pattern::String { .. } => {
+                unreachable!()
+            }
             Pattern::Var { .. } => unreachable!(),
-            Pattern::Assign { .. } => todo!("Nested assign not yet implemented"),
+            Pattern::Assign { location, .. } => {
+                self.unsupported(UnsupportedKind::NestedAssignPattern, *location)
+            }
             Pattern::Discard { .. } => {
                 pattern_stack.void();
 
@@ -1222,8 +1732,9 @@ impl<'a> CodeGenerator<'a> {
                 tipo,
                 ..
             } => {
-                let data_type =
-                    builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo).unwrap();
+                let Some(data_type) = self.lookup_data_type(tipo) else {
+                    return;
+                };
 
                 let (_, constructor_type) = data_type
                     .constructors
@@ -1349,7 +1860,12 @@ impl<'a> CodeGenerator<'a> {
                         *clause_properties.is_final_clause(),
                     );
 
-                    names.push((name.unwrap_or_else(|| "_".to_string()), index))
+                    // Discarded elements still get a name of their own, so that a
+                    // later clause aliasing them never shadows another element.
+                    let name = name
+                        .unwrap_or_else(|| format!("__tuple_index_{index}_{}", self.id_gen.next()));
+
+                    names.push((name, index))
                 }
                 let mut defined_indices = match clause_properties.clone() {
                     ClauseProperties::TupleClause {
@@ -1444,6 +1960,7 @@ impl<'a> CodeGenerator<'a> {
                             original_subject_name: item_name.clone(),
                             current_index: index as i64,
                             final_clause,
+                            checks_elements: false,
                         };
 
                         let tail_name = format!("{new_tail_name}_{index}");
@@ -1522,9 +2039,8 @@ impl<'a> CodeGenerator<'a> {
                 ..
             } => {
                 let id = self.id_gen.next();
-                let constr_var_name = format!("{constr_name}_{id}");
-                let data_type =
-                    builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo).unwrap();
+                let constr_var_name = format!("__{constr_name}_{id}");
+                let data_type = self.lookup_data_type(tipo)?;
 
                 let mut when_stack = pattern_stack.empty_with_scope();
 
@@ -1556,6 +2072,10 @@ impl<'a> CodeGenerator<'a> {
                             empty_stack,
                         );
                     }
+                } else {
+                    // only one constructor, so there is nothing to check and
+                    // we just need to expose the fields of the nested constr
+                    pattern_stack.merge(when_stack);
                 }
 
                 Some(constr_var_name)
@@ -1602,27 +2122,65 @@ impl<'a> CodeGenerator<'a> {
                 Some(item_name)
             }
             Pattern::Assign { name, pattern, .. } => {
+                // Only `None` when the inner pattern failed, which is already
+                // recorded as the error code generation stops with.
                 let inner_name = self.nested_pattern_ir_and_label(
                     pattern,
                     pattern_stack,
                     pattern_type,
                     final_clause,
-                );
+                )?;
 
                 let mut var_stack = pattern_stack.empty_with_scope();
-                var_stack.local_var(pattern_type.clone().into(), inner_name.clone().unwrap());
+                var_stack.local_var(pattern_type.clone().into(), inner_name.clone());
 
                 pattern_stack.let_assignment(name, var_stack);
 
-                inner_name
+                Some(inner_name)
             }
-            Pattern::Int { .. } => {
-                let error_message = "Nested pattern-match on integers isn't implemented yet. Use when clause-guard as an alternative, or break down the pattern.";
-                todo!("{}", error_message)
+            Pattern::Int { value, .. } => {
+                let mut checker_stack = pattern_stack.empty_with_scope();
+
+                checker_stack.integer(value.clone());
+
+                Some(self.nested_literal_guard(pattern_stack, checker_stack, int()))
+            }
+            Pattern::ByteArray { value, .. } => {
+                let mut checker_stack = pattern_stack.empty_with_scope();
+
+                checker_stack.byte_array(value.clone());
+
+                Some(self.nested_literal_guard(pattern_stack, checker_stack, byte_array()))
+            }
+            Pattern::String { value, .. } => {
+                let mut checker_stack = pattern_stack.empty_with_scope();
+
+                checker_stack.string(value);
+
+                Some(self.nested_literal_guard(pattern_stack, checker_stack, string()))
             }
         }
     }
 
+    /// Binds a literal nested in a pattern to a fresh name, and falls through
+    /// to the next clause unless it's equal to the checker. Unlike nested
+    /// constructors, this holds for the final clause too: a literal never makes
+    /// a `when` exhaustive, so that clause falls through to an error.
+    fn nested_literal_guard(
+        &mut self,
+        pattern_stack: &mut AirStack,
+        checker_stack: AirStack,
+        tipo: Arc<Type>,
+    ) -> String {
+        let item_name = format!("__literal_item_id_{}", self.id_gen.next());
+
+        let empty_stack = pattern_stack.empty_with_scope();
+
+        pattern_stack.clause_guard(item_name.clone(), tipo, checker_stack, empty_stack);
+
+        item_name
+    }
+
     fn assignment(
         &mut self,
         pattern: &Pattern<PatternConstructor, Arc<Type>>,
@@ -1652,7 +2210,15 @@ impl<'a> CodeGenerator<'a> {
         };
 
         match pattern {
-            Pattern::Int { .. } => todo!(),
+            Pattern::Int { location, .. } => {
+                self.unsupported(UnsupportedKind::IntPattern, *location)
+            }
+            Pattern::ByteArray { location, .. } => {
+                self.unsupported(UnsupportedKind::ByteArrayPattern, *location)
+            }
+            Pattern::String { location, .. } => {
+                self.unsupported(UnsupportedKind::StringPattern, *location)
+            }
             Pattern::Var { name, .. } => {
                 let expect_value_stack = value_stack.empty_with_scope();
                 pattern_stack.let_assignment(name, value_stack);
@@ -1674,7 +2240,9 @@ impl<'a> CodeGenerator<'a> {
                     pattern_stack.merge(expect_stack);
                 }
             }
-            Pattern::Assign { .. } => todo!("Assign not yet implemented"),
+            Pattern::Assign { location, .. } => {
+                self.unsupported(UnsupportedKind::AssignPattern, *location)
+            }
             Pattern::Discard { .. } => {
                 pattern_stack.let_assignment("_", value_stack);
             }
@@ -1756,10 +2324,24 @@ impl<'a> CodeGenerator<'a> {
         assignment_properties: AssignmentProperties,
     ) {
         match pattern {
-            Pattern::Int { .. } => todo!(),
-            Pattern::Var { .. } => todo!(),
-            Pattern::Assign { .. } => todo!(),
-            Pattern::Discard { .. } => todo!(),
+            Pattern::Int { location, .. } => {
+                self.unsupported(UnsupportedKind::IntPattern, *location)
+            }
+            Pattern::ByteArray { location, .. } => {
+                self.unsupported(UnsupportedKind::ByteArrayPattern, *location)
+            }
+            Pattern::String { location, .. } => {
+                self.unsupported(UnsupportedKind::StringPattern, *location)
+            }
+            Pattern::Var { name, .. } => {
+                pattern_stack.let_assignment(name, value_stack);
+            }
+            Pattern::Assign { location, .. } => {
+                self.unsupported(UnsupportedKind::AssignPattern, *location)
+            }
+            Pattern::Discard { .. } => {
+                pattern_stack.let_assignment("_", value_stack);
+            }
             Pattern::List { elements, tail, .. } => {
                 let inner_list_type = &tipo.get_inner_types()[0];
                 let mut elements_stack = pattern_stack.empty_with_scope();
@@ -1775,7 +2357,7 @@ impl<'a> CodeGenerator<'a> {
                         | Pattern::Tuple { .. }) => {
                             let mut var_stack = pattern_stack.empty_with_scope();
 
-                            let item_name = format!("list_item_id_{}", self.id_gen.next());
+                            let item_name = format!("__list_item_id_{}", self.id_gen.next());
 
                             names.push(item_name.clone());
 
@@ -1793,8 +2375,18 @@ impl<'a> CodeGenerator<'a> {
 
                             elements_stack.merge(element_stack);
                         }
-                        Pattern::Int { .. } => todo!(),
-                        Pattern::Assign { .. } => todo!(),
+                        Pattern::Int { location, .. } => {
+                            self.unsupported(UnsupportedKind::NestedIntPattern, *location)
+                        }
+                        Pattern::ByteArray { location, .. } => {
+                            self.unsupported(UnsupportedKind::NestedByteArrayPattern, *location)
+                        }
+                        Pattern::String { location, .. } => {
+                            self.unsupported(UnsupportedKind::NestedStringPattern, *location)
+                        }
+                        Pattern::Assign { location, .. } => {
+                            self.unsupported(UnsupportedKind::NestedAssignPattern, *location)
+                        }
                         Pattern::Discard { .. } => {
                             names.push("_".to_string());
                         }
@@ -1804,6 +2396,11 @@ impl<'a> CodeGenerator<'a> {
                 if let Some(tail) = tail {
                     match &**tail {
                         Pattern::Var { name, .. } => names.push(name.clone()),
+                        // the tail still needs a slot, or the last element
+                        // would be bound to the rest of the list instead
+                        Pattern::Discard { .. } if !elements.is_empty() => {
+                            names.push("_".to_string())
+                        }
                         Pattern::Discard { .. } => {}
                         _ => unreachable!(),
                     }
@@ -1828,6 +2425,7 @@ impl<'a> CodeGenerator<'a> {
                 constructor,
                 tipo: constr_tipo,
                 name: constructor_name,
+                location,
                 ..
             } => {
                 let mut stacks = pattern_stack.empty_with_scope();
@@ -1887,7 +2485,7 @@ impl<'a> CodeGenerator<'a> {
                         } else if tipo.is_void() {
                             expect_stack.choose_unit(value_stack);
                         } else if tipo.is_data() {
-                            unimplemented!("What are you doing with Data type?")
+                            self.unsupported(UnsupportedKind::DataConstructorPattern, *location)
                         } else {
                             let data_type =
                                 builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo)
@@ -2001,13 +2599,17 @@ impl<'a> CodeGenerator<'a> {
         assignment_properties: AssignmentProperties,
     ) {
         match pattern {
-            Pattern::Int { .. } => unreachable!(),
+            Pattern::Int { .. } | Pattern::ByteArray { .. } | Pattern::String { .. } => {
+                unreachable!()
+            }
             Pattern::Var { name, .. } => {
                 expect_stack.merge(value_stack);
 
                 self.expect_type(tipo, expect_stack, name, &mut IndexMap::new());
             }
-            Pattern::Assign { .. } => todo!(),
+            Pattern::Assign { location, .. } => {
+                self.unsupported(UnsupportedKind::AssignPattern, *location)
+            }
             Pattern::Discard { .. } => unreachable!(),
             Pattern::List { elements, tail, .. } => {
                 let inner_list_type = &tipo.get_inner_types()[0];
@@ -2020,11 +2622,13 @@ impl<'a> CodeGenerator<'a> {
                         Pattern::Var { name, .. } => {
                             names.push(name.clone());
                         }
-                        Pattern::Assign { .. } => todo!(),
+                        Pattern::Assign { location, .. } => {
+                            self.unsupported(UnsupportedKind::NestedAssignPattern, *location)
+                        }
                         element_pattern @ (Pattern::List { .. }
                         | Pattern::Constructor { .. }
                         | Pattern::Tuple { .. }) => {
-                            let name = format!("list_item_id_{}", self.id_gen.next());
+                            let name = format!("__list_item_id_{}", self.id_gen.next());
 
                             names.push(name.clone());
 
@@ -2084,8 +2688,9 @@ impl<'a> CodeGenerator<'a> {
                     PatternConstructor::Record { field_map, .. } => field_map.clone().unwrap(),
                 };
 
-                let data_type =
-                    builder::lookup_data_type_by_tipo(self.data_types.clone(), tipo).unwrap();
+                let Some(data_type) = self.lookup_data_type(tipo) else {
+                    return;
+                };
 
                 let (index, data_type_constr) = data_type
                     .constructors
@@ -2252,6 +2857,29 @@ impl<'a> CodeGenerator<'a> {
         expect_stack: &mut AirStack,
         name: &str,
         defined_data_types: &mut IndexMap<String, u64>,
+    ) {
+        if self.expect_depth >= self.expect_depth_limit {
+            self.fail(CodeGenError::ExpectTooDeep {
+                tipo: tipo.to_pretty(0),
+                limit: self.expect_depth_limit,
+            });
+
+            return;
+        }
+
+        self.expect_depth += 1;
+
+        self.expect_type_processor(tipo, expect_stack, name, defined_data_types);
+
+        self.expect_depth -= 1;
+    }
+
+    fn expect_type_processor(
+        &mut self,
+        tipo: &Type,
+        expect_stack: &mut AirStack,
+        name: &str,
+        defined_data_types: &mut IndexMap<String, u64>,
     ) {
         let mut tipo = tipo.clone().into();
         builder::replace_opaque_type(&mut tipo, self.data_types.clone());
@@ -2399,8 +3027,9 @@ impl<'a> CodeGenerator<'a> {
                 );
             }
         } else {
-            let data_type =
-                builder::lookup_data_type_by_tipo(self.data_types.clone(), &tipo).unwrap();
+            let Some(data_type) = self.lookup_data_type(&tipo) else {
+                return;
+            };
 
             let new_id = self.id_gen.next();
 
@@ -2514,11 +3143,11 @@ impl<'a> CodeGenerator<'a> {
                     );
                 }
 
-                trace_stack.trace(tipo.clone());
+                trace_stack.trace(tipo.clone(), Span::empty());
 
                 trace_stack.string("Constr index did not match any type variant");
 
-                trace_stack.error(tipo.clone());
+                trace_stack.error(tipo.clone(), Span::empty());
 
                 subject_stack.local_var(tipo.clone(), name);
 
@@ -2627,7 +3256,7 @@ impl<'a> CodeGenerator<'a> {
                 ..
             } => {
                 let id = self.id_gen.next();
-                let constr_name = format!("{constr_name}_{id}");
+                let constr_name = format!("__{constr_name}_{id}");
 
                 let mut local_var_stack = nested_pattern_stack.empty_with_scope();
 
@@ -2687,8 +3316,26 @@ impl<'a> CodeGenerator<'a> {
 
                 Some(tuple_name)
             }
-            Pattern::Int { .. } => todo!(),
-            Pattern::Assign { .. } => todo!(),
+            Pattern::Int { location, .. } => {
+                self.unsupported(UnsupportedKind::NestedIntPattern, *location);
+
+                None
+            }
+            Pattern::ByteArray { location, .. } => {
+                self.unsupported(UnsupportedKind::NestedByteArrayPattern, *location);
+
+                None
+            }
+            Pattern::String { location, .. } => {
+                self.unsupported(UnsupportedKind::NestedStringPattern, *location);
+
+                None
+            }
+            Pattern::Assign { location, .. } => {
+                self.unsupported(UnsupportedKind::NestedAssignPattern, *location);
+
+                None
+            }
         }
     }
 
@@ -2706,6 +3353,14 @@ impl<'a> CodeGenerator<'a> {
             false,
         );
 
+        let mut func_calls = IndexMap::new();
+
+        builder::count_function_calls(ir_stack, &mut func_calls);
+
+        for function_component in function_definitions.values() {
+            builder::count_function_calls(&function_component.ir, &mut func_calls);
+        }
+
         let mut final_func_dep_ir = IndexMap::new();
         let mut to_be_defined = IndexMap::new();
 
@@ -2870,14 +3525,49 @@ impl<'a> CodeGenerator<'a> {
                         .unwrap()
                         .clone();
 
-                    // zero arg functions are not recursive
-                    if !func_comp.args.is_empty() {
-                        let mut recursion_ir = vec![];
-                        builder::handle_recursion_ir(
-                            &function_access_key,
-                            &func_comp,
-                            &mut recursion_ir,
-                        );
+                    // zero arg functions are not recursive
+                    if !func_comp.args.is_empty() {
+                        let mut recursion_ir = vec![];
+                        builder::handle_recursion_ir(
+                            &function_access_key,
+                            &func_comp,
+                            &mut recursion_ir,
+                        );
+
+                        // When optimizing, a function called exactly once is inlined at
+                        // its call site instead of being defined and then applied.
+                        let call_index = if self.opt_level != OptLevel::None
+                            && !func_comp.recursive
+                            && !func_comp.is_code_gen_func
+                            && func_calls.get(&function_access_key) == Some(&1)
+                        {
+                            ir_stack[index..]
+                                .iter()
+                                .position(|ir| {
+                                    builder::function_access_key(ir).as_ref()
+                                        == Some(&function_access_key)
+                                })
+                                .map(|position| index + position)
+                        } else {
+                            None
+                        };
+
+                        if let Some(call_index) = call_index {
+                            ir_stack[call_index] = Air::Fn {
+                                scope: ir_stack[call_index].scope(),
+                                params: func_comp.args.clone(),
+                            };
+
+                            for ir in recursion_ir.into_iter().rev() {
+                                ir_stack.insert(call_index + 1, ir);
+                            }
+
+                            for ir in full_func_ir.into_iter().rev() {
+                                ir_stack.insert(index, ir);
+                            }
+
+                            continue;
+                        }
 
                         let recursion_stack = AirStack {
                             id_gen: self.id_gen.clone(),
@@ -3243,7 +3933,10 @@ impl<'a> CodeGenerator<'a> {
 
                 to_be_defined_map.insert(function_key, scope.clone());
             } else {
-                unreachable!("We found a function with no definitions");
+                self.fail(CodeGenError::MissingFunction {
+                    module: module.clone(),
+                    name: name.clone(),
+                });
             }
         }
 
@@ -3254,6 +3947,33 @@ impl<'a> CodeGenerator<'a> {
         }
     }
 
+    /// Replaces integer arithmetic and comparisons whose operands are both
+    /// literals with their result, e.g. `1 + 2` becomes `3`. Comparisons and
+    /// boolean operators over `True` and `False` fold the same way. The stack is
+    /// walked backwards so that nested operations fold from the inside out.
+    fn fold_constants(&mut self, ir_stack: &mut Vec<Air>) {
+        for index in (0..ir_stack.len()).rev() {
+            let folded = match &ir_stack[index..] {
+                [Air::BinOp { scope, name, .. }, Air::Int { value: left, .. }, Air::Int { value: right, .. }, ..] => {
+                    builder::fold_int_bin_op(scope.clone(), name, left, right)
+                }
+                [Air::BinOp { scope, name, .. }, left, right, ..] => {
+                    match (builder::bool_constant(left), builder::bool_constant(right)) {
+                        (Some(left), Some(right)) => {
+                            builder::fold_bool_bin_op(scope.clone(), name, left, right)
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+
+            if let Some(folded) = folded {
+                ir_stack.splice(index..index + 3, [folded]);
+            }
+        }
+    }
+
     fn convert_opaque_type_to_inner_ir(&mut self, ir_stack: &mut Vec<Air>) {
         let mut indices_to_remove = vec![];
         for (index, ir) in ir_stack.clone().into_iter().enumerate() {
@@ -3344,7 +4064,12 @@ impl<'a> CodeGenerator<'a> {
                         tipo: replaced_type,
                     };
                 }
-                Air::BinOp { tipo, scope, name } => {
+                Air::BinOp {
+                    tipo,
+                    scope,
+                    name,
+                    location,
+                } => {
                     let mut replaced_type = tipo.clone();
                     builder::replace_opaque_type(&mut replaced_type, self.data_types.clone());
 
@@ -3352,6 +4077,7 @@ impl<'a> CodeGenerator<'a> {
                         scope,
                         name,
                         tipo: replaced_type,
+                        location,
                     };
                 }
                 Air::When {
@@ -3480,21 +4206,31 @@ impl<'a> CodeGenerator<'a> {
                         tuple_index,
                     };
                 }
-                Air::ErrorTerm { tipo, scope } => {
+                Air::ErrorTerm {
+                    tipo,
+                    scope,
+                    location,
+                } => {
                     let mut replaced_type = tipo.clone();
                     builder::replace_opaque_type(&mut replaced_type, self.data_types.clone());
 
                     ir_stack[index] = Air::ErrorTerm {
                         scope,
+                        location,
                         tipo: replaced_type,
                     };
                 }
-                Air::Trace { tipo, scope } => {
+                Air::Trace {
+                    tipo,
+                    scope,
+                    location,
+                } => {
                     let mut replaced_type = tipo.clone();
                     builder::replace_opaque_type(&mut replaced_type, self.data_types.clone());
 
                     ir_stack[index] = Air::Trace {
                         scope,
+                        location,
                         tipo: replaced_type,
                     };
                 }
@@ -3682,19 +4418,57 @@ impl<'a> CodeGenerator<'a> {
         }
     }
 
+    /// Turns the IR into a term. Each IR node consumes the terms of its
+    /// children, so exactly one term must be left once they're all processed;
+    /// anything else is a bug in the lowering, reported as
+    /// [`CodeGenError::StackImbalance`].
     fn uplc_code_gen(&mut self, ir_stack: &mut Vec<Air>) -> Term<Name> {
         let mut arg_stack: Vec<Term<Name>> = vec![];
+        let mut last = "";
+
+        if let Some(annotations) = &mut self.annotations {
+            annotations.extend(
+                ir_stack
+                    .iter()
+                    .map(|ir| format!("-- {}{}", "  ".repeat(ir.scope().len()), ir.label())),
+            );
+        }
 
         while let Some(ir_element) = ir_stack.pop() {
+            if ir_stack.is_empty() {
+                last = ir_element.variant_name();
+            }
+
+            let location = ir_element.location();
+
             self.gen_uplc(ir_element, &mut arg_stack);
+
+            if let (Some(spans), Some(location)) = (&mut self.source_spans, location) {
+                if let Some(term) = arg_stack.pop() {
+                    arg_stack.push(builder::mark_source(term, spans.len()));
+
+                    spans.push(location);
+                }
+            }
+        }
+
+        match arg_stack.as_slice() {
+            [term] => term.clone(),
+            _ => {
+                self.fail(CodeGenError::StackImbalance {
+                    remaining: arg_stack.len(),
+                    last: last.to_string(),
+                });
+
+                Term::Error
+            }
         }
-        arg_stack[0].clone()
     }
 
     fn gen_uplc(&mut self, ir: Air, arg_stack: &mut Vec<Term<Name>>) {
         match ir {
             Air::Int { value, .. } => {
-                arg_stack.push(Term::integer(value.parse().unwrap()));
+                arg_stack.push(Term::integer(builder::int_literal_value(&value).unwrap()));
             }
             Air::String { value, .. } => {
                 arg_stack.push(Term::string(value));
@@ -3747,16 +4521,18 @@ impl<'a> CodeGenerator<'a> {
                     ValueConstructorVariant::Record {
                         name: constr_name, ..
                     } => {
+                        // `Bool` and `Void` are builtin constants, which don't need
+                        // their data type to be known.
                         if constructor.tipo.is_bool() {
                             arg_stack.push(Term::bool(constr_name == "True"));
                         } else if constructor.tipo.is_void() {
                             arg_stack.push(Term::Constant(UplcConstant::Unit.into()));
                         } else {
-                            let data_type = builder::lookup_data_type_by_tipo(
-                                self.data_types.clone(),
-                                &constructor.tipo,
-                            )
-                            .unwrap();
+                            let Some(data_type) = self.lookup_data_type(&constructor.tipo) else {
+                                arg_stack.push(Term::Error);
+
+                                return;
+                            };
 
                             let (constr_index, _) = data_type
                                 .constructors
@@ -3765,13 +4541,43 @@ impl<'a> CodeGenerator<'a> {
                                 .find(|(_, x)| x.name == *constr_name)
                                 .unwrap();
 
-                            let fields = Term::empty_list();
+                            if let Some((arg_types, _)) = constructor.tipo.function_types() {
+                                // constructor used as a function value, so we wrap
+                                // it in a lambda taking each of its fields
+                                let arg_names = arg_types
+                                    .iter()
+                                    .map(|_| format!("__constr_arg_{}", self.id_gen.next()))
+                                    .collect_vec();
+
+                                let mut fields = Term::empty_list();
+
+                                for (arg_name, arg_type) in arg_names.iter().zip(arg_types).rev() {
+                                    fields = Term::mk_cons()
+                                        .apply(builder::convert_type_to_data(
+                                            Term::var(arg_name),
+                                            &arg_type,
+                                        ))
+                                        .apply(fields);
+                                }
+
+                                let mut term = Term::constr_data()
+                                    .apply(Term::integer(constr_index.into()))
+                                    .apply(fields);
+
+                                for arg_name in arg_names.iter().rev() {
+                                    term = term.lambda(arg_name);
+                                }
+
+                                arg_stack.push(term);
+                            } else {
+                                let fields = Term::empty_list();
 
-                            let term = Term::constr_data()
-                                .apply(Term::integer(constr_index.try_into().unwrap()))
-                                .apply(fields);
+                                let term = Term::constr_data()
+                                    .apply(Term::integer(constr_index.into()))
+                                    .apply(fields);
 
-                            arg_stack.push(term);
+                                arg_stack.push(term);
+                            }
                         }
                     }
                 };
@@ -3948,6 +4754,9 @@ impl<'a> CodeGenerator<'a> {
                     }
                     arg_stack.push(term);
                 } else {
+                    // A zero-argument function has no lambda to apply: module
+                    // functions are evaluated once at compile-time and replaced
+                    // by their value, anything else already is the value.
                     let term = arg_stack.pop().unwrap();
 
                     let zero_arg_functions = self.zero_arg_functions.clone();
@@ -3975,22 +4784,16 @@ impl<'a> CodeGenerator<'a> {
                                     .constr_fields_exposer()
                                     .constr_index_exposer();
 
-                                let mut program: Program<Name> = Program {
+                                if self.needs_list_equality {
+                                    term = term.list_equals();
+                                }
+
+                                let program: Program<Name> = Program {
                                     version: (1, 0, 0),
                                     term,
                                 };
 
-                                let mut interner = Interner::new();
-
-                                interner.program(&mut program);
-
-                                let eval_program: Program<NamedDeBruijn> =
-                                    program.try_into().unwrap();
-
-                                let evaluated_term: Term<NamedDeBruijn> =
-                                    eval_program.eval(ExBudget::default()).result().unwrap();
-
-                                arg_stack.push(evaluated_term.try_into().unwrap());
+                                arg_stack.push(eval(&program).unwrap().0);
                                 anon_func = false;
                             }
                         }
@@ -4063,12 +4866,12 @@ impl<'a> CodeGenerator<'a> {
                             term = term.lambda(temp_var);
                         }
                     }
-                    DefaultFunction::MkCons => {
-                        unimplemented!("Use brackets instead.");
-                    }
-                    DefaultFunction::IfThenElse
+                    DefaultFunction::MkCons
+                    | DefaultFunction::IfThenElse
                     | DefaultFunction::ChooseList
-                    | DefaultFunction::Trace => unimplemented!("{func:#?}"),
+                    | DefaultFunction::Trace => {
+                        unreachable!("{func:?} is reported as unsupported while building the IR")
+                    }
                     DefaultFunction::ChooseData => {
                         let temp_vars = (0..func.arity())
                             .map(|_| format!("__item_{}", self.id_gen.next()))
@@ -4110,6 +4913,12 @@ impl<'a> CodeGenerator<'a> {
                     Term::equals_data()
                 };
 
+                let list_elem_equals = if self.element_wise_list_equality {
+                    builder::list_element_equality(&tipo)
+                } else {
+                    None
+                };
+
                 let term =
                     match name {
                         BinOp::And => left.delayed_if_else(right, Term::bool(false)),
@@ -4141,6 +4950,16 @@ impl<'a> CodeGenerator<'a> {
                                         Term::mk_cons().apply(right).apply(Term::empty_map()),
                                     ));
 
+                                arg_stack.push(term);
+                                return;
+                            } else if let Some(elem_equals) = list_elem_equals {
+                                self.needs_list_equality = true;
+
+                                let term = Term::var(LIST_EQUALS)
+                                    .apply(elem_equals)
+                                    .apply(left)
+                                    .apply(right);
+
                                 arg_stack.push(term);
                                 return;
                             } else if tipo.is_list() || tipo.is_tuple() {
@@ -4186,6 +5005,17 @@ impl<'a> CodeGenerator<'a> {
                                     ))
                                     .if_else(Term::bool(false), Term::bool(true));
 
+                                arg_stack.push(term);
+                                return;
+                            } else if let Some(elem_equals) = list_elem_equals {
+                                self.needs_list_equality = true;
+
+                                let term = Term::var(LIST_EQUALS)
+                                    .apply(elem_equals)
+                                    .apply(left)
+                                    .apply(right)
+                                    .if_else(Term::bool(false), Term::bool(true));
+
                                 arg_stack.push(term);
                                 return;
                             } else if tipo.is_list() || tipo.is_tuple() {
@@ -4231,6 +5061,14 @@ impl<'a> CodeGenerator<'a> {
                         BinOp::ModInt => Term::Builtin(DefaultFunction::ModInteger)
                             .apply(left)
                             .apply(right),
+                        BinOp::Concat if tipo.is_bytearray() => {
+                            Term::Builtin(DefaultFunction::AppendByteString)
+                                .apply(left)
+                                .apply(right)
+                        }
+                        BinOp::Concat => Term::Builtin(DefaultFunction::AppendString)
+                            .apply(left)
+                            .apply(right),
                     };
                 arg_stack.push(term);
             }
@@ -4329,6 +5167,8 @@ impl<'a> CodeGenerator<'a> {
             } => {
                 let subject = arg_stack.pop().unwrap();
 
+                // Only constructors of a data type are matched on their index;
+                // every other subject, tuples included, is bound as-is.
                 let subject = if tipo.is_int()
                     || tipo.is_bytearray()
                     || tipo.is_string()
@@ -4367,7 +5207,7 @@ impl<'a> CodeGenerator<'a> {
                     let other_clauses = if complex_clause {
                         Term::var("__other_clauses_delayed")
                     } else {
-                        term.clone()
+                        term.clone().delay()
                     };
 
                     if matches!(clause, Term::Constant(boolean) if matches!(boolean.as_ref(), UplcConstant::Bool(true)))
@@ -4383,6 +5223,8 @@ impl<'a> CodeGenerator<'a> {
 
                     if complex_clause {
                         term = body.lambda("__other_clauses_delayed").apply(term.delay());
+                    } else {
+                        term = body;
                     }
                 } else {
                     let condition = if tipo.is_int() {
@@ -4536,6 +5378,8 @@ impl<'a> CodeGenerator<'a> {
                 arg_stack.push(term);
             }
             Air::Finally { .. } => {
+                // the last clause matches whatever is left, so only the value
+                // to check against is dropped and its body stays on the stack
                 let _clause = arg_stack.pop().unwrap();
             }
             Air::If { .. } => {
@@ -4557,6 +5401,19 @@ impl<'a> CodeGenerator<'a> {
                 for _ in 0..count {
                     arg_vec.push(arg_stack.pop().unwrap());
                 }
+
+                // A tag past the last constructor would still build valid Data,
+                // so a mismatch with the data type is caught here instead.
+                if let Some(data_type) = self.lookup_data_type(&tipo) {
+                    if constr_index >= data_type.constructors.len() {
+                        self.fail(CodeGenError::ConstructorIndexOutOfRange {
+                            name: data_type.name.clone(),
+                            index: constr_index,
+                            count: data_type.constructors.len(),
+                        });
+                    }
+                }
+
                 let mut term = Term::empty_list();
 
                 for (index, arg) in arg_vec.iter().enumerate().rev() {
@@ -4573,20 +5430,12 @@ impl<'a> CodeGenerator<'a> {
                     .apply(term);
 
                 if arg_vec.iter().all(|item| matches!(item, Term::Constant(_))) {
-                    let mut program: Program<Name> = Program {
+                    let program: Program<Name> = Program {
                         version: (1, 0, 0),
                         term,
                     };
 
-                    let mut interner = Interner::new();
-
-                    interner.program(&mut program);
-
-                    let eval_program: Program<NamedDeBruijn> = program.try_into().unwrap();
-
-                    let evaluated_term: Term<NamedDeBruijn> =
-                        eval_program.eval(ExBudget::default()).result().unwrap();
-                    term = evaluated_term.try_into().unwrap();
+                    term = eval(&program).unwrap().0;
                 }
 
                 arg_stack.push(term);
@@ -4616,18 +5465,48 @@ impl<'a> CodeGenerator<'a> {
                 let mut term = arg_stack.pop().unwrap();
                 let list_id = self.id_gen.next();
 
+                // fields skipped by the pattern (e.g. with `..`) still need a slot
+                // so that each name lines up with its index in the constr fields
+                let fields_count = indices.iter().map(|item| item.0 + 1).max().unwrap_or(0);
+
+                let (names, inner_types): (Vec<_>, Vec<_>) = (0..fields_count)
+                    .map(|index| {
+                        indices
+                            .iter()
+                            .find(|item| item.0 == index)
+                            .map(|item| (item.1.clone(), item.2.clone()))
+                            .unwrap_or_else(|| ("_".to_string(), data()))
+                    })
+                    .unzip();
+
                 id_list.push(list_id);
 
-                for _ in 0..indices.len() {
+                for _ in 0..names.len() {
                     id_list.push(self.id_gen.next());
                 }
 
                 let current_index = 0;
 
-                let names = indices.iter().cloned().map(|item| item.1).collect_vec();
-                let inner_types = indices.iter().cloned().map(|item| item.2).collect_vec();
+                let bound_fields = indices
+                    .iter()
+                    .filter(|(_, name, _)| name != "_")
+                    .collect_vec();
+
+                term = if let ([(index, name, tipo)], false) = (&bound_fields[..], check_last_item)
+                {
+                    // a single field is reached directly, without binding each tail
+                    let mut fields = Term::var(CONSTR_FIELDS_EXPOSER).apply(value);
+
+                    for _ in 0..*index {
+                        fields = Term::tail_list().apply(fields);
+                    }
 
-                term = if !indices.is_empty() {
+                    term.lambda(name.clone())
+                        .apply(builder::convert_data_to_type(
+                            Term::head_list().apply(fields),
+                            tipo,
+                        ))
+                } else if !indices.is_empty() {
                     builder::list_access_to_uplc(
                         &names,
                         &id_list,
@@ -4638,12 +5517,11 @@ impl<'a> CodeGenerator<'a> {
                         check_last_item,
                         false,
                     )
+                    .apply(Term::var(CONSTR_FIELDS_EXPOSER).apply(value))
                 } else {
-                    term
+                    term.apply(Term::var(CONSTR_FIELDS_EXPOSER).apply(value))
                 };
 
-                term = term.apply(Term::var(CONSTR_FIELDS_EXPOSER).apply(value));
-
                 arg_stack.push(term);
             }
             Air::FieldsEmpty { .. } => {
@@ -4858,19 +5736,26 @@ impl<'a> CodeGenerator<'a> {
                 let list_id = self.id_gen.next();
 
                 if names.len() == 2 {
-                    term = term
-                        .lambda(names[1].clone())
-                        .apply(builder::convert_data_to_type(
-                            Term::snd_pair().apply(Term::var(format!("__tuple_{list_id}"))),
-                            &inner_types[1],
-                        ))
-                        .lambda(names[0].clone())
-                        .apply(builder::convert_data_to_type(
-                            Term::fst_pair().apply(Term::var(format!("__tuple_{list_id}"))),
-                            &inner_types[0],
-                        ))
-                        .lambda(format!("__tuple_{list_id}"))
-                        .apply(value);
+                    // Discarded elements are not extracted from the pair at all.
+                    if names[1] != "_" {
+                        term = term
+                            .lambda(names[1].clone())
+                            .apply(builder::convert_data_to_type(
+                                Term::snd_pair().apply(Term::var(format!("__tuple_{list_id}"))),
+                                &inner_types[1],
+                            ));
+                    }
+
+                    if names[0] != "_" {
+                        term = term
+                            .lambda(names[0].clone())
+                            .apply(builder::convert_data_to_type(
+                                Term::fst_pair().apply(Term::var(format!("__tuple_{list_id}"))),
+                                &inner_types[0],
+                            ));
+                    }
+
+                    term = term.lambda(format!("__tuple_{list_id}")).apply(value);
                 } else {
                     let mut id_list = vec![];
                     id_list.push(list_id);
@@ -5023,3 +5908,456 @@ fn process_scope_updates(
         }
     }
 }
+
+/// Number of nodes in the program's term, a rough measure of its size that
+/// doesn't depend on how it is serialised.
+pub fn term_size(program: &Program<Name>) -> usize {
+    fn go(term: &Term<Name>) -> usize {
+        1 + match term {
+            Term::Delay(term) | Term::Force(term) => go(term),
+            Term::Lambda { body, .. } => go(body),
+            Term::Apply { function, argument } => go(function) + go(argument),
+            Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) => 0,
+        }
+    }
+
+    go(&program.term)
+}
+
+/// Length in bytes of the program once serialised to CBOR, as it would be
+/// when deployed on-chain.
+pub fn serialized_size(program: &Program<Name>) -> usize {
+    let program: Program<DeBruijn> = program
+        .clone()
+        .try_into()
+        .expect("generated programs have no free variables");
+
+    program
+        .to_cbor()
+        .expect("generated programs can be serialised")
+        .len()
+}
+
+/// Evaluates the program with the default budget and cost model, giving back
+/// the term it reduces to along with the traces collected along the way. When
+/// evaluation fails, the traces come with the error instead.
+pub fn eval(program: &Program<Name>) -> Result<(Term<Name>, Vec<String>), EvalError> {
+    let mut program = program.clone();
+
+    Interner::new().program(&mut program);
+
+    let program: Program<NamedDeBruijn> =
+        program.try_into().map_err(|error| EvalError::Conversion {
+            reason: format!("{error}"),
+        })?;
+
+    let mut result = program.eval(ExBudget::default());
+
+    let logs = result.logs();
+
+    match result.result() {
+        Ok(term) => {
+            let term = term.try_into().map_err(|error| EvalError::Conversion {
+                reason: format!("{error}"),
+            })?;
+
+            Ok((term, logs))
+        }
+        Err(error) => Err(EvalError::Machine { error, logs }),
+    }
+}
+
+/// Where a subterm is in a term, as the index of the child to pick at each
+/// step from the root: `0` for the body of a lambda, delay or force, and `0`
+/// or `1` for the function or argument of an application.
+pub type TermPath = Vec<usize>;
+
+/// Each term carrying a source location, along with that location.
+pub type SourceMap = Vec<(TermPath, Span)>;
+
+/// The subterm at the given path, if there's one.
+pub fn term_at<'t>(term: &'t Term<Name>, path: &[usize]) -> Option<&'t Term<Name>> {
+    match (path, term) {
+        ([], _) => Some(term),
+        ([0, rest @ ..], Term::Lambda { body: term, .. })
+        | ([0, rest @ ..], Term::Delay(term) | Term::Force(term))
+        | ([0, rest @ ..], Term::Apply { function: term, .. })
+        | ([1, rest @ ..], Term::Apply { argument: term, .. }) => term_at(term, rest),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use indexmap::IndexMap;
+
+    use crate::{
+        ast::{BinOp, CallArg, Span},
+        builtins::{self, bool, function, int, option, string, unbound_var},
+        expr::TypedExpr,
+        tipo::{Type, TypeVar, ValueConstructor, ValueConstructorVariant},
+        IdGenerator,
+    };
+
+    use super::{
+        air::Air,
+        builder::{self, DataTypeKey},
+        eval,
+        scope::Scope,
+        serialized_size,
+        stack::AirStack,
+        term_size, CodeGenError, CodeGenerator, EvalError,
+    };
+    use uplc::ast::{Program, Term};
+
+    #[test]
+    fn sizes_grow_with_the_program() {
+        let trivial = Program {
+            version: (1, 0, 0),
+            term: Term::bool(true),
+        };
+
+        let larger = Program {
+            version: (1, 0, 0),
+            term: Term::add_integer()
+                .apply(Term::integer(1.into()))
+                .apply(Term::integer(2.into()))
+                .lambda("x")
+                .apply(Term::unit()),
+        };
+
+        assert_eq!(term_size(&trivial), 1);
+        assert_eq!(term_size(&larger), 8);
+        assert!(serialized_size(&trivial) < serialized_size(&larger));
+    }
+
+    #[test]
+    fn eval_reduces_programs() {
+        let program = Program {
+            version: (1, 0, 0),
+            term: Term::add_integer()
+                .apply(Term::integer(1.into()))
+                .apply(Term::integer(1.into())),
+        };
+
+        assert_eq!(eval(&program).unwrap(), (Term::integer(2.into()), vec![]));
+
+        let traced = Program {
+            version: (1, 0, 0),
+            term: Term::integer(2.into()).trace(Term::string("done")),
+        };
+
+        assert_eq!(
+            eval(&traced).unwrap(),
+            (Term::integer(2.into()), vec!["done".to_string()])
+        );
+
+        let failing = Program {
+            version: (1, 0, 0),
+            term: Term::Error.trace(Term::string("boom")),
+        };
+
+        match eval(&failing) {
+            Err(EvalError::Machine { logs, .. }) => assert_eq!(logs, vec!["boom".to_string()]),
+            result => panic!("expected a machine error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn unbalanced_ir_stack() {
+        let mut generator = CodeGenerator::new(IndexMap::new(), IndexMap::new(), IndexMap::new());
+
+        let mut ir_stack = vec![
+            Air::Int {
+                scope: Scope::default(),
+                value: "1".to_string(),
+            },
+            Air::Int {
+                scope: Scope::default(),
+                value: "2".to_string(),
+            },
+        ];
+
+        generator.uplc_code_gen(&mut ir_stack);
+
+        assert_eq!(
+            generator.check_error(),
+            Err(CodeGenError::StackImbalance {
+                remaining: 2,
+                last: "Int".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn record_tag_within_data_type() {
+        let data_types = builtins::prelude_data_types(&IdGenerator::new());
+
+        let mut generator = CodeGenerator::new(
+            IndexMap::new(),
+            data_types
+                .iter()
+                .map(|(key, data_type)| (key.clone(), data_type))
+                .collect(),
+            IndexMap::new(),
+        );
+
+        // `Some(1)`, then the same record with a tag `Option` doesn't have.
+        for (tag, expected) in [
+            (0, Ok(())),
+            (
+                2,
+                Err(CodeGenError::ConstructorIndexOutOfRange {
+                    name: "Option".to_string(),
+                    index: 2,
+                    count: 2,
+                }),
+            ),
+        ] {
+            let mut ir_stack = vec![
+                Air::Record {
+                    scope: Scope::default(),
+                    tag,
+                    tipo: function(vec![int()], option(int())),
+                    count: 1,
+                },
+                int_literal("1"),
+            ];
+
+            generator.uplc_code_gen(&mut ir_stack);
+
+            assert_eq!(generator.check_error(), expected);
+        }
+    }
+
+    #[test]
+    fn record_call_with_unknown_constructor() {
+        let data_types = builtins::prelude_data_types(&IdGenerator::new());
+
+        let mut generator = CodeGenerator::new(
+            IndexMap::new(),
+            data_types
+                .iter()
+                .map(|(key, data_type)| (key.clone(), data_type))
+                .collect(),
+            IndexMap::new(),
+        );
+
+        let tipo = function(vec![int()], option(int()));
+
+        // `Nope(1)`, a record call to a constructor `Option` doesn't have.
+        let expr = TypedExpr::Call {
+            location: Span::empty(),
+            tipo: option(int()),
+            fun: TypedExpr::Var {
+                location: Span::empty(),
+                constructor: ValueConstructor::public(
+                    tipo,
+                    ValueConstructorVariant::Record {
+                        name: "Nope".to_string(),
+                        arity: 1,
+                        field_map: None,
+                        location: Span::empty(),
+                        module: String::new(),
+                        constructors_count: 2,
+                    },
+                ),
+                name: "Nope".to_string(),
+            }
+            .into(),
+            args: vec![CallArg {
+                label: None,
+                location: Span::empty(),
+                value: TypedExpr::Int {
+                    location: Span::empty(),
+                    tipo: int(),
+                    value: "1".to_string(),
+                },
+            }],
+        };
+
+        let mut ir_stack = AirStack::new(generator.id_gen.clone());
+
+        generator.build(&expr, &mut ir_stack);
+
+        assert_eq!(
+            generator.check_error(),
+            Err(CodeGenError::MissingConstructor {
+                name: "Nope".to_string(),
+                data_type: "Option".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn trace_and_error_keep_their_location() {
+        let mut generator = CodeGenerator::new(IndexMap::new(), IndexMap::new(), IndexMap::new());
+
+        let trace_location = Span { start: 4, end: 32 };
+        let error_location = Span { start: 18, end: 32 };
+
+        let expr = TypedExpr::Trace {
+            location: trace_location,
+            tipo: int(),
+            then: TypedExpr::ErrorTerm {
+                location: error_location,
+                tipo: int(),
+            }
+            .into(),
+            text: TypedExpr::String {
+                location: Span { start: 10, end: 16 },
+                tipo: string(),
+                value: "oops".to_string(),
+            }
+            .into(),
+        };
+
+        let mut ir_stack = AirStack::new(generator.id_gen.clone());
+
+        generator.build(&expr, &mut ir_stack);
+
+        let locations = ir_stack
+            .complete()
+            .iter()
+            .filter_map(Air::location)
+            .collect::<Vec<_>>();
+
+        assert_eq!(locations, vec![trace_location, error_location]);
+    }
+
+    fn bin_op(name: BinOp) -> Air {
+        Air::BinOp {
+            scope: Scope::default(),
+            name,
+            tipo: int(),
+            location: Span::empty(),
+        }
+    }
+
+    fn int_literal(value: &str) -> Air {
+        Air::Int {
+            scope: Scope::default(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn fold_nested_arithmetic() {
+        let mut generator = CodeGenerator::new(IndexMap::new(), IndexMap::new(), IndexMap::new());
+
+        // (2 * 3) + (10 - 14)
+        let mut ir_stack = vec![
+            bin_op(BinOp::AddInt),
+            bin_op(BinOp::MultInt),
+            int_literal("2"),
+            int_literal("3"),
+            bin_op(BinOp::SubInt),
+            int_literal("10"),
+            int_literal("14"),
+        ];
+
+        generator.fold_constants(&mut ir_stack);
+
+        assert_eq!(ir_stack, vec![int_literal("2")]);
+    }
+
+    #[test]
+    fn fold_comparisons() {
+        let mut generator = CodeGenerator::new(IndexMap::new(), IndexMap::new(), IndexMap::new());
+
+        for (name, expected) in [
+            (BinOp::LtInt, true),
+            (BinOp::LtEqInt, true),
+            (BinOp::GtInt, false),
+            (BinOp::GtEqInt, false),
+            (BinOp::Eq, false),
+            (BinOp::NotEq, true),
+        ] {
+            let mut ir_stack = vec![
+                Air::BinOp {
+                    scope: Scope::default(),
+                    name,
+                    tipo: bool(),
+                    location: Span::empty(),
+                },
+                int_literal("1"),
+                int_literal("2"),
+            ];
+
+            generator.fold_constants(&mut ir_stack);
+
+            assert_eq!(
+                ir_stack,
+                vec![Air::Bool {
+                    scope: Scope::default(),
+                    value: expected,
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn fold_leaves_non_literal_operands() {
+        let mut generator = CodeGenerator::new(IndexMap::new(), IndexMap::new(), IndexMap::new());
+
+        let x = Air::Var {
+            scope: Scope::default(),
+            constructor: ValueConstructor::public(
+                int(),
+                ValueConstructorVariant::LocalVariable {
+                    location: Span::empty(),
+                },
+            ),
+            name: "x".to_string(),
+            variant_name: String::new(),
+        };
+
+        // x + (1 + 2)
+        let mut ir_stack = vec![
+            bin_op(BinOp::AddInt),
+            x.clone(),
+            bin_op(BinOp::AddInt),
+            int_literal("1"),
+            int_literal("2"),
+        ];
+
+        generator.fold_constants(&mut ir_stack);
+
+        assert_eq!(ir_stack, vec![bin_op(BinOp::AddInt), x, int_literal("3")]);
+
+        // Division by zero must still fail at runtime.
+        let mut ir_stack = vec![bin_op(BinOp::DivInt), int_literal("7"), int_literal("0")];
+
+        generator.fold_constants(&mut ir_stack);
+
+        assert_eq!(
+            ir_stack,
+            vec![bin_op(BinOp::DivInt), int_literal("7"), int_literal("0")]
+        );
+    }
+
+    #[test]
+    fn resolve_data_type_key_through_type_variables() {
+        // What a generic `fn(a) -> b` instantiated to return an `Option<Int>`
+        // looks like once `b` has been unified.
+        let ret = unbound_var(0);
+
+        if let Type::Var { tipo } = ret.as_ref() {
+            *tipo.borrow_mut() = TypeVar::Link {
+                tipo: option(int()),
+            };
+        }
+
+        let expected = Some(DataTypeKey {
+            module_name: String::new(),
+            defined_type: "Option".to_string(),
+        });
+
+        assert_eq!(builder::resolve_data_type_key(&ret), expected);
+        assert_eq!(
+            builder::resolve_data_type_key(&function(vec![int()], ret)),
+            expected
+        );
+        assert_eq!(builder::resolve_data_type_key(&unbound_var(1)), None);
+    }
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use uplc::builtins::DefaultFunction;
 
 use crate::{
-    ast::{BinOp, UnOp},
+    ast::{BinOp, Span, UnOp},
     tipo::{Type, ValueConstructor},
 };
 
@@ -77,6 +77,7 @@ pub enum Air {
         scope: Scope,
         name: BinOp,
         tipo: Arc<Type>,
+        location: Span,
     },
     UnOp {
         scope: Scope,
@@ -207,10 +208,12 @@ pub enum Air {
     // Misc.
     ErrorTerm {
         scope: Scope,
+        location: Span,
         tipo: Arc<Type>,
     },
     Trace {
         scope: Scope,
+        location: Span,
         tipo: Arc<Type>,
     },
     NoOp {
@@ -222,6 +225,88 @@ pub enum Air {
 }
 
 impl Air {
+    /// The name of the variant, e.g. `Call` for `Air::Call { .. }`.
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Air::Int { .. } => "Int",
+            Air::String { .. } => "String",
+            Air::ByteArray { .. } => "ByteArray",
+            Air::Bool { .. } => "Bool",
+            Air::List { .. } => "List",
+            Air::Tuple { .. } => "Tuple",
+            Air::Void { .. } => "Void",
+            Air::Var { .. } => "Var",
+            Air::Call { .. } => "Call",
+            Air::DefineFunc { .. } => "DefineFunc",
+            Air::Fn { .. } => "Fn",
+            Air::Builtin { .. } => "Builtin",
+            Air::BinOp { .. } => "BinOp",
+            Air::UnOp { .. } => "UnOp",
+            Air::Let { .. } => "Let",
+            Air::UnWrapData { .. } => "UnWrapData",
+            Air::WrapData { .. } => "WrapData",
+            Air::AssertConstr { .. } => "AssertConstr",
+            Air::AssertBool { .. } => "AssertBool",
+            Air::When { .. } => "When",
+            Air::Clause { .. } => "Clause",
+            Air::ListClause { .. } => "ListClause",
+            Air::WrapClause { .. } => "WrapClause",
+            Air::TupleClause { .. } => "TupleClause",
+            Air::ClauseGuard { .. } => "ClauseGuard",
+            Air::ListClauseGuard { .. } => "ListClauseGuard",
+            Air::Finally { .. } => "Finally",
+            Air::If { .. } => "If",
+            Air::Record { .. } => "Record",
+            Air::RecordUpdate { .. } => "RecordUpdate",
+            Air::RecordAccess { .. } => "RecordAccess",
+            Air::FieldsExpose { .. } => "FieldsExpose",
+            Air::ListAccessor { .. } => "ListAccessor",
+            Air::ListExpose { .. } => "ListExpose",
+            Air::TupleAccessor { .. } => "TupleAccessor",
+            Air::TupleIndex { .. } => "TupleIndex",
+            Air::ErrorTerm { .. } => "ErrorTerm",
+            Air::Trace { .. } => "Trace",
+            Air::NoOp { .. } => "NoOp",
+            Air::FieldsEmpty { .. } => "FieldsEmpty",
+        }
+    }
+
+    /// A one-line description of the node, e.g. `Var double` or `Let x`, used
+    /// to annotate generated programs.
+    pub fn label(&self) -> String {
+        let detail = match self {
+            Air::Int { value, .. } => value.clone(),
+            Air::String { value, .. } => format!("@{value:?}"),
+            Air::ByteArray { bytes, .. } => format!("#\"{}\"", hex::encode(bytes)),
+            Air::Bool { value, .. } => value.to_string(),
+            Air::Var {
+                name, variant_name, ..
+            } => format!("{name}{variant_name}"),
+            Air::DefineFunc {
+                func_name,
+                module_name,
+                variant_name,
+                ..
+            } => format!("{module_name}.{func_name}{variant_name}"),
+            Air::Fn { params, .. } => params.join(", "),
+            Air::Builtin { func, .. } => func.to_string(),
+            Air::BinOp { name, .. } => format!("{name:?}"),
+            Air::Let { name, .. } => name.clone(),
+            Air::When { subject_name, .. }
+            | Air::Clause { subject_name, .. }
+            | Air::TupleClause { subject_name, .. }
+            | Air::ClauseGuard { subject_name, .. } => subject_name.clone(),
+            Air::ListClause { tail_name, .. } | Air::ListClauseGuard { tail_name, .. } => {
+                tail_name.clone()
+            }
+            _ => String::new(),
+        };
+
+        format!("{} {detail}", self.variant_name())
+            .trim_end()
+            .to_string()
+    }
+
     pub fn scope(&self) -> Scope {
         match self {
             Air::Int { scope, .. }
@@ -310,6 +395,18 @@ impl Air {
             | Air::NoOp { scope, .. } => scope,
         }
     }
+
+    /// Source location of the expression this node originates from, when
+    /// known. Nodes synthesized by the code generator have an empty span.
+    pub fn location(&self) -> Option<Span> {
+        match self {
+            Air::ErrorTerm { location, .. }
+            | Air::Trace { location, .. }
+            | Air::BinOp { location, .. } => Some(*location),
+            _ => None,
+        }
+    }
+
     pub fn tipo(&self) -> Option<Arc<Type>> {
         match self {
             Air::Int { .. } => Some(
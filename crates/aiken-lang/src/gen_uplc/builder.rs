@@ -2,6 +2,7 @@ use std::{rc::Rc, sync::Arc};
 
 use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
+use num_bigint::BigInt;
 use uplc::{
     ast::{Constant as UplcConstant, Name, Term, Type as UplcType},
     builder::{CONSTR_FIELDS_EXPOSER, CONSTR_INDEX_EXPOSER},
@@ -15,11 +16,11 @@ use uplc::{
 
 use crate::{
     ast::{
-        AssignmentKind, BinOp, ClauseGuard, Constant, DataType, Pattern, Span, TypedArg,
+        AssignmentKind, BinOp, ClauseGuard, Constant, DataType, IfBranch, Pattern, Span, TypedArg,
         TypedClause, TypedDataType, UnOp,
     },
     expr::TypedExpr,
-    tipo::{PatternConstructor, Type, TypeVar, ValueConstructorVariant},
+    tipo::{PatternConstructor, Type, TypeVar, ValueConstructor, ValueConstructorVariant},
     IdGenerator,
 };
 
@@ -78,6 +79,7 @@ pub enum ClauseProperties {
         original_subject_name: String,
         current_index: i64,
         final_clause: bool,
+        checks_elements: bool,
     },
     TupleClause {
         clause_var_name: String,
@@ -99,6 +101,7 @@ impl ClauseProperties {
                 original_subject_name: subject_name,
                 current_index: -1,
                 final_clause: false,
+                checks_elements: false,
             }
         } else if t.is_tuple() {
             ClauseProperties::TupleClause {
@@ -277,7 +280,247 @@ pub fn convert_data_to_type(term: Term<Name>, field_type: &Arc<Type>) -> Term<Na
     }
 }
 
+/// The value of an integer literal as spelled in the source: possibly with a
+/// `0x`, `0o` or `0b` radix prefix and with underscores between its digits.
+pub fn int_literal_value(literal: &str) -> Option<BigInt> {
+    let digits = literal.replace('_', "");
+
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, digits.as_str()),
+    };
+
+    BigInt::parse_bytes(digits.as_bytes(), radix)
+}
+
+/// The result of an integer operation on two literals, when it can be
+/// computed at compile-time. Division and modulo are left alone so that a
+/// division by zero still fails at runtime.
+pub fn fold_int_bin_op(scope: Scope, name: &BinOp, left: &str, right: &str) -> Option<Air> {
+    let left = int_literal_value(left)?;
+    let right = int_literal_value(right)?;
+
+    let to_int = |value: BigInt| Air::Int {
+        scope: scope.clone(),
+        value: value.to_string(),
+    };
+
+    let to_bool = |value: bool| Air::Bool {
+        scope: scope.clone(),
+        value,
+    };
+
+    match name {
+        BinOp::AddInt => Some(to_int(left + right)),
+        BinOp::SubInt => Some(to_int(left - right)),
+        BinOp::MultInt => Some(to_int(left * right)),
+        BinOp::Eq => Some(to_bool(left == right)),
+        BinOp::NotEq => Some(to_bool(left != right)),
+        BinOp::LtInt => Some(to_bool(left < right)),
+        BinOp::LtEqInt => Some(to_bool(left <= right)),
+        BinOp::GtInt => Some(to_bool(left > right)),
+        BinOp::GtEqInt => Some(to_bool(left >= right)),
+        BinOp::And | BinOp::Or | BinOp::DivInt | BinOp::ModInt | BinOp::Concat => None,
+    }
+}
+
+/// The value of a `True` or `False` literal, whether it has already been
+/// folded into an `Air::Bool` or is still a reference to the constructor.
+pub fn bool_constant(ir: &Air) -> Option<bool> {
+    match ir {
+        Air::Bool { value, .. } => Some(*value),
+        Air::Var {
+            constructor:
+                ValueConstructor {
+                    variant: ValueConstructorVariant::Record { name, .. },
+                    tipo,
+                    ..
+                },
+            ..
+        } if tipo.is_bool() => Some(name == "True"),
+        _ => None,
+    }
+}
+
+pub fn fold_bool_bin_op(scope: Scope, name: &BinOp, left: bool, right: bool) -> Option<Air> {
+    let value = match name {
+        BinOp::Eq => left == right,
+        BinOp::NotEq => left != right,
+        BinOp::And => left && right,
+        BinOp::Or => left || right,
+        _ => return None,
+    };
+
+    Some(Air::Bool { scope, value })
+}
+
+/// An if-chain whose conditions all compare the same local `Int` variable
+/// against an integer literal, rewritten as the equivalent `when` over that
+/// variable so that it goes through the integer clauses instead.
+pub fn if_chain_as_when(
+    location: Span,
+    branches: &[IfBranch<TypedExpr>],
+    final_else: &TypedExpr,
+    tipo: &Arc<Type>,
+) -> Option<TypedExpr> {
+    let mut subject: Option<&TypedExpr> = None;
+    let mut clauses = vec![];
+
+    for branch in branches {
+        let TypedExpr::BinOp {
+            name: BinOp::Eq,
+            left,
+            right,
+            ..
+        } = &branch.condition
+        else {
+            return None;
+        };
+
+        let (var, value) = match (left.as_ref(), right.as_ref()) {
+            (var @ TypedExpr::Var { .. }, TypedExpr::Int { value, .. })
+            | (TypedExpr::Int { value, .. }, var @ TypedExpr::Var { .. }) => (var, value),
+            _ => return None,
+        };
+
+        let TypedExpr::Var {
+            constructor, name, ..
+        } = var
+        else {
+            return None;
+        };
+
+        if !matches!(
+            constructor.variant,
+            ValueConstructorVariant::LocalVariable { .. }
+        ) || !constructor.tipo.is_int()
+        {
+            return None;
+        }
+
+        match subject {
+            Some(TypedExpr::Var {
+                name: subject_name, ..
+            }) if subject_name != name => return None,
+            _ => subject = Some(var),
+        }
+
+        clauses.push(TypedClause {
+            location: branch.location,
+            pattern: Pattern::Int {
+                location: branch.location,
+                value: value.clone(),
+            },
+            guard: None,
+            then: branch.body.clone(),
+        });
+    }
+
+    clauses.push(TypedClause {
+        location: final_else.location(),
+        pattern: Pattern::Discard {
+            name: "_".to_string(),
+            location: final_else.location(),
+        },
+        guard: None,
+        then: final_else.clone(),
+    });
+
+    Some(TypedExpr::When {
+        location,
+        tipo: tipo.clone(),
+        subject: subject?.clone().into(),
+        clauses,
+    })
+}
+
+/// Element equality for lists whose elements have a dedicated equality
+/// builtin. When enabled with `CodeGenerator::set_element_wise_list_equality`,
+/// such lists are compared through `__list_equals` rather than by converting
+/// both sides to data.
+pub fn list_element_equality(tipo: &Arc<Type>) -> Option<Term<Name>> {
+    if !tipo.is_list() {
+        return None;
+    }
+
+    let elem_type = tipo.get_inner_types().into_iter().next()?;
+
+    let builtin = if elem_type.is_int() {
+        Term::equals_integer()
+    } else if elem_type.is_bytearray() {
+        Term::equals_bytestring()
+    } else if elem_type.is_string() {
+        Term::equals_string()
+    } else {
+        return None;
+    };
+
+    Some(
+        builtin
+            .apply(convert_data_to_type(Term::var("__left_elem"), &elem_type))
+            .apply(convert_data_to_type(Term::var("__right_elem"), &elem_type))
+            .lambda("__right_elem")
+            .lambda("__left_elem"),
+    )
+}
+
+/// Whether a clause of a `when` on a list checks more than the length of the
+/// list, through a nested pattern or a guard. Such clauses can't be sorted by
+/// length, so each one checks the whole list on its own instead.
+pub fn list_clauses_check_elements(clauses: &[TypedClause]) -> bool {
+    fn checks_elements(pattern: &Pattern<PatternConstructor, Arc<Type>>) -> bool {
+        match pattern {
+            Pattern::Assign { pattern, .. } => checks_elements(pattern),
+            Pattern::List { elements, .. } => elements
+                .iter()
+                .any(|element| !matches!(element, Pattern::Var { .. } | Pattern::Discard { .. })),
+            _ => false,
+        }
+    }
+
+    clauses
+        .iter()
+        .any(|clause| clause.guard.is_some() || checks_elements(&clause.pattern))
+}
+
+/// Whether a pattern checks a literal below its top level, such as the `0`
+/// in `(0, _)` or `Some(0)`.
+pub fn has_nested_literal(pattern: &Pattern<PatternConstructor, Arc<Type>>) -> bool {
+    fn is_or_has_literal(pattern: &Pattern<PatternConstructor, Arc<Type>>) -> bool {
+        match pattern {
+            Pattern::Int { .. } | Pattern::ByteArray { .. } | Pattern::String { .. } => true,
+            pattern => has_nested_literal(pattern),
+        }
+    }
+
+    match pattern {
+        Pattern::Assign { pattern, .. } => has_nested_literal(pattern),
+        Pattern::List { elements, tail, .. } => elements
+            .iter()
+            .chain(tail.as_deref())
+            .any(is_or_has_literal),
+        Pattern::Tuple { elems, .. } => elems.iter().any(is_or_has_literal),
+        Pattern::Constructor { arguments, .. } => arguments
+            .iter()
+            .any(|argument| is_or_has_literal(&argument.value)),
+        _ => false,
+    }
+}
+
 pub fn rearrange_clauses(clauses: Vec<TypedClause>) -> Vec<TypedClause> {
+    // A list pattern bound with `as` still refers to the whole subject, so it is
+    // sorted and checked by the list it wraps.
+    fn list_pattern(
+        pattern: &Pattern<PatternConstructor, Arc<Type>>,
+    ) -> &Pattern<PatternConstructor, Arc<Type>> {
+        match pattern {
+            Pattern::Assign { pattern, .. } => list_pattern(pattern),
+            pattern => pattern,
+        }
+    }
+
     let mut sorted_clauses = clauses;
 
     // if we have a list sort clauses so we can plug holes for cases not covered by clauses
@@ -285,11 +528,11 @@ pub fn rearrange_clauses(clauses: Vec<TypedClause>) -> Vec<TypedClause> {
     // let's sort clauses by a safer manner
     // TODO: how shall tails be weighted? Since any clause after will not run
     sorted_clauses.sort_by(|clause1, clause2| {
-        let clause1_len = match &clause1.pattern {
+        let clause1_len = match list_pattern(&clause1.pattern) {
             Pattern::List { elements, tail, .. } => elements.len() + usize::from(tail.is_some()),
             _ => 10000000,
         };
-        let clause2_len = match &clause2.pattern {
+        let clause2_len = match list_pattern(&clause2.pattern) {
             Pattern::List { elements, tail, .. } => elements.len() + usize::from(tail.is_some()),
             _ => 10000001,
         };
@@ -331,7 +574,7 @@ pub fn rearrange_clauses(clauses: Vec<TypedClause>) -> Vec<TypedClause> {
     };
 
     for (index, clause) in sorted_clauses.iter().enumerate() {
-        if let Pattern::List { elements, .. } = &clause.pattern {
+        if let Pattern::List { elements, .. } = list_pattern(&clause.pattern) {
             // found a hole and now we plug it
             while elems_len < elements.len() {
                 let mut discard_elems = vec![];
@@ -379,7 +622,7 @@ pub fn rearrange_clauses(clauses: Vec<TypedClause>) -> Vec<TypedClause> {
         }
 
         // if we have a pattern with no clause guards and a tail then no lists will get past here to other clauses
-        match &clause.pattern {
+        match list_pattern(&clause.pattern) {
             Pattern::Var { .. } => {
                 last_clause_index = index + 1;
                 last_clause_set = true;
@@ -410,7 +653,7 @@ pub fn rearrange_clauses(clauses: Vec<TypedClause>) -> Vec<TypedClause> {
 
         // If the last condition doesn't have a catch all or tail then add a catch all with a todo
         if index == sorted_clauses.len() - 1 {
-            if let Pattern::List { tail: None, .. } = &clause.pattern {
+            if let Pattern::List { tail: None, .. } = list_pattern(&clause.pattern) {
                 final_clauses.push(TypedClause {
                     location: Span::empty(),
                     pattern: Pattern::Discard {
@@ -456,13 +699,13 @@ pub fn list_access_to_uplc(
         let head_list =
             if matches!(current_tipo.get_uplc_type(), UplcType::Pair(_, _)) && is_list_accessor {
                 Term::head_list().apply(Term::var(format!(
-                    "tail_index_{}_{}",
+                    "__tail_index_{}_{}",
                     current_index, id_list[current_index]
                 )))
             } else {
                 convert_data_to_type(
                     Term::head_list().apply(Term::var(format!(
-                        "tail_index_{}_{}",
+                        "__tail_index_{}_{}",
                         current_index, id_list[current_index]
                     ))),
                     &current_tipo.to_owned(),
@@ -475,28 +718,28 @@ pub fn list_access_to_uplc(
             } else if first == "_" {
                 term.lambda(names[0].clone())
                     .apply(Term::tail_list().apply(Term::var(format!(
-                        "tail_index_{}_{}",
+                        "__tail_index_{}_{}",
                         current_index, id_list[current_index]
                     ))))
                     .lambda(format!(
-                        "tail_index_{}_{}",
+                        "__tail_index_{}_{}",
                         current_index, id_list[current_index]
                     ))
             } else if names[0] == "_" {
                 term.lambda(first.clone()).apply(head_list).lambda(format!(
-                    "tail_index_{}_{}",
+                    "__tail_index_{}_{}",
                     current_index, id_list[current_index]
                 ))
             } else {
                 term.lambda(names[0].clone())
                     .apply(Term::tail_list().apply(Term::var(format!(
-                        "tail_index_{}_{}",
+                        "__tail_index_{}_{}",
                         current_index, id_list[current_index]
                     ))))
                     .lambda(first.clone())
                     .apply(head_list)
                     .lambda(format!(
-                        "tail_index_{}_{}",
+                        "__tail_index_{}_{}",
                         current_index, id_list[current_index]
                     ))
             }
@@ -505,7 +748,7 @@ pub fn list_access_to_uplc(
                 if check_last_item {
                     Term::tail_list()
                         .apply(Term::var(format!(
-                            "tail_index_{}_{}",
+                            "__tail_index_{}_{}",
                             current_index, id_list[current_index]
                         )))
                         .delayed_choose_list(
@@ -518,7 +761,7 @@ pub fn list_access_to_uplc(
                     term
                 }
                 .lambda(if check_last_item {
-                    format!("tail_index_{}_{}", current_index, id_list[current_index])
+                    format!("__tail_index_{}_{}", current_index, id_list[current_index])
                 } else {
                     "_".to_string()
                 })
@@ -526,7 +769,7 @@ pub fn list_access_to_uplc(
                 if check_last_item {
                     Term::tail_list()
                         .apply(Term::var(format!(
-                            "tail_index_{}_{}",
+                            "__tail_index_{}_{}",
                             current_index, id_list[current_index]
                         )))
                         .delayed_choose_list(
@@ -541,7 +784,7 @@ pub fn list_access_to_uplc(
                 .lambda(first.clone())
                 .apply(head_list)
                 .lambda(format!(
-                    "tail_index_{}_{}",
+                    "__tail_index_{}_{}",
                     current_index, id_list[current_index]
                 ))
             }
@@ -567,11 +810,11 @@ pub fn list_access_to_uplc(
                     } else {
                         list_access_inner
                             .apply(Term::tail_list().apply(Term::var(format!(
-                                "tail_index_{}_{}",
+                                "__tail_index_{}_{}",
                                 current_index, id_list[current_index]
                             ))))
                             .lambda(format!(
-                                "tail_index_{}_{}",
+                                "__tail_index_{}_{}",
                                 current_index, id_list[current_index]
                             ))
                     }
@@ -606,19 +849,19 @@ pub fn list_access_to_uplc(
                             .lambda(first.clone())
                             .apply(head_list)
                             .lambda(format!(
-                                "tail_index_{}_{}",
+                                "__tail_index_{}_{}",
                                 current_index, id_list[current_index]
                             ))
                     } else {
                         list_access_inner
                             .apply(Term::tail_list().apply(Term::var(format!(
-                                "tail_index_{}_{}",
+                                "__tail_index_{}_{}",
                                 current_index, id_list[current_index]
                             ))))
                             .lambda(first.clone())
                             .apply(head_list)
                             .lambda(format!(
-                                "tail_index_{}_{}",
+                                "__tail_index_{}_{}",
                                 current_index, id_list[current_index]
                             ))
                     }
@@ -627,7 +870,7 @@ pub fn list_access_to_uplc(
                     .lambda(first.clone())
                     .apply(head_list)
                     .lambda(format!(
-                        "tail_index_{}_{}",
+                        "__tail_index_{}_{}",
                         current_index, id_list[current_index]
                     )),
             };
@@ -667,7 +910,7 @@ pub fn check_when_pattern_needs(
                 check_when_pattern_needs(element, clause_properties);
             }
         }
-        Pattern::Int { .. } => {
+        Pattern::Int { .. } | Pattern::ByteArray { .. } | Pattern::String { .. } => {
             *clause_properties.needs_constr_var() = true;
 
             *clause_properties.is_complex_clause() = true;
@@ -706,6 +949,38 @@ pub fn constants_ir(literal: &Constant, ir_stack: &mut AirStack) {
     };
 }
 
+/// Counts how many times each user function is referenced in the given ir.
+pub fn count_function_calls(ir_stack: &[Air], calls: &mut IndexMap<FunctionAccessKey, usize>) {
+    for ir in ir_stack {
+        if let Some(func_key) = function_access_key(ir) {
+            *calls.entry(func_key).or_insert(0) += 1;
+        }
+    }
+}
+
+pub fn function_access_key(ir: &Air) -> Option<FunctionAccessKey> {
+    match ir {
+        Air::Var {
+            constructor,
+            variant_name,
+            ..
+        } => match &constructor.variant {
+            ValueConstructorVariant::ModuleFn {
+                name,
+                module,
+                builtin: None,
+                ..
+            } => Some(FunctionAccessKey {
+                module_name: module.clone(),
+                function_name: name.clone(),
+                variant_name: variant_name.clone(),
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 pub fn match_ir_for_recursion(
     ir: Air,
     insert_var_vec: &mut Vec<(usize, Air)>,
@@ -909,7 +1184,7 @@ pub fn convert_constants_to_data(constants: Vec<Rc<UplcConstant>>) -> Vec<UplcCo
                         .into_iter()
                         .map(|constant| match constant {
                             UplcConstant::Data(d) => d,
-                            _ => todo!(),
+                            _ => unreachable!("constants are always converted to data"),
                         })
                         .collect_vec();
 
@@ -921,7 +1196,7 @@ pub fn convert_constants_to_data(constants: Vec<Rc<UplcConstant>>) -> Vec<UplcCo
                     .into_iter()
                     .map(|constant| match constant {
                         UplcConstant::Data(d) => d,
-                        _ => todo!(),
+                        _ => unreachable!("constants are always converted to data"),
                     })
                     .collect_vec();
 
@@ -959,6 +1234,138 @@ pub fn wrap_validator_args(term: Term<Name>, arguments: &[TypedArg]) -> Term<Nam
     term
 }
 
+/// Rename the variables introduced by code generation (e.g. `__subject_name_42`)
+/// after the number of such variables bound around them, instead of after the
+/// program-wide counter they were created with. Names then only depend on the
+/// surrounding code, so unrelated changes elsewhere don't renumber them.
+///
+/// Two variables with the same prefix and number are never bound one inside
+/// the other, so this can't capture any variable.
+pub fn stabilize_generated_names(
+    term: &Term<Name>,
+    renamed: &mut Vec<(String, String)>,
+) -> Term<Name> {
+    match term {
+        Term::Var(name) => renamed
+            .iter()
+            .rev()
+            .find(|(original, _)| original == &name.text)
+            .map(|(_, new_name)| Term::var(new_name))
+            .unwrap_or_else(|| term.clone()),
+        Term::Lambda {
+            parameter_name,
+            body,
+        } => match generated_name_prefix(&parameter_name.text) {
+            Some(prefix) => {
+                let new_name = format!("{prefix}_{}", renamed.len());
+
+                renamed.push((parameter_name.text.clone(), new_name.clone()));
+
+                let body = stabilize_generated_names(body, renamed);
+
+                renamed.pop();
+
+                body.lambda(new_name)
+            }
+            None => stabilize_generated_names(body, renamed).lambda(parameter_name.text.clone()),
+        },
+        Term::Apply { function, argument } => stabilize_generated_names(function, renamed)
+            .apply(stabilize_generated_names(argument, renamed)),
+        Term::Delay(term) => stabilize_generated_names(term, renamed).delay(),
+        Term::Force(term) => stabilize_generated_names(term, renamed).force(),
+        Term::Constant(_) | Term::Error | Term::Builtin(_) => term.clone(),
+    }
+}
+
+/// Name of the identity functions that mark located terms while building a
+/// source map, followed by the index of the term's span.
+const SOURCE_MARKER: &str = "__source_map#";
+
+/// Marks a term as coming from the span at `index`, by applying the identity
+/// function to it. Markers are removed by [`strip_source_markers`].
+pub fn mark_source(term: Term<Name>, index: usize) -> Term<Name> {
+    let marker = format!("{SOURCE_MARKER}{index}");
+
+    Term::var(&marker).lambda(marker).apply(term)
+}
+
+/// Removes the markers left by [`mark_source`], and records the path of each
+/// marked term in the resulting tree along with its span.
+pub fn strip_source_markers(
+    term: &Term<Name>,
+    path: &mut Vec<usize>,
+    spans: &[Span],
+    source_map: &mut Vec<(Vec<usize>, Span)>,
+) -> Term<Name> {
+    match term {
+        Term::Apply { function, argument } => {
+            let marked = match function.as_ref() {
+                Term::Lambda { parameter_name, .. } => parameter_name
+                    .text
+                    .strip_prefix(SOURCE_MARKER)
+                    .and_then(|index| index.parse::<usize>().ok()),
+                _ => None,
+            };
+
+            if let Some(index) = marked {
+                source_map.push((path.clone(), spans[index]));
+
+                return strip_source_markers(argument, path, spans, source_map);
+            }
+
+            path.push(0);
+            let function = strip_source_markers(function, path, spans, source_map);
+            path.pop();
+
+            path.push(1);
+            let argument = strip_source_markers(argument, path, spans, source_map);
+            path.pop();
+
+            function.apply(argument)
+        }
+        Term::Lambda {
+            parameter_name,
+            body,
+        } => {
+            path.push(0);
+            let body = strip_source_markers(body, path, spans, source_map);
+            path.pop();
+
+            Term::Lambda {
+                parameter_name: parameter_name.clone(),
+                body: body.into(),
+            }
+        }
+        Term::Delay(term) => {
+            path.push(0);
+            let term = strip_source_markers(term, path, spans, source_map);
+            path.pop();
+
+            term.delay()
+        }
+        Term::Force(term) => {
+            path.push(0);
+            let term = strip_source_markers(term, path, spans, source_map);
+            path.pop();
+
+            term.force()
+        }
+        Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) => term.clone(),
+    }
+}
+
+/// The prefix of a name generated during code generation, which starts with
+/// `__` and ends with a number, e.g. `__subject_name` for `__subject_name_42`.
+fn generated_name_prefix(name: &str) -> Option<&str> {
+    let (prefix, number) = name.rsplit_once('_')?;
+
+    if name.starts_with("__") && !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) {
+        Some(prefix)
+    } else {
+        None
+    }
+}
+
 pub fn wrap_as_multi_validator(spend: Term<Name>, mint: Term<Name>) -> Term<Name> {
     Term::equals_integer()
         .apply(Term::integer(0.into()))
@@ -1080,12 +1487,22 @@ pub fn monomorphize(
                     needs_variant = true;
                 }
             }
-            Air::BinOp { scope, name, tipo } => {
+            Air::BinOp {
+                scope,
+                name,
+                tipo,
+                location,
+            } => {
                 if tipo.is_generic() {
                     let mut tipo = tipo.clone();
                     find_and_replace_generics(&mut tipo, &mono_types);
 
-                    new_air[index] = Air::BinOp { scope, name, tipo };
+                    new_air[index] = Air::BinOp {
+                        scope,
+                        name,
+                        tipo,
+                        location,
+                    };
                     needs_variant = true;
                 }
             }
@@ -1272,21 +1689,37 @@ pub fn monomorphize(
                     needs_variant = true;
                 }
             }
-            Air::ErrorTerm { scope, tipo } => {
+            Air::ErrorTerm {
+                scope,
+                location,
+                tipo,
+            } => {
                 if tipo.is_generic() {
                     let mut tipo = tipo.clone();
                     find_and_replace_generics(&mut tipo, &mono_types);
 
-                    new_air[index] = Air::ErrorTerm { scope, tipo };
+                    new_air[index] = Air::ErrorTerm {
+                        scope,
+                        location,
+                        tipo,
+                    };
                     needs_variant = true;
                 }
             }
-            Air::Trace { scope, tipo } => {
+            Air::Trace {
+                scope,
+                location,
+                tipo,
+            } => {
                 if tipo.is_generic() {
                     let mut tipo = tipo.clone();
                     find_and_replace_generics(&mut tipo, &mono_types);
 
-                    new_air[index] = Air::Trace { scope, tipo };
+                    new_air[index] = Air::Trace {
+                        scope,
+                        location,
+                        tipo,
+                    };
                     needs_variant = true;
                 }
             }
@@ -1569,33 +2002,29 @@ pub fn lookup_data_type_by_tipo(
     data_types: IndexMap<DataTypeKey, &TypedDataType>,
     tipo: &Type,
 ) -> Option<DataType<Arc<Type>>> {
+    let data_type_key = resolve_data_type_key(tipo)?;
+
+    data_types.get(&data_type_key).map(|item| (*item).clone())
+}
+
+/// Key of the data type a value of the given type belongs to, following
+/// type variables that were unified with a concrete type. Constructors
+/// taking arguments are functions, so their return type is looked at.
+pub fn resolve_data_type_key(tipo: &Type) -> Option<DataTypeKey> {
     match tipo {
         Type::Fn { ret, .. } => match ret.as_ref() {
-            Type::App { module, name, .. } => {
-                let data_type_key = DataTypeKey {
-                    module_name: module.clone(),
-                    defined_type: name.clone(),
-                };
-                data_types.get(&data_type_key).map(|item| (*item).clone())
-            }
+            Type::Fn { .. } => None,
+            ret => resolve_data_type_key(ret),
+        },
+        Type::App { module, name, .. } => Some(DataTypeKey {
+            module_name: module.clone(),
+            defined_type: name.clone(),
+        }),
+        Type::Var { tipo } => match &*tipo.borrow() {
+            TypeVar::Link { tipo } => resolve_data_type_key(tipo),
             _ => None,
         },
-        Type::App { module, name, .. } => {
-            let data_type_key = DataTypeKey {
-                module_name: module.clone(),
-                defined_type: name.clone(),
-            };
-
-            data_types.get(&data_type_key).map(|item| (*item).clone())
-        }
-        Type::Var { tipo } => {
-            if let TypeVar::Link { tipo } = &*tipo.borrow() {
-                lookup_data_type_by_tipo(data_types, tipo)
-            } else {
-                None
-            }
-        }
-        _ => None,
+        Type::Tuple { .. } => None,
     }
 }
 
@@ -1710,7 +2139,13 @@ pub fn handle_clause_guard(
             handle_clause_guard(left, &mut left_stack);
             handle_clause_guard(right, &mut right_stack);
 
-            clause_guard_stack.binop(BinOp::Eq, left.tipo(), left_stack, right_stack);
+            clause_guard_stack.binop(
+                BinOp::Eq,
+                left.tipo(),
+                clause_guard.location(),
+                left_stack,
+                right_stack,
+            );
         }
         ClauseGuard::NotEquals { left, right, .. } => {
             let mut left_stack = clause_guard_stack.empty_with_scope();
@@ -1719,7 +2154,13 @@ pub fn handle_clause_guard(
             handle_clause_guard(left, &mut left_stack);
             handle_clause_guard(right, &mut right_stack);
 
-            clause_guard_stack.binop(BinOp::NotEq, left.tipo(), left_stack, right_stack);
+            clause_guard_stack.binop(
+                BinOp::NotEq,
+                left.tipo(),
+                clause_guard.location(),
+                left_stack,
+                right_stack,
+            );
         }
         ClauseGuard::GtInt { left, right, .. } => {
             let mut left_stack = clause_guard_stack.empty_with_scope();
@@ -1728,7 +2169,13 @@ pub fn handle_clause_guard(
             handle_clause_guard(left, &mut left_stack);
             handle_clause_guard(right, &mut right_stack);
 
-            clause_guard_stack.binop(BinOp::GtInt, left.tipo(), left_stack, right_stack);
+            clause_guard_stack.binop(
+                BinOp::GtInt,
+                left.tipo(),
+                clause_guard.location(),
+                left_stack,
+                right_stack,
+            );
         }
         ClauseGuard::GtEqInt { left, right, .. } => {
             let mut left_stack = clause_guard_stack.empty_with_scope();
@@ -1737,7 +2184,13 @@ pub fn handle_clause_guard(
             handle_clause_guard(left, &mut left_stack);
             handle_clause_guard(right, &mut right_stack);
 
-            clause_guard_stack.binop(BinOp::GtEqInt, left.tipo(), left_stack, right_stack);
+            clause_guard_stack.binop(
+                BinOp::GtEqInt,
+                left.tipo(),
+                clause_guard.location(),
+                left_stack,
+                right_stack,
+            );
         }
         ClauseGuard::LtInt { left, right, .. } => {
             let mut left_stack = clause_guard_stack.empty_with_scope();
@@ -1746,7 +2199,13 @@ pub fn handle_clause_guard(
             handle_clause_guard(left, &mut left_stack);
             handle_clause_guard(right, &mut right_stack);
 
-            clause_guard_stack.binop(BinOp::LtInt, left.tipo(), left_stack, right_stack);
+            clause_guard_stack.binop(
+                BinOp::LtInt,
+                left.tipo(),
+                clause_guard.location(),
+                left_stack,
+                right_stack,
+            );
         }
         ClauseGuard::LtEqInt { left, right, .. } => {
             let mut left_stack = clause_guard_stack.empty_with_scope();
@@ -1755,7 +2214,13 @@ pub fn handle_clause_guard(
             handle_clause_guard(left, &mut left_stack);
             handle_clause_guard(right, &mut right_stack);
 
-            clause_guard_stack.binop(BinOp::LtEqInt, left.tipo(), left_stack, right_stack);
+            clause_guard_stack.binop(
+                BinOp::LtEqInt,
+                left.tipo(),
+                clause_guard.location(),
+                left_stack,
+                right_stack,
+            );
         }
         ClauseGuard::Or { left, right, .. } => {
             let mut left_stack = clause_guard_stack.empty_with_scope();
@@ -1764,7 +2229,13 @@ pub fn handle_clause_guard(
             handle_clause_guard(left, &mut left_stack);
             handle_clause_guard(right, &mut right_stack);
 
-            clause_guard_stack.binop(BinOp::Or, left.tipo(), left_stack, right_stack);
+            clause_guard_stack.binop(
+                BinOp::Or,
+                left.tipo(),
+                clause_guard.location(),
+                left_stack,
+                right_stack,
+            );
         }
         ClauseGuard::And { left, right, .. } => {
             let mut left_stack = clause_guard_stack.empty_with_scope();
@@ -1773,7 +2244,13 @@ pub fn handle_clause_guard(
             handle_clause_guard(left, &mut left_stack);
             handle_clause_guard(right, &mut right_stack);
 
-            clause_guard_stack.binop(BinOp::And, left.tipo(), left_stack, right_stack);
+            clause_guard_stack.binop(
+                BinOp::And,
+                left.tipo(),
+                clause_guard.location(),
+                left_stack,
+                right_stack,
+            );
         }
         ClauseGuard::Var { tipo, name, .. } => {
             clause_guard_stack.local_var(tipo.clone(), name);
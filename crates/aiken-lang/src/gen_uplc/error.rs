@@ -0,0 +1,41 @@
+use crate::ast::Span;
+
+#[derive(Debug, thiserror::Error, Clone, PartialEq)]
+pub enum CodeGenError {
+    #[error("I don't know how to generate code for {construct} yet.")]
+    UnsupportedExpression { construct: String, location: Span },
+
+    #[error("I couldn't find the definition of the data type '{name}'.")]
+    MissingDataType { name: String },
+
+    #[error("I couldn't find the constructor '{name}' of the data type '{data_type}'.")]
+    MissingConstructor { name: String, data_type: String },
+
+    #[error("I couldn't find the definition of the function '{module}.{name}'.")]
+    MissingFunction { module: String, name: String },
+
+    #[error("I can't expect a '{tipo}' more than {limit} levels deep into a type.")]
+    ExpectTooDeep { tipo: String, limit: usize },
+
+    #[error("I ended up with {remaining} terms instead of one after generating code for {last}.")]
+    StackImbalance { remaining: usize, last: String },
+
+    #[error("I was asked to build constructor {index} of '{name}', which only has {count}.")]
+    ConstructorIndexOutOfRange {
+        name: String,
+        index: usize,
+        count: usize,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error("I couldn't convert the program between named and de Bruijn variables: {reason}")]
+    Conversion { reason: String },
+
+    #[error("I couldn't evaluate the program: {error}")]
+    Machine {
+        error: uplc::machine::Error,
+        logs: Vec<String>,
+    },
+}
@@ -16,6 +16,11 @@ impl Scope {
         self.0.is_empty()
     }
 
+    /// How deeply nested the scope is.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
     /// Find the common ancestor with the replacement,
     /// remove it from `self`, and then prepend the
     /// `replacement` to `self`.
@@ -30,24 +35,18 @@ impl Scope {
         self.0 = replacement.0;
     }
 
+    /// The longest scope that both `self` and `other` start with. This is
+    /// `self` when both are equal, the shorter one when one is a prefix of the
+    /// other, and the empty (root) scope when they differ from the start.
     pub fn common_ancestor(&self, other: &Self) -> Scope {
-        let longest_length = self.0.len().max(other.0.len());
-
-        if *self.0 == *other.0 {
-            return self.clone();
-        }
-
-        for index in 0..longest_length {
-            if self.0.get(index).is_none() {
-                return self.clone();
-            } else if other.0.get(index).is_none() {
-                return other.clone();
-            } else if self.0[index] != other.0[index] {
-                return Scope(self.0[0..index].to_vec());
-            }
-        }
-
-        Scope::default()
+        Scope(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .take_while(|(left, right)| left == right)
+                .map(|(id, _)| *id)
+                .collect(),
+        )
     }
 }
 
@@ -79,6 +78,28 @@ mod test {
         assert_eq!(result, Scope(vec![1, 2, 3, 4]));
     }
 
+    #[test]
+    fn common_ancestor_equal_descendant() {
+        let descendant = Scope(vec![1, 2, 3, 4, 5, 6]);
+
+        let ancestor = Scope(vec![1, 2, 3, 4]);
+
+        let result = descendant.common_ancestor(&ancestor);
+
+        assert_eq!(result, Scope(vec![1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn common_ancestor_root() {
+        let root = Scope::default();
+
+        let scope = Scope(vec![1, 2, 3]);
+
+        assert_eq!(root.common_ancestor(&scope), Scope::default());
+        assert_eq!(scope.common_ancestor(&root), Scope::default());
+        assert_eq!(root.common_ancestor(&root), Scope::default());
+    }
+
     #[test]
     fn common_ancestor_not_subset() {
         let ancestor = Scope(vec![1, 2, 3, 4, 5]);
@@ -210,6 +210,7 @@ impl AirStack {
         &mut self,
         name: crate::ast::BinOp,
         tipo: Arc<Type>,
+        location: Span,
         left: AirStack,
         right: AirStack,
     ) {
@@ -219,6 +220,7 @@ impl AirStack {
             scope: self.scope.clone(),
             name,
             tipo,
+            location,
         });
 
         self.merge_child(left);
@@ -439,20 +441,22 @@ impl AirStack {
         self.merge_child(body);
     }
 
-    pub fn trace(&mut self, tipo: Arc<Type>) {
+    pub fn trace(&mut self, tipo: Arc<Type>, location: Span) {
         self.new_scope();
 
         self.air.push(Air::Trace {
             scope: self.scope.clone(),
+            location,
             tipo,
         })
     }
 
-    pub fn error(&mut self, tipo: Arc<Type>) {
+    pub fn error(&mut self, tipo: Arc<Type>, location: Span) {
         self.new_scope();
 
         self.air.push(Air::ErrorTerm {
             scope: self.scope.clone(),
+            location,
             tipo,
         })
     }
@@ -910,8 +914,6 @@ mod test {
 
         stack1.expect_on_list();
 
-        println!("{:#?}", stack1);
-
         let air_vec = vec![
             Air::DefineFunc {
                 scope: vec![0].into(),
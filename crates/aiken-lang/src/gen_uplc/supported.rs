@@ -0,0 +1,291 @@
+use std::{fmt, sync::Arc};
+
+use uplc::builtins::DefaultFunction;
+
+use crate::{
+    ast::{AssignmentKind, Pattern, Span},
+    expr::TypedExpr,
+    tipo::{ModuleValueConstructor, PatternConstructor, Type, ValueConstructorVariant},
+};
+
+use super::error::CodeGenError;
+
+/// The builtins the generator has no lowering for, whether they are called or
+/// used as values.
+const UNSUPPORTED_BUILTINS: [DefaultFunction; 4] = [
+    DefaultFunction::IfThenElse,
+    DefaultFunction::ChooseList,
+    DefaultFunction::MkCons,
+    DefaultFunction::Trace,
+];
+
+/// A construct the code generator can't lower to UPLC yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsupportedKind {
+    IntPattern,
+    ByteArrayPattern,
+    StringPattern,
+    AssignPattern,
+    NestedIntPattern,
+    NestedByteArrayPattern,
+    NestedStringPattern,
+    NestedAssignPattern,
+    DataConstructorPattern,
+    Builtin(DefaultFunction),
+}
+
+impl UnsupportedKind {
+    pub fn builtin(func: DefaultFunction) -> Option<Self> {
+        UNSUPPORTED_BUILTINS
+            .contains(&func)
+            .then_some(UnsupportedKind::Builtin(func))
+    }
+}
+
+impl fmt::Display for UnsupportedKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnsupportedKind::IntPattern => write!(f, "integer patterns"),
+            UnsupportedKind::ByteArrayPattern => write!(f, "byte array patterns"),
+            UnsupportedKind::StringPattern => write!(f, "string patterns"),
+            UnsupportedKind::AssignPattern => write!(f, "assign patterns"),
+            UnsupportedKind::NestedIntPattern => write!(f, "nested integer patterns"),
+            UnsupportedKind::NestedByteArrayPattern => write!(f, "nested byte array patterns"),
+            UnsupportedKind::NestedStringPattern => write!(f, "nested string patterns"),
+            UnsupportedKind::NestedAssignPattern => write!(f, "nested assign patterns"),
+            UnsupportedKind::DataConstructorPattern => write!(f, "constructor patterns on Data"),
+            UnsupportedKind::Builtin(func) => write!(f, "the builtin '{}'", func.aiken_name()),
+        }
+    }
+}
+
+/// A construct rejected by [`check_supported`], with where it was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedFeature {
+    pub kind: UnsupportedKind,
+    pub span: Span,
+}
+
+impl From<UnsupportedFeature> for CodeGenError {
+    fn from(UnsupportedFeature { kind, span }: UnsupportedFeature) -> Self {
+        CodeGenError::UnsupportedExpression {
+            construct: kind.to_string(),
+            location: span,
+        }
+    }
+}
+
+/// What the code generator can't handle yet. Everything else a module can
+/// type check with is supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureSet {
+    pub unsupported: Vec<UnsupportedKind>,
+}
+
+impl FeatureSet {
+    pub fn supports(&self, kind: UnsupportedKind) -> bool {
+        !self.unsupported.contains(&kind)
+    }
+}
+
+pub fn supported_features() -> FeatureSet {
+    let mut unsupported = vec![
+        UnsupportedKind::IntPattern,
+        UnsupportedKind::ByteArrayPattern,
+        UnsupportedKind::StringPattern,
+        UnsupportedKind::AssignPattern,
+        UnsupportedKind::NestedIntPattern,
+        UnsupportedKind::NestedByteArrayPattern,
+        UnsupportedKind::NestedStringPattern,
+        UnsupportedKind::NestedAssignPattern,
+        UnsupportedKind::DataConstructorPattern,
+    ];
+
+    unsupported.extend(UNSUPPORTED_BUILTINS.map(UnsupportedKind::Builtin));
+
+    FeatureSet { unsupported }
+}
+
+/// Walks `body` ahead of code generation and returns the first construct
+/// [`CodeGenerator`](super::CodeGenerator) would report as unsupported, so a
+/// frontend can point at it without generating anything.
+pub fn check_supported(body: &TypedExpr) -> Result<(), UnsupportedFeature> {
+    match body {
+        TypedExpr::Int { .. }
+        | TypedExpr::String { .. }
+        | TypedExpr::ByteArray { .. }
+        | TypedExpr::ErrorTerm { .. } => Ok(()),
+
+        TypedExpr::Var {
+            location,
+            constructor,
+            ..
+        } => match &constructor.variant {
+            ValueConstructorVariant::ModuleFn {
+                builtin: Some(func),
+                ..
+            } => check_builtin(*func, *location),
+            _ => Ok(()),
+        },
+
+        TypedExpr::ModuleSelect {
+            location,
+            module_name,
+            constructor: ModuleValueConstructor::Fn { name, .. },
+            ..
+        } if module_name == "aiken/builtin" => UNSUPPORTED_BUILTINS
+            .into_iter()
+            .find(|func| &func.aiken_name() == name)
+            .map_or(Ok(()), |func| check_builtin(func, *location)),
+
+        TypedExpr::ModuleSelect { .. } => Ok(()),
+
+        TypedExpr::Sequence { expressions, .. } | TypedExpr::Pipeline { expressions, .. } => {
+            expressions.iter().try_for_each(check_supported)
+        }
+
+        TypedExpr::Fn { body, .. } => check_supported(body),
+
+        TypedExpr::List { elements, tail, .. } => elements
+            .iter()
+            .chain(tail.as_deref())
+            .try_for_each(check_supported),
+
+        TypedExpr::Call { fun, args, .. } => {
+            check_supported(fun)?;
+
+            args.iter().try_for_each(|arg| check_supported(&arg.value))
+        }
+
+        TypedExpr::BinOp { left, right, .. } => {
+            check_supported(left)?;
+
+            check_supported(right)
+        }
+
+        TypedExpr::Assignment {
+            value,
+            pattern,
+            kind,
+            ..
+        } => {
+            check_supported(value)?;
+
+            check_assignment_pattern(pattern, kind, &value.tipo())
+        }
+
+        TypedExpr::Trace { then, text, .. } => {
+            check_supported(text)?;
+
+            check_supported(then)
+        }
+
+        TypedExpr::When {
+            subject, clauses, ..
+        } => {
+            check_supported(subject)?;
+
+            // A `when` with a single clause is compiled like a `let`.
+            if let [clause] = clauses.as_slice() {
+                check_assignment_pattern(&clause.pattern, &AssignmentKind::Let, &subject.tipo())?;
+            }
+
+            clauses
+                .iter()
+                .try_for_each(|clause| check_supported(&clause.then))
+        }
+
+        TypedExpr::If {
+            branches,
+            final_else,
+            ..
+        } => {
+            for branch in branches {
+                check_supported(&branch.condition)?;
+
+                check_supported(&branch.body)?;
+            }
+
+            check_supported(final_else)
+        }
+
+        TypedExpr::RecordAccess { record, .. } => check_supported(record),
+
+        TypedExpr::Tuple { elems, .. } => elems.iter().try_for_each(check_supported),
+
+        TypedExpr::TupleIndex { tuple, .. } => check_supported(tuple),
+
+        TypedExpr::RecordUpdate { spread, args, .. } => {
+            check_supported(spread)?;
+
+            args.iter().try_for_each(|arg| check_supported(&arg.value))
+        }
+
+        TypedExpr::UnOp { value, .. } => check_supported(value),
+    }
+}
+
+fn check_builtin(func: DefaultFunction, span: Span) -> Result<(), UnsupportedFeature> {
+    match UnsupportedKind::builtin(func) {
+        Some(kind) => Err(UnsupportedFeature { kind, span }),
+        None => Ok(()),
+    }
+}
+
+fn check_assignment_pattern(
+    pattern: &Pattern<PatternConstructor, Arc<Type>>,
+    kind: &AssignmentKind,
+    tipo: &Type,
+) -> Result<(), UnsupportedFeature> {
+    let unsupported = |kind, span| Err(UnsupportedFeature { kind, span });
+
+    match pattern {
+        Pattern::Int { location, .. } => unsupported(UnsupportedKind::IntPattern, *location),
+        Pattern::ByteArray { location, .. } => {
+            unsupported(UnsupportedKind::ByteArrayPattern, *location)
+        }
+        Pattern::String { location, .. } => unsupported(UnsupportedKind::StringPattern, *location),
+        Pattern::Assign { location, .. } => unsupported(UnsupportedKind::AssignPattern, *location),
+        Pattern::Constructor { location, .. }
+            if matches!(kind, AssignmentKind::Expect) && tipo.is_data() =>
+        {
+            unsupported(UnsupportedKind::DataConstructorPattern, *location)
+        }
+        _ => check_nested_patterns(pattern),
+    }
+}
+
+fn check_nested_patterns(
+    pattern: &Pattern<PatternConstructor, Arc<Type>>,
+) -> Result<(), UnsupportedFeature> {
+    let unsupported = |kind, span| Err(UnsupportedFeature { kind, span });
+
+    let nested: Vec<&Pattern<PatternConstructor, Arc<Type>>> = match pattern {
+        Pattern::List { elements, tail, .. } => elements.iter().chain(tail.as_deref()).collect(),
+        Pattern::Tuple { elems, .. } => elems.iter().collect(),
+        Pattern::Constructor { arguments, .. } => {
+            arguments.iter().map(|argument| &argument.value).collect()
+        }
+        _ => vec![],
+    };
+
+    for pattern in nested {
+        match pattern {
+            Pattern::Int { location, .. } => {
+                return unsupported(UnsupportedKind::NestedIntPattern, *location)
+            }
+            Pattern::ByteArray { location, .. } => {
+                return unsupported(UnsupportedKind::NestedByteArrayPattern, *location)
+            }
+            Pattern::String { location, .. } => {
+                return unsupported(UnsupportedKind::NestedStringPattern, *location)
+            }
+            Pattern::Assign { location, .. } => {
+                return unsupported(UnsupportedKind::NestedAssignPattern, *location)
+            }
+            _ => check_nested_patterns(pattern)?,
+        }
+    }
+
+    Ok(())
+}
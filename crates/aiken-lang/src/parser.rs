@@ -1245,6 +1245,7 @@ pub fn expr_parser(
         let op = choice((
             just(Token::Plus).to(BinOp::AddInt),
             just(Token::Minus).to(BinOp::SubInt),
+            just(Token::LessGreater).to(BinOp::Concat),
         ));
 
         let sum = product
@@ -1685,6 +1686,19 @@ pub fn pattern_parser() -> impl Parser<Token, ast::UntypedPattern, Error = Parse
                     value,
                 }
             }),
+            bytearray_parser().map_with_span(|(preferred_format, value), span| {
+                ast::UntypedPattern::ByteArray {
+                    location: span,
+                    value,
+                    preferred_format,
+                }
+            }),
+            select! {Token::String {value} => value}.map_with_span(|value, span| {
+                ast::UntypedPattern::String {
+                    location: span,
+                    value,
+                }
+            }),
             r.clone()
                 .separated_by(just(Token::Comma))
                 .allow_trailing()
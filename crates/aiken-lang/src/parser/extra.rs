@@ -1,7 +1,8 @@
 use crate::ast::Span;
+use serde::{Deserialize, Serialize};
 use std::iter::Peekable;
 
-#[derive(Debug, PartialEq, Eq, Default, Clone)]
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
 pub struct ModuleExtra {
     pub module_comments: Vec<Span>,
     pub doc_comments: Vec<Span>,
@@ -37,10 +38,39 @@ impl<'a> From<(&Span, &'a str)> for Comment<'a> {
     }
 }
 
+/// The byte offset of each character of a source, so that spans, which
+/// count characters, can be sliced out of it without scanning it each time.
+pub struct SourceIndex<'a> {
+    src: &'a str,
+    offsets: Vec<usize>,
+}
+
+impl<'a> SourceIndex<'a> {
+    pub fn new(src: &'a str) -> Self {
+        let offsets = src
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(src.len()))
+            .collect();
+
+        SourceIndex { src, offsets }
+    }
+
+    pub fn comment(&self, span: &Span) -> Comment<'a> {
+        Comment {
+            start: span.start,
+            content: self
+                .src
+                .get(self.offsets[span.start]..self.offsets[span.end])
+                .expect("From span to comment"),
+        }
+    }
+}
+
 pub fn comments_before<'a>(
     comment_spans: &mut Peekable<impl Iterator<Item = &'a Span>>,
     byte: usize,
-    src: &'a str,
+    src: &SourceIndex<'a>,
 ) -> Vec<&'a str> {
     let mut comments = vec![];
     while let Some(Span { start, .. }) = comment_spans.peek() {
@@ -48,7 +78,7 @@ pub fn comments_before<'a>(
             let comment = comment_spans
                 .next()
                 .expect("Comment before accessing next span");
-            comments.push(Comment::from((comment, src)).content)
+            comments.push(src.comment(comment).content)
         } else {
             break;
         }
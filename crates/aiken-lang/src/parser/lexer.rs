@@ -7,7 +7,25 @@ use ordinal::Ordinal;
 use super::{error::ParseError, token::Token};
 
 pub fn lexer() -> impl Parser<char, Vec<(Token, Span)>, Error = ParseError> {
-    let int = text::int(10).map(|value| Token::Int { value });
+    // Integers may be written in base 16, 8 or 2 and have their digits
+    // grouped with underscores. They keep their spelling, so that formatting
+    // preserves it; see `gen_uplc::builder::int_literal_value` for the value.
+    let radix = |prefix: &'static str, radix| {
+        just(prefix)
+            .then(digits(radix))
+            .map(|(prefix, digits)| format!("{prefix}{digits}"))
+    };
+
+    let int = choice((
+        radix("0x", 16),
+        radix("0o", 8),
+        radix("0b", 2),
+        filter(|c: &char| c.is_ascii_digit() && *c != '0')
+            .rewind()
+            .ignore_then(digits(10)),
+        just('0').map(String::from),
+    ))
+    .map(|value| Token::Int { value });
 
     let ordinal = text::int(10)
         .then_with(|index: String| {
@@ -42,6 +60,7 @@ pub fn lexer() -> impl Parser<char, Vec<(Token, Span)>, Error = ParseError> {
         just('?').to(Token::Question),
         choice((
             just("<=").to(Token::LessEqual),
+            just("<>").to(Token::LessGreater),
             just('<').to(Token::Less),
             just(">=").to(Token::GreaterEqual),
             just('>').to(Token::Greater),
@@ -189,3 +208,12 @@ pub fn lexer() -> impl Parser<char, Vec<(Token, Span)>, Error = ParseError> {
     .padded_by(one_of(" \t").ignored().repeated())
     .then_ignore(end())
 }
+
+/// Digits of the given radix, where single underscores may separate digits.
+fn digits(radix: u32) -> impl Parser<char, String, Error = ParseError> + Clone {
+    let digit = filter(move |c: &char| c.is_digit(radix));
+
+    digit
+        .chain(just('_').or_not().chain(digit).repeated().flatten())
+        .collect()
+}
@@ -28,6 +28,8 @@ pub enum Token {
     LessEqual,
     GreaterEqual,
     Percent,
+    // String and ByteArray Operators
+    LessGreater, // '<>'
     // ByteString Operators
     PlusDot,         // '+.'
     MinusDot,        // '-.'
@@ -116,6 +118,7 @@ impl fmt::Display for Token {
             Token::LessEqual => "<=",
             Token::GreaterEqual => ">=",
             Token::Percent => "%",
+            Token::LessGreater => "<>",
             Token::PlusDot => "+.",
             Token::MinusDot => "-.",
             Token::StarDot => "*.",
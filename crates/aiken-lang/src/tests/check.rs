@@ -95,6 +95,28 @@ fn validator_correct_form() {
     assert!(check_validator(parse(source_code)).is_ok())
 }
 
+#[test]
+fn validator_duplicate_purpose() {
+    let source_code = r#"
+      validator {
+        fn mint(r, c) {
+          True
+        }
+      }
+
+      validator {
+        fn mint(r, c) {
+          False
+        }
+      }
+    "#;
+
+    assert!(matches!(
+        check_validator(parse(source_code)),
+        Err((_, Error::DuplicateName { ref name, .. })) if name == "mint"
+    ))
+}
+
 #[test]
 fn validator_in_lib_warning() {
     let source_code = r#"
@@ -388,13 +410,18 @@ fn discarded_let_bindings() {
     assert!(matches!(warnings[0], Warning::UnusedVariable { ref name, .. } if name == "unused"));
     assert!(matches!(warnings[1], Warning::UnusedVariable { ref name, .. } if name == "_"));
 
-    // Controls that unused let-bindings have been erased from the transformed AST.
+    // Controls that unused let-bindings have been erased from the transformed AST, while
+    // discarded ones are kept for their side effects.
     match ast.definitions.first() {
         Some(Definition::Fn(def)) => match &def.body {
             TypedExpr::Sequence { expressions, .. } => {
-                assert_eq!(expressions.len(), 2);
+                assert_eq!(expressions.len(), 3);
                 assert!(
-                    matches!(expressions[1], TypedExpr::Var { .. }),
+                    matches!(expressions[1], TypedExpr::Assignment { ref pattern, .. } if pattern.is_discard()),
+                    "second expression isn't discarded assignment"
+                );
+                assert!(
+                    matches!(expressions[2], TypedExpr::Var { .. }),
                     "last expression isn't return variable"
                 );
                 match &expressions[0] {
@@ -414,3 +441,126 @@ fn discarded_let_bindings() {
         _ => unreachable!("ast isn't a Fn"),
     }
 }
+
+#[test]
+fn literal_patterns_must_match_subject_type() {
+    let source_code = r#"
+        fn foo(hash: ByteArray, name: String) {
+          when hash is {
+            #"deadbeef" -> name
+            _ -> when name is {
+              @"bar" -> name
+              _ -> @"baz"
+            }
+          }
+        }
+    "#;
+
+    assert!(check(parse(source_code)).is_ok());
+
+    let source_code = r#"
+        fn foo(n: Int) {
+          when n is {
+            #"deadbeef" -> True
+            _ -> False
+          }
+        }
+    "#;
+
+    assert!(matches!(
+        check(parse(source_code)),
+        Err((_, Error::CouldNotUnify { .. }))
+    ))
+}
+
+#[test]
+fn concat_operands_are_strings_or_byte_arrays() {
+    let source_code = r#"
+        fn foo(a: ByteArray, b: String) -> (ByteArray, String) {
+          (a <> #"ff", b <> @"bar")
+        }
+    "#;
+
+    assert!(check(parse(source_code)).is_ok());
+
+    for source_code in [
+        r#"
+        fn foo(a: Int) {
+          a <> 1
+        }
+    "#,
+        r#"
+        fn foo(a: ByteArray, b: String) {
+          a <> b
+        }
+    "#,
+    ] {
+        assert!(matches!(
+            check(parse(source_code)),
+            Err((_, Error::CouldNotUnify { .. }))
+        ))
+    }
+}
+
+#[test]
+fn concat_infers_byte_arrays_from_either_operand() {
+    let source_code = r#"
+        fn suffix(a) {
+          a <> #"ff"
+        }
+
+        fn prefix(b) {
+          #"ff" <> b
+        }
+
+        fn greet(name) {
+          name <> @"!"
+        }
+
+        fn foo() -> (ByteArray, ByteArray, String) {
+          (suffix(#"00"), prefix(#"00"), greet(@"aiken"))
+        }
+    "#;
+
+    assert!(check(parse(source_code)).is_ok());
+
+    let source_code = r#"
+        fn suffix(a) {
+          a <> #"ff"
+        }
+
+        fn foo() {
+          suffix(@"aiken")
+        }
+    "#;
+
+    assert!(matches!(
+        check(parse(source_code)),
+        Err((_, Error::CouldNotUnify { .. }))
+    ))
+}
+
+#[test]
+fn under_applied_call() {
+    let source_code = r#"
+        fn add(a: Int, b: Int) -> Int {
+          a + b
+        }
+
+        fn add5() {
+          add(5)
+        }
+    "#;
+
+    assert!(matches!(
+        check(parse(source_code)),
+        Err((
+            _,
+            Error::IncorrectFieldsArity {
+                expected: 2,
+                given: 1,
+                ..
+            }
+        ))
+    ))
+}
@@ -807,3 +807,136 @@ fn match_record() {
 
     assert_fmt(src, src);
 }
+
+#[test]
+fn match_literals() {
+    let src = indoc! { r#"
+      fn foo() {
+        when bar is {
+          #"deadbeef" ->
+            Void
+          "foo" ->
+            Void
+          #[1, 2] ->
+            Void
+          _ ->
+            Void
+        }
+      }
+
+      fn bar() {
+        when baz is {
+          @"foo" ->
+            Void
+          _ ->
+            Void
+        }
+      }
+    "#};
+
+    assert_fmt(src, src);
+}
+
+#[test]
+fn concat_operator() {
+    let src = indoc! { r#"
+      fn foo(a, b) {
+        a <> #"ff" <> b
+      }
+    "#};
+
+    assert_fmt(src, src);
+
+    assert_fmt(
+        indoc! { r#"
+          fn foo(a, b) {
+            a<>b
+          }
+        "#},
+        indoc! { r#"
+          fn foo(a, b) {
+            a <> b
+          }
+        "#},
+    );
+}
+
+#[test]
+fn test_format_messy_module() {
+    let src = indoc! { r#"
+        use aiken/string
+          use aiken/bytearray.{concat}
+        // The list module
+        use aiken/list
+
+        /// Double every element
+        pub fn double(xs:List<Int>)->List<Int>{
+        // map over xs
+            list.map(xs,fn(x){x*2})
+        }
+
+          type Point{x:Int,
+        y:Int}
+
+        test foo(){
+          let xs=double([1,2])
+            xs==[2,4]
+        }
+    "#};
+
+    let expected = indoc! { r#"
+        use aiken/bytearray.{concat}
+        // The list module
+        use aiken/list
+        use aiken/string
+
+        /// Double every element
+        pub fn double(xs: List<Int>) -> List<Int> {
+          // map over xs
+          list.map(xs, fn(x) { x * 2 })
+        }
+
+        type Point {
+          x: Int,
+          y: Int,
+        }
+
+        test foo() {
+          let xs =
+            double([1, 2])
+          xs == [2, 4]
+        }
+    "#};
+
+    assert_fmt(src, expected);
+}
+
+#[test]
+fn test_format_int_literals() {
+    let src = indoc! { r#"
+        const mask = 0xff
+
+        fn f(n: Int) {
+          when n is {
+            0b101 -> 1_000
+            _ -> mask
+          }
+        }
+    "#};
+
+    let expected = indoc! { r#"
+        const mask =
+          0xff
+
+        fn f(n: Int) {
+          when n is {
+            0b101 ->
+              1_000
+            _ ->
+              mask
+          }
+        }
+    "#};
+
+    assert_fmt(src, expected);
+}
@@ -35,3 +35,28 @@ fn tokens() {
         ]),
     );
 }
+
+#[test]
+fn int_literals() {
+    let code = "1_000 0xff 0o17 0b101 0 0x1_F 007";
+    let len = code.chars().count();
+
+    let span = |i| Span::new((), i..i + 1);
+
+    assert_eq!(
+        lexer::lexer()
+            .parse(chumsky::Stream::from_iter(
+                span(len),
+                code.chars().enumerate().map(|(i, c)| (c, span(i))),
+            ))
+            .map(|tokens| tokens.into_iter().map(|(tok, _)| tok).collect::<Vec<_>>()),
+        Ok(
+            ["1_000", "0xff", "0o17", "0b101", "0", "0x1_F", "0", "0", "7"]
+                .into_iter()
+                .map(|value| Token::Int {
+                    value: value.to_string()
+                })
+                .collect()
+        ),
+    );
+}
@@ -3,6 +3,7 @@ use crate::{
     ast::{Constant, DefinitionLocation, ModuleKind, Span},
     tipo::fields::FieldMap,
 };
+use serde::{Deserialize, Serialize};
 use std::{cell::RefCell, collections::HashMap, ops::Deref, sync::Arc};
 use uplc::{ast::Type as UplcType, builtins::DefaultFunction};
 
@@ -16,7 +17,7 @@ mod pattern;
 mod pipe;
 pub mod pretty;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Type {
     /// A nominal (named) type such as `Int`, `Float`, or a programmer defined
     /// custom type such as `Person`. The type can take other types as
@@ -273,8 +274,8 @@ impl Type {
                         UplcType::List(UplcType::Data.into())
                     }
                 }
-                Self::Var { tipo } => tipo.borrow().get_uplc_type().unwrap(),
-                _ => todo!(),
+                Self::Var { tipo } => tipo.borrow().get_uplc_type().unwrap_or(UplcType::Data),
+                _ => UplcType::Data,
             }
         } else {
             UplcType::Data
@@ -378,7 +379,7 @@ impl Type {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TypeVar {
     /// Unbound is an unbound variable. It is one specific type but we don't
     /// know what yet in the inference process. It has a unique id which can be used to
@@ -523,7 +524,7 @@ impl TypeVar {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ValueConstructor {
     pub public: bool,
     pub variant: ValueConstructorVariant,
@@ -572,7 +573,7 @@ impl ValueConstructor {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ValueConstructorVariant {
     /// A locally defined variable or function parameter
     LocalVariable { location: Span },
@@ -669,7 +670,7 @@ impl ValueConstructorVariant {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeInfo {
     pub name: String,
     pub kind: ModuleKind,
@@ -680,7 +681,7 @@ pub struct TypeInfo {
     pub accessors: HashMap<String, AccessorsMap>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeConstructor {
     pub public: bool,
     pub location: Span,
@@ -689,14 +690,14 @@ pub struct TypeConstructor {
     pub tipo: Arc<Type>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccessorsMap {
     pub public: bool,
     pub tipo: Arc<Type>,
     pub accessors: HashMap<String, RecordAccessor>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordAccessor {
     // TODO: smaller int. Doesn't need to be this big
     pub index: u64,
@@ -704,7 +705,7 @@ pub struct RecordAccessor {
     pub tipo: Arc<Type>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PatternConstructor {
     Record {
         name: String,
@@ -712,7 +713,7 @@ pub enum PatternConstructor {
     },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ModuleValueConstructor {
     Record {
         name: String,
@@ -755,3 +756,36 @@ impl ModuleValueConstructor {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::builtins::{byte_array, int, list, tuple};
+
+    #[test]
+    fn uplc_type_of_tuples() {
+        let pair = tuple(vec![int(), byte_array()]);
+
+        assert_eq!(
+            pair.get_uplc_type(),
+            UplcType::Pair(UplcType::Data.into(), UplcType::Data.into())
+        );
+
+        let linked = Type::Var {
+            tipo: RefCell::new(TypeVar::Link { tipo: pair.clone() }).into(),
+        };
+
+        assert_eq!(linked.get_uplc_type(), pair.get_uplc_type());
+
+        assert_eq!(
+            tuple(vec![int(), byte_array(), int()]).get_uplc_type(),
+            UplcType::List(UplcType::Data.into())
+        );
+
+        // A list of pairs is how maps are represented.
+        assert_eq!(
+            list(pair).get_uplc_type(),
+            UplcType::List(UplcType::Pair(UplcType::Data.into(), UplcType::Data.into()).into())
+        );
+    }
+}
@@ -13,6 +13,7 @@ use owo_colors::{
     OwoColorize,
     Stream::{Stderr, Stdout},
 };
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt::Display, sync::Arc};
 
 #[derive(Debug, thiserror::Error, Diagnostic)]
@@ -1196,7 +1197,7 @@ fn suggest_import_constructor() -> String {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, thiserror::Error, Diagnostic)]
+#[derive(Debug, PartialEq, Clone, thiserror::Error, Diagnostic, Serialize, Deserialize)]
 pub enum Warning {
     #[error("I found a record update using all fields; thus redundant.\n")]
     #[diagnostic(url("https://aiken-lang.org/language-tour/custom-types#record-updates"))]
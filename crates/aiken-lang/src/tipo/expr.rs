@@ -474,6 +474,43 @@ impl<'a, 'b> ExprTyper<'a, 'b> {
                     right: Box::new(right),
                 });
             }
+            BinOp::Concat => {
+                let left = self.infer(left)?;
+
+                let right = self.infer(right)?;
+
+                // Either operand may still be generic, so look at both before
+                // settling on byte arrays or strings.
+                let tipo = if left.tipo().is_bytearray() || right.tipo().is_bytearray() {
+                    byte_array()
+                } else {
+                    string()
+                };
+
+                self.unify(
+                    tipo.clone(),
+                    left.tipo(),
+                    left.type_defining_location(),
+                    false,
+                )
+                .map_err(|e| e.operator_situation(name))?;
+
+                self.unify(
+                    tipo.clone(),
+                    right.tipo(),
+                    right.type_defining_location(),
+                    false,
+                )
+                .map_err(|e| e.operator_situation(name))?;
+
+                return Ok(TypedExpr::BinOp {
+                    location,
+                    name,
+                    tipo,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                });
+            }
             BinOp::And => (bool(), bool()),
             BinOp::Or => (bool(), bool()),
             BinOp::LtInt => (int(), bool()),
@@ -1638,8 +1675,10 @@ impl<'a, 'b> ExprTyper<'a, 'b> {
         let expressions = sequence
             .into_iter()
             .filter(|expr| {
+                // Discarded assignments are kept around, so that any trace or error
+                // in their value still happens at runtime.
                 if let TypedExpr::Assignment { pattern, .. } = expr {
-                    !unused.contains(&pattern.location())
+                    pattern.is_discard() || !unused.contains(&pattern.location())
                 } else {
                     true
                 }
@@ -16,7 +16,7 @@ use super::{
 };
 use crate::{
     ast::{CallArg, Pattern, Span, TypedPattern, UntypedPattern},
-    builtins::{int, list, tuple},
+    builtins::{byte_array, int, list, string, tuple},
 };
 
 pub struct PatternTyper<'a, 'b> {
@@ -188,6 +188,27 @@ impl<'a, 'b> PatternTyper<'a, 'b> {
                 Ok(Pattern::Int { location, value })
             }
 
+            Pattern::ByteArray {
+                location,
+                value,
+                preferred_format,
+            } => {
+                self.environment
+                    .unify(tipo, byte_array(), location, false)?;
+
+                Ok(Pattern::ByteArray {
+                    location,
+                    value,
+                    preferred_format,
+                })
+            }
+
+            Pattern::String { location, value } => {
+                self.environment.unify(tipo, string(), location, false)?;
+
+                Ok(Pattern::String { location, value })
+            }
+
             Pattern::List {
                 location,
                 elements,
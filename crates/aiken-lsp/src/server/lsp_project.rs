@@ -1,7 +1,10 @@
 use std::{collections::HashMap, path::PathBuf};
 
 use aiken_lang::ast::Tracing;
-use aiken_project::{config::Config, error::Error as ProjectError, module::CheckedModule, Project};
+use aiken_project::{
+    config::Config, error::Error as ProjectError, module::CheckedModule, options::CheckOptions,
+    Project,
+};
 
 use crate::line_numbers::LineNumbers;
 
@@ -32,9 +35,11 @@ impl LspProject {
     pub fn compile(&mut self) -> Result<(), Vec<ProjectError>> {
         let checkpoint = self.project.checkpoint();
 
-        let result = self
-            .project
-            .check(true, None, false, false, Tracing::NoTraces);
+        let result = self.project.check(CheckOptions {
+            skip_tests: true,
+            tracing: Tracing::NoTraces,
+            ..CheckOptions::default()
+        });
 
         self.project.restore(checkpoint);
 
@@ -201,6 +201,66 @@ mod test {
         );
     }
 
+    #[test]
+    fn serialize_preamble_from_config() {
+        let config: Config = toml::from_str(
+            r#"
+            name = "aiken-lang/foo"
+            version = "1.0.0"
+            license = "Apache-2.0"
+            description = "Lorem ipsum"
+            "#,
+        )
+        .unwrap();
+
+        let blueprint = Blueprint {
+            preamble: Preamble::from(&config),
+            validators: vec![],
+            definitions: Definitions::new(),
+        };
+        assert_eq!(
+            serde_json::to_value(&blueprint).unwrap(),
+            json!({
+                "preamble": {
+                    "title": "aiken-lang/foo",
+                    "description": "Lorem ipsum",
+                    "version": "1.0.0",
+                    "plutusVersion": "v2",
+                    "license": "Apache-2.0"
+                },
+                "validators": []
+            }),
+        );
+    }
+
+    #[test]
+    fn serialize_preamble_from_minimal_config() {
+        let config: Config = toml::from_str(
+            r#"
+            name = "aiken-lang/foo"
+            version = "1.0.0"
+            "#,
+        )
+        .unwrap();
+
+        let blueprint = Blueprint {
+            preamble: Preamble::from(&config),
+            validators: vec![],
+            definitions: Definitions::new(),
+        };
+        assert_eq!(
+            serde_json::to_value(&blueprint).unwrap(),
+            json!({
+                "preamble": {
+                    "title": "aiken-lang/foo",
+                    "version": "1.0.0",
+                    "plutusVersion": "v2"
+                },
+                "validators": []
+            }),
+        );
+    }
+
     #[test]
     fn serialize_with_definitions() {
         let mut definitions = Definitions::new();
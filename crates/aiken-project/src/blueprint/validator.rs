@@ -183,110 +183,13 @@ mod test {
         },
         *,
     };
-    use crate::{module::ParsedModule, PackageName};
-    use aiken_lang::{
-        self,
-        ast::{ModuleKind, Tracing, TypedDataType, TypedFunction},
-        builtins,
-        gen_uplc::builder::{DataTypeKey, FunctionAccessKey},
-        parser,
-        tipo::TypeInfo,
-        IdGenerator,
-    };
+    use crate::tests::TestProject;
+    use aiken_lang::{self, builtins};
     use assert_json_diff::assert_json_eq;
-    use indexmap::IndexMap;
     use serde_json::{self, json};
-    use std::{collections::HashMap, path::PathBuf};
+    use std::collections::HashMap;
     use uplc::ast as uplc;
 
-    // TODO: Possible refactor this out of the module and have it used by `Project`. The idea would
-    // be to make this struct below the actual project, and wrap it in another metadata struct
-    // which contains all the config and I/O stuff regarding the project.
-    struct TestProject {
-        package: PackageName,
-        id_gen: IdGenerator,
-        module_types: HashMap<String, TypeInfo>,
-        functions: IndexMap<FunctionAccessKey, TypedFunction>,
-        data_types: IndexMap<DataTypeKey, TypedDataType>,
-    }
-
-    impl TestProject {
-        fn new() -> Self {
-            let id_gen = IdGenerator::new();
-
-            let package = PackageName {
-                owner: "test".to_owned(),
-                repo: "project".to_owned(),
-            };
-
-            let mut module_types = HashMap::new();
-            module_types.insert("aiken".to_string(), builtins::prelude(&id_gen));
-            module_types.insert("aiken/builtin".to_string(), builtins::plutus(&id_gen));
-
-            let functions = builtins::prelude_functions(&id_gen);
-            let data_types = builtins::prelude_data_types(&id_gen);
-
-            TestProject {
-                package,
-                id_gen,
-                module_types,
-                functions,
-                data_types,
-            }
-        }
-
-        fn parse(&self, source_code: &str) -> ParsedModule {
-            let kind = ModuleKind::Validator;
-            let name = "test_module".to_owned();
-            let (mut ast, extra) =
-                parser::module(source_code, kind).expect("Failed to parse module");
-            ast.name = name.clone();
-
-            ParsedModule {
-                kind,
-                ast,
-                code: source_code.to_string(),
-                name,
-                path: PathBuf::new(),
-                extra,
-                package: self.package.to_string(),
-            }
-        }
-
-        fn check(&mut self, module: ParsedModule) -> CheckedModule {
-            let mut warnings = vec![];
-
-            let ast = module
-                .ast
-                .infer(
-                    &self.id_gen,
-                    module.kind,
-                    &self.package.to_string(),
-                    &self.module_types,
-                    Tracing::NoTraces,
-                    &mut warnings,
-                )
-                .expect("Failed to type-check module");
-
-            self.module_types
-                .insert(module.name.clone(), ast.type_info.clone());
-
-            let mut checked_module = CheckedModule {
-                kind: module.kind,
-                extra: module.extra,
-                name: module.name,
-                code: module.code,
-                package: module.package,
-                input_path: module.path,
-                ast,
-            };
-
-            checked_module.attach_doc_and_module_comments();
-
-            checked_module
-        }
-    }
-
     fn assert_validator(source_code: &str, expected: serde_json::Value) {
         let mut project = TestProject::new();
 
@@ -412,6 +315,42 @@ mod test {
         );
     }
 
+    #[test]
+    fn spend_always_true() {
+        assert_validator(
+            r#"
+            validator {
+              fn spend(_datum: Data, _redeemer: Data, _ctx: Data) -> Bool {
+                True
+              }
+            }
+            "#,
+            json!({
+              "title": "test_module.spend",
+              "datum": {
+                "title": "_datum",
+                "schema": {
+                  "$ref": "#/definitions/Data"
+                }
+              },
+              "redeemer": {
+                "title": "_redeemer",
+                "schema": {
+                  "$ref": "#/definitions/Data"
+                }
+              },
+              "compiledCode": "500100003222253330044a22930b2b9a01",
+              "hash": "90592520b329fe08f0e93946ecd405e49b7480795e27cb618f002d88",
+              "definitions": {
+                "Data": {
+                  "title": "Data",
+                  "description": "Any Plutus data."
+                }
+              }
+            }),
+        );
+    }
+
     #[test]
     fn mint_parameterized() {
         assert_validator(
@@ -918,6 +857,75 @@ mod test {
         );
     }
 
+    #[test]
+    fn record_datum() {
+        assert_validator(
+            r#"
+            pub type Datum {
+              owner: ByteArray,
+              amount: Int,
+            }
+
+            validator {
+              fn record_datum(datum: Datum, redeemer: List<Int>, ctx: Void) {
+                True
+              }
+            }
+            "#,
+            json!({
+              "title": "test_module.record_datum",
+              "datum": {
+                "title": "datum",
+                "schema": {
+                  "$ref": "#/definitions/test_module~1Datum"
+                }
+              },
+              "redeemer": {
+                "title": "redeemer",
+                "schema": {
+                  "$ref": "#/definitions/List$Int"
+                }
+              },
+              "compiledCode": "5901240100003232323232323232323232322223232533300a4a22930b1980519299980519b87480000044c8c8c8c94ccc04cc05400852615330104901334c6973742f5475706c652f436f6e73747220636f6e7461696e73206d6f7265206974656d73207468616e2065787065637465640016375a602600260260046eb8c044004c038dd50028a9980624812b436f6e73747220696e64657820646964206e6f74206d6174636820616e7920747970652076617269616e740016300c375400800864660146600a002464931bad00100137580046002002444a66601600429309980398009806001199801801980680100099800800a40004444666600a66e1c00400c02c8cccc014014cdc000224004601a002004004ae695cdab9c5573aaae7955cfaba157441",
+              "hash": "628a85aaf77057e0466d23e4c5c528f367c8ede498de7df22c3d9b48",
+              "definitions": {
+                "ByteArray": {
+                  "dataType": "bytes"
+                },
+                "Int": {
+                  "dataType": "integer"
+                },
+                "List$Int": {
+                  "dataType": "list",
+                  "items": {
+                    "$ref": "#/definitions/Int"
+                  }
+                },
+                "test_module/Datum": {
+                  "title": "Datum",
+                  "anyOf": [
+                    {
+                      "title": "Datum",
+                      "dataType": "constructor",
+                      "index": 0,
+                      "fields": [
+                        {
+                          "title": "owner",
+                          "$ref": "#/definitions/ByteArray"
+                        },
+                        {
+                          "title": "amount",
+                          "$ref": "#/definitions/Int"
+                        }
+                      ]
+                    }
+                  ]
+                }
+              }
+            }),
+        );
+    }
+
     #[test]
     fn recursive_types() {
         assert_validator(
@@ -6,6 +6,7 @@ use std::{fmt::Display, fs, io, path::Path};
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Config {
+    #[serde(deserialize_with = "PackageName::deserialize_project_name")]
     pub name: PackageName,
     pub version: String,
     pub license: Option<String>,
@@ -14,6 +15,9 @@ pub struct Config {
     pub repository: Option<Repository>,
     #[serde(default)]
     pub dependencies: Vec<Dependency>,
+    /// UPLC version of the generated programs, e.g. `uplc_version = [1, 0, 0]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uplc_version: Option<(usize, usize, usize)>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -55,11 +59,15 @@ impl Config {
             version: "0.0.0".to_string(),
             license: Some("Apache-2.0".to_string()),
             description: format!("Aiken contracts for project '{name}'"),
-            repository: Some(Repository {
-                user: name.owner.clone(),
-                project: name.repo.clone(),
-                platform: Platform::Github,
-            }),
+            repository: if name.owner.is_empty() {
+                None
+            } else {
+                Some(Repository {
+                    user: name.owner.clone(),
+                    project: name.repo.clone(),
+                    platform: Platform::Github,
+                })
+            },
             dependencies: vec![Dependency {
                 name: PackageName {
                     owner: "aiken-lang".to_string(),
@@ -68,6 +76,7 @@ impl Config {
                 version: "main".to_string(),
                 source: Platform::Github,
             }],
+            uplc_version: None,
         }
     }
 
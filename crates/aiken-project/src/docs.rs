@@ -521,6 +521,13 @@ struct DocTypeConstructor {
     definition: String,
     documentation: String,
     raw_documentation: String,
+    arguments: Vec<DocTypeConstructorArg>,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct DocTypeConstructorArg {
+    label: String,
+    documentation: String,
 }
 
 impl DocTypeConstructor {
@@ -535,6 +542,16 @@ impl DocTypeConstructor {
                 .map(render_markdown)
                 .unwrap_or_default(),
             raw_documentation: constructor.doc.as_deref().unwrap_or_default().to_string(),
+            arguments: constructor
+                .arguments
+                .iter()
+                .filter_map(|argument| {
+                    Some(DocTypeConstructorArg {
+                        label: argument.label.clone()?,
+                        documentation: render_markdown(argument.doc.as_deref()?),
+                    })
+                })
+                .collect(),
         }
     }
 }
@@ -737,3 +754,58 @@ fn to_breadcrumbs_test() {
     assert_eq!(to_breadcrumbs("a/b"), "..");
     assert_eq!(to_breadcrumbs("a/b/c"), "../..");
 }
+
+#[test]
+fn generate_all_renders_documented_definitions() {
+    use crate::tests::TestProject;
+    use aiken_lang::ast::ModuleKind;
+
+    let mut project = TestProject::new();
+
+    let module = project.check(project.parse_module(
+        "foo/bar",
+        ModuleKind::Lib,
+        r#"
+/// Double an **integer**.
+pub fn double(n: Int) -> Int {
+  n * 2
+}
+
+/// A point on a line.
+pub type Point {
+  /// Somewhere on the line.
+  At {
+    /// Distance from the origin.
+    offset: Int,
+  }
+  Origin
+}
+"#,
+    ));
+
+    let config = Config::default(&project.package);
+
+    let files = generate_all(Path::new("."), &config, vec![&module]);
+
+    let page = files
+        .iter()
+        .find(|file| file.path == Path::new("foo/bar.html"))
+        .unwrap_or_else(|| panic!("no page for foo/bar in {files:?}"));
+
+    for expected in [
+        "double",
+        "Double an <strong>integer</strong>.",
+        "Point",
+        "A point on a line.",
+        "At",
+        "Somewhere on the line.",
+        "Distance from the origin.",
+        "Origin",
+    ] {
+        assert!(page.content.contains(expected), "{expected}");
+    }
+
+    assert!(files
+        .iter()
+        .any(|file| file.path == Path::new("index.html")));
+}
@@ -99,6 +99,15 @@ pub enum Error {
         evaluation_hint: Option<EvalHint>,
     },
 
+    #[error("{name} went over its execution budget")]
+    TestOverBudget {
+        name: String,
+        path: PathBuf,
+        spent_budget: ExBudget,
+        max_mem: Option<i64>,
+        max_cpu: Option<i64>,
+    },
+
     #[error(
         "I was unable to resolve '{}' for {}/{}",
         package.version,
@@ -117,6 +126,17 @@ pub enum Error {
 
     #[error("I found multiple suitable validators and I need you to tell me which one to pick.")]
     MoreThanOneValidatorFound { known_validators: Vec<String> },
+
+    #[error("I found a '{purpose}' validator taking {found} argument(s) instead of {expected}.")]
+    MalformedValidator {
+        purpose: String,
+        expected: usize,
+        found: usize,
+        path: PathBuf,
+        src: String,
+        named: NamedSource,
+        location: Span,
+    },
 }
 
 impl Error {
@@ -184,6 +204,7 @@ impl GetSource for Error {
             Error::Parse { path, .. } => Some(path.to_path_buf()),
             Error::Type { path, .. } => Some(path.to_path_buf()),
             Error::TestFailure { path, .. } => Some(path.to_path_buf()),
+            Error::TestOverBudget { path, .. } => Some(path.to_path_buf()),
             Error::Http(_) => None,
             Error::ZipExtract(_) => None,
             Error::JoinError(_) => None,
@@ -192,6 +213,7 @@ impl GetSource for Error {
             Error::MalformedStakeAddress { .. } => None,
             Error::NoValidatorNotFound { .. } => None,
             Error::MoreThanOneValidatorFound { .. } => None,
+            Error::MalformedValidator { path, .. } => Some(path.to_path_buf()),
             Error::Module { .. } => None,
         }
     }
@@ -209,6 +231,7 @@ impl GetSource for Error {
             Error::Parse { src, .. } => Some(src.to_string()),
             Error::Type { src, .. } => Some(src.to_string()),
             Error::TestFailure { .. } => None,
+            Error::TestOverBudget { .. } => None,
             Error::Http(_) => None,
             Error::ZipExtract(_) => None,
             Error::JoinError(_) => None,
@@ -217,6 +240,7 @@ impl GetSource for Error {
             Error::MalformedStakeAddress { .. } => None,
             Error::NoValidatorNotFound { .. } => None,
             Error::MoreThanOneValidatorFound { .. } => None,
+            Error::MalformedValidator { src, .. } => Some(src.to_string()),
             Error::Module { .. } => None,
         }
     }
@@ -243,6 +267,7 @@ impl Diagnostic for Error {
             Error::TomlLoading { .. } => Some(Box::new("aiken::loading::toml")),
             Error::Format { .. } => None,
             Error::TestFailure { path, .. } => Some(Box::new(path.to_str().unwrap_or(""))),
+            Error::TestOverBudget { path, .. } => Some(Box::new(path.to_str().unwrap_or(""))),
             Error::Http(_) => Some(Box::new("aiken::packages::download")),
             Error::ZipExtract(_) => None,
             Error::JoinError(_) => None,
@@ -251,6 +276,7 @@ impl Diagnostic for Error {
             Error::MalformedStakeAddress { .. } => None,
             Error::NoValidatorNotFound { .. } => None,
             Error::MoreThanOneValidatorFound { .. } => None,
+            Error::MalformedValidator { .. } => Some(Box::new("aiken::check::validator")),
             Error::Module(e) => e.code(),
         }
     }
@@ -265,8 +291,8 @@ impl Diagnostic for Error {
             Error::FileIo { error, .. } => Some(Box::new(format!("{error}"))),
             Error::Blueprint(e) => e.help(),
             Error::ImportCycle { modules } => Some(Box::new(format!(
-                "Try moving the shared code to a separate module that the others can depend on\n- {}",
-                modules.join("\n- ")
+                "Try moving the shared code to a separate module that the others can depend on\n\n{}",
+                modules.join(" -> ")
             ))),
             Error::Parse { error, .. } => error.kind.help(),
             Error::Type { error, .. } => error.help(),
@@ -337,6 +363,17 @@ impl Diagnostic for Error {
                         .join("\n")
                 )))
             },
+            Error::TestOverBudget { spent_budget, max_mem, max_cpu, .. } => Some(Box::new(format!(
+                "It spent {} memory units and {} CPU units, whereas at most {} memory units and {} CPU units are allowed.",
+                spent_budget.mem,
+                spent_budget.cpu,
+                max_mem.map(|mem| mem.to_string()).unwrap_or_else(|| "any".to_string()),
+                max_cpu.map(|cpu| cpu.to_string()).unwrap_or_else(|| "any".to_string()),
+            ))),
+            Error::MalformedValidator { purpose, expected, .. } => Some(Box::new(format!(
+                "A {purpose} validator takes {expected} arguments: {}. Either change its arguments, or rename it.",
+                if *expected == 3 { "a datum, a redeemer and a script context" } else { "a redeemer and a script context" }
+            ))),
             Error::Module(e) => e.help(),
         }
     }
@@ -362,6 +399,7 @@ impl Diagnostic for Error {
             }
             Error::Format { .. } => None,
             Error::TestFailure { .. } => None,
+            Error::TestOverBudget { .. } => None,
             Error::Http(_) => None,
             Error::ZipExtract(_) => None,
             Error::JoinError(_) => None,
@@ -370,6 +408,9 @@ impl Diagnostic for Error {
             Error::MalformedStakeAddress { .. } => None,
             Error::NoValidatorNotFound { .. } => None,
             Error::MoreThanOneValidatorFound { .. } => None,
+            Error::MalformedValidator { location, .. } => Some(Box::new(
+                vec![LabeledSpan::new_with_span(None, *location)].into_iter(),
+            )),
             Error::Module(e) => e.labels(),
         }
     }
@@ -387,6 +428,7 @@ impl Diagnostic for Error {
             Error::TomlLoading { named, .. } => Some(named.deref()),
             Error::Format { .. } => None,
             Error::TestFailure { .. } => None,
+            Error::TestOverBudget { .. } => None,
             Error::Http(_) => None,
             Error::ZipExtract(_) => None,
             Error::JoinError(_) => None,
@@ -395,6 +437,7 @@ impl Diagnostic for Error {
             Error::MalformedStakeAddress { .. } => None,
             Error::NoValidatorNotFound { .. } => None,
             Error::MoreThanOneValidatorFound { .. } => None,
+            Error::MalformedValidator { named, .. } => Some(named),
             Error::Module(e) => e.source_code(),
         }
     }
@@ -412,6 +455,7 @@ impl Diagnostic for Error {
             Error::TomlLoading { .. } => None,
             Error::Format { .. } => None,
             Error::TestFailure { .. } => None,
+            Error::TestOverBudget { .. } => None,
             Error::Http { .. } => None,
             Error::ZipExtract { .. } => None,
             Error::JoinError { .. } => None,
@@ -420,6 +464,7 @@ impl Diagnostic for Error {
             Error::MalformedStakeAddress { .. } => None,
             Error::NoValidatorNotFound { .. } => None,
             Error::MoreThanOneValidatorFound { .. } => None,
+            Error::MalformedValidator { .. } => None,
             Error::Module(e) => e.url(),
         }
     }
@@ -437,6 +482,7 @@ impl Diagnostic for Error {
             Error::TomlLoading { .. } => None,
             Error::Format { .. } => None,
             Error::TestFailure { .. } => None,
+            Error::TestOverBudget { .. } => None,
             Error::Http { .. } => None,
             Error::ZipExtract { .. } => None,
             Error::JoinError { .. } => None,
@@ -445,6 +491,7 @@ impl Diagnostic for Error {
             Error::MalformedStakeAddress { .. } => None,
             Error::NoValidatorNotFound { .. } => None,
             Error::MoreThanOneValidatorFound { .. } => None,
+            Error::MalformedValidator { .. } => None,
             Error::Module(e) => e.related(),
         }
     }
@@ -12,23 +12,31 @@ pub mod pretty;
 pub mod script;
 pub mod telemetry;
 
+#[cfg(test)]
+mod tests;
+
 use crate::blueprint::Blueprint;
 use aiken_lang::{
     ast::{Definition, Function, ModuleKind, Tracing, TypedDataType, TypedFunction},
     builtins,
-    gen_uplc::builder::{DataTypeKey, FunctionAccessKey},
-    tipo::TypeInfo,
+    gen_uplc::{
+        self,
+        builder::{DataTypeKey, FunctionAccessKey},
+    },
+    tipo::{self, TypeInfo},
     IdGenerator,
 };
 use deps::UseManifest;
 use indexmap::IndexMap;
 use miette::NamedSource;
-use options::{CodeGenMode, Options};
+use options::{CheckOptions, CodeGenMode, Options};
 use package_name::PackageName;
+use pallas::crypto::hash::{Hash, Hasher};
 use pallas::ledger::addresses::{
     Address, Network, ShelleyAddress, ShelleyDelegationPart, StakePayload,
 };
 use script::{EvalHint, EvalInfo, Script};
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     fs::{self, File},
@@ -37,15 +45,15 @@ use std::{
 };
 use telemetry::EventListener;
 use uplc::{
-    ast::{DeBruijn, Term},
+    ast::{DeBruijn, Name, Program, Term},
     machine::cost_model::ExBudget,
 };
 
 use crate::{
     config::Config,
-    error::{Error, Warning},
+    error::{Error, GetSource, Warning},
     module::{CheckedModule, CheckedModules, ParsedModule, ParsedModules},
-    telemetry::Event,
+    telemetry::{Event, ValidatorSize},
 };
 
 #[derive(Debug)]
@@ -61,6 +69,19 @@ pub struct Checkpoint {
     defined_modules: HashMap<String, PathBuf>,
 }
 
+/// What a module was last type-checked from, and the warnings it raised. The
+/// checked module itself stays in `checked_modules`, and is reused as long as
+/// neither its source nor any module it imports changes.
+///
+/// Both are also written under `build/`, so that the next run can reuse the
+/// module too, without even parsing it again.
+#[derive(Serialize, Deserialize)]
+struct CachedModule {
+    fingerprint: Hash<32>,
+    dependencies: Vec<String>,
+    warnings: Vec<tipo::error::Warning>,
+}
+
 pub struct Project<T>
 where
     T: EventListener,
@@ -76,6 +97,8 @@ where
     event_listener: T,
     functions: IndexMap<FunctionAccessKey, TypedFunction>,
     data_types: IndexMap<DataTypeKey, TypedDataType>,
+    module_cache: HashMap<String, CachedModule>,
+    unparsed_modules: Vec<String>,
 }
 
 impl<T> Project<T>
@@ -114,6 +137,8 @@ where
             event_listener,
             functions,
             data_types,
+            module_cache: HashMap::new(),
+            unparsed_modules: vec![],
         }
     }
 
@@ -141,9 +166,21 @@ where
         self.defined_modules = checkpoint.defined_modules;
     }
 
-    pub fn build(&mut self, uplc: bool, tracing: Tracing) -> Result<(), Vec<Error>> {
+    pub fn build(
+        &mut self,
+        uplc: bool,
+        uplc_json: bool,
+        dual: bool,
+        uplc_dir: Option<PathBuf>,
+        tracing: Tracing,
+    ) -> Result<(), Vec<Error>> {
         let options = Options {
-            code_gen_mode: CodeGenMode::Build(uplc),
+            code_gen_mode: CodeGenMode::Build {
+                uplc,
+                uplc_json,
+                dual,
+                uplc_dir,
+            },
             tracing,
         };
 
@@ -192,11 +229,16 @@ where
 
     pub fn check(
         &mut self,
-        skip_tests: bool,
-        match_tests: Option<Vec<String>>,
-        verbose: bool,
-        exact_match: bool,
-        tracing: Tracing,
+        CheckOptions {
+            skip_tests,
+            match_tests,
+            debug,
+            verbose,
+            exact_match,
+            max_mem,
+            max_cpu,
+            tracing,
+        }: CheckOptions,
     ) -> Result<(), Vec<Error>> {
         let options = Options {
             tracing,
@@ -205,8 +247,11 @@ where
             } else {
                 CodeGenMode::Test {
                     match_tests,
+                    debug,
                     verbose,
                     exact_match,
+                    max_mem,
+                    max_cpu,
                 }
             },
         };
@@ -214,16 +259,15 @@ where
         self.compile(options)
     }
 
-    pub fn dump_uplc(&self, blueprint: &Blueprint) -> Result<(), Error> {
-        let dir = self.root.join("artifacts");
-
-        self.event_listener
-            .handle_event(Event::DumpingUPLC { path: dir.clone() });
+    pub fn dump_uplc(&self, blueprint: &Blueprint, dir: &Path) -> Result<(), Error> {
+        self.event_listener.handle_event(Event::DumpingUPLC {
+            path: dir.to_path_buf(),
+        });
 
-        fs::create_dir_all(&dir)?;
+        fs::create_dir_all(dir)?;
 
         for validator in &blueprint.validators {
-            let path = dir.clone().join(format!("{}.uplc", validator.title));
+            let path = dir.join(format!("{}.uplc", validator.title));
 
             fs::write(&path, validator.program.to_pretty())
                 .map_err(|error| Error::FileIo { error, path })?;
@@ -232,6 +276,50 @@ where
         Ok(())
     }
 
+    pub fn dump_uplc_json(&self, blueprint: &Blueprint, dir: &Path) -> Result<(), Error> {
+        self.event_listener.handle_event(Event::DumpingUPLCJson {
+            path: dir.to_path_buf(),
+        });
+
+        fs::create_dir_all(dir)?;
+
+        for validator in &blueprint.validators {
+            let path = dir.join(format!("{}.uplc.json", validator.title));
+
+            let program: Program<Name> = validator.program.clone().try_into().unwrap();
+
+            let json = serde_json::to_string_pretty(&program.to_json()).unwrap();
+
+            fs::write(&path, json).map_err(|error| Error::FileIo { error, path })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn dump_dual_uplc(
+        &self,
+        optimized: &Blueprint,
+        unoptimized: &Blueprint,
+        dir: &Path,
+    ) -> Result<(), Error> {
+        self.event_listener.handle_event(Event::DumpingUPLC {
+            path: dir.to_path_buf(),
+        });
+
+        fs::create_dir_all(dir)?;
+
+        for (suffix, blueprint) in [("optimized", optimized), ("unoptimized", unoptimized)] {
+            for validator in &blueprint.validators {
+                let path = dir.join(format!("{}.{suffix}.uplc", validator.title));
+
+                fs::write(&path, validator.program.to_pretty())
+                    .map_err(|error| Error::FileIo { error, path })?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn blueprint_path(&self) -> PathBuf {
         self.root.join("plutus.json")
     }
@@ -252,8 +340,17 @@ where
 
         self.type_check(parsed_modules, options.tracing, true)?;
 
+        self.checked_modules.check_validator_arity()?;
+
         match options.code_gen_mode {
-            CodeGenMode::Build(uplc_dump) => {
+            CodeGenMode::Build {
+                uplc,
+                uplc_json,
+                dual,
+                uplc_dir,
+            } => {
+                let artifacts = self.root.join(uplc_dir.unwrap_or_else(paths::uplc));
+
                 self.event_listener
                     .handle_event(Event::GeneratingBlueprint {
                         path: self.blueprint_path(),
@@ -265,6 +362,10 @@ where
                     &self.module_types,
                 );
 
+                if let Some(version) = self.config.uplc_version {
+                    generator.set_version(version);
+                }
+
                 let blueprint = Blueprint::new(&self.config, &self.checked_modules, &mut generator)
                     .map_err(Error::Blueprint)?;
 
@@ -272,8 +373,43 @@ where
                     self.warnings.push(Warning::NoValidators);
                 }
 
-                if uplc_dump {
-                    self.dump_uplc(&blueprint)?;
+                self.event_listener
+                    .handle_event(Event::ReportingValidatorSizes {
+                        validators: blueprint
+                            .validators
+                            .iter()
+                            .map(|validator| {
+                                let program: Program<Name> = validator
+                                    .program
+                                    .clone()
+                                    .try_into()
+                                    .expect("compiled validators have no free variables");
+
+                                ValidatorSize {
+                                    title: validator.title.clone(),
+                                    term_size: gen_uplc::term_size(&program),
+                                    serialized_size: gen_uplc::serialized_size(&program),
+                                }
+                            })
+                            .collect(),
+                    });
+
+                if uplc {
+                    self.dump_uplc(&blueprint, &artifacts)?;
+                }
+
+                if uplc_json {
+                    self.dump_uplc_json(&blueprint, &artifacts)?;
+                }
+
+                if dual {
+                    generator.set_optimize(false);
+
+                    let unoptimized =
+                        Blueprint::new(&self.config, &self.checked_modules, &mut generator)
+                            .map_err(Error::Blueprint)?;
+
+                    self.dump_dual_uplc(&blueprint, &unoptimized, &artifacts)?;
                 }
 
                 let json = serde_json::to_string_pretty(&blueprint).unwrap();
@@ -288,36 +424,53 @@ where
             }
             CodeGenMode::Test {
                 match_tests,
+                debug,
                 verbose,
                 exact_match,
+                max_mem,
+                max_cpu,
             } => {
-                let tests = self.collect_tests(verbose, match_tests, exact_match)?;
+                let tests = self.collect_tests(debug, match_tests, exact_match)?;
 
                 if !tests.is_empty() {
                     self.event_listener.handle_event(Event::RunningTests);
                 }
 
-                let results = self.eval_scripts(tests);
+                let mut results = self.eval_scripts(tests);
 
                 let errors: Vec<Error> = results
                     .iter()
                     .filter_map(|e| {
-                        if e.success {
-                            None
-                        } else {
+                        if !e.success {
                             Some(Error::TestFailure {
                                 name: e.script.name.clone(),
                                 path: e.script.input_path.clone(),
                                 evaluation_hint: e.script.evaluation_hint.clone(),
                                 src: e.script.program.to_pretty(),
-                                verbose,
+                                verbose: debug,
+                            })
+                        } else if e.exceeds(max_mem, max_cpu) {
+                            Some(Error::TestOverBudget {
+                                name: e.script.name.clone(),
+                                path: e.script.input_path.clone(),
+                                spent_budget: e.spent_budget,
+                                max_mem,
+                                max_cpu,
                             })
+                        } else {
+                            None
                         }
                     })
                     .collect();
 
-                self.event_listener
-                    .handle_event(Event::FinishedTests { tests: results });
+                for result in results.iter_mut() {
+                    result.success = result.success && !result.exceeds(max_mem, max_cpu);
+                }
+
+                self.event_listener.handle_event(Event::FinishedTests {
+                    tests: results,
+                    verbose,
+                });
 
                 if !errors.is_empty() {
                     Err(errors)
@@ -458,46 +611,72 @@ where
     }
 
     fn parse_sources(&mut self, package_name: PackageName) -> Result<ParsedModules, Vec<Error>> {
+        use rayon::prelude::*;
+
+        let package = package_name.to_string();
+
+        self.unparsed_modules.clear();
+
+        let sources: Vec<_> = std::mem::take(&mut self.sources)
+            .into_iter()
+            .map(|source| {
+                let unchanged = self.is_unchanged(&source, &package);
+                (source, unchanged)
+            })
+            .collect();
+
+        // Sources are parsed concurrently, but results are collected in their
+        // original order so that duplicates are reported the same way each time.
+        let parsed_sources: Vec<_> = sources
+            .into_par_iter()
+            .map(|(source, unchanged)| {
+                let result =
+                    (!unchanged).then(|| aiken_lang::parser::module(&source.code, source.kind));
+                (source, result)
+            })
+            .collect();
+
         let mut errors = Vec::new();
-        let mut parsed_modules = HashMap::with_capacity(self.sources.len());
+        let mut parsed_modules = HashMap::with_capacity(parsed_sources.len());
 
-        for Source {
-            path,
-            name,
-            code,
-            kind,
-        } in self.sources.drain(0..)
+        for (
+            Source {
+                path,
+                name,
+                code,
+                kind,
+            },
+            result,
+        ) in parsed_sources
         {
-            match aiken_lang::parser::module(&code, kind) {
-                Ok((mut ast, extra)) => {
+            match result {
+                // Left to `type_check`, which only parses it again if it has to
+                // be checked again.
+                None => {
+                    self.define_module(&name, &path)?;
+
+                    self.unparsed_modules.push(name);
+                }
+                Some(Ok((mut ast, extra))) => {
                     // Store the name
                     ast.name = name.clone();
 
                     let module = ParsedModule {
                         kind,
                         ast,
+                        hash: module::content_hash(&code),
                         code,
                         name,
                         path,
                         extra,
-                        package: package_name.to_string(),
+                        package: package.clone(),
                     };
 
-                    if let Some(first) = self
-                        .defined_modules
-                        .insert(module.name.clone(), module.path.clone())
-                    {
-                        return Err(Error::DuplicateModule {
-                            module: module.name.clone(),
-                            first,
-                            second: module.path,
-                        }
-                        .into());
-                    }
+                    self.define_module(&module.name, &module.path)?;
 
                     parsed_modules.insert(module.name.clone(), module);
                 }
-                Err(errs) => {
+                Some(Err(errs)) => {
                     for error in errs {
                         errors.push(Error::Parse {
                             path: path.clone(),
@@ -513,85 +692,320 @@ where
         if errors.is_empty() {
             Ok(parsed_modules.into())
         } else {
+            errors.sort_by_key(|error| error.path());
+
             Err(errors)
         }
     }
 
+    fn define_module(&mut self, name: &str, path: &Path) -> Result<(), Error> {
+        match self
+            .defined_modules
+            .insert(name.to_string(), path.to_path_buf())
+        {
+            Some(first) => Err(Error::DuplicateModule {
+                module: name.to_string(),
+                first,
+                second: path.to_path_buf(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a source is the one its module was last checked from, in which
+    /// case it isn't parsed until it has to be checked again. What was last
+    /// checked is looked up under `build/` if not known yet.
+    fn is_unchanged(&mut self, source: &Source, package: &str) -> bool {
+        if !self.module_cache.contains_key(&source.name) {
+            self.load_cached_module(package, &source.name);
+        }
+
+        self.module_cache.contains_key(&source.name)
+            && self
+                .checked_modules
+                .get(&source.name)
+                .is_some_and(|module| {
+                    module.hash == module::content_hash(&source.code)
+                        && module.kind == source.kind
+                        && module.package == package
+                        && module.input_path == source.path
+                })
+    }
+
+    fn load_cached_module(&mut self, package: &str, name: &str) {
+        let path = self.root.join(paths::module_cache(package, name));
+
+        // A cache that can't be read, e.g. one written by another version, is
+        // as good as none: the module is parsed and checked again.
+        let cached = fs::read(path)
+            .ok()
+            .and_then(|json| serde_json::from_slice::<(CachedModule, CheckedModule)>(&json).ok());
+
+        if let Some((cached, module)) = cached {
+            self.module_cache.insert(name.to_string(), cached);
+            self.checked_modules.insert(name.to_string(), module);
+        }
+    }
+
+    fn save_cached_module(&self, name: &str) -> Result<(), Error> {
+        let (Some(cached), Some(module)) =
+            (self.module_cache.get(name), self.checked_modules.get(name))
+        else {
+            return Ok(());
+        };
+
+        let path = self.root.join(paths::module_cache(&module.package, name));
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|error| Error::FileIo {
+                error,
+                path: parent.to_path_buf(),
+            })?;
+        }
+
+        let json = serde_json::to_string(&(cached, module))?;
+
+        fs::write(&path, json).map_err(|error| Error::FileIo { error, path })
+    }
+
+    /// Type-check modules in dependency order, and return the names of those
+    /// that were actually checked. A module whose source, checking options and
+    /// imports are all unchanged since the last check is reused as-is, and one
+    /// that `parse_sources` skipped is only parsed if it has to be checked.
     fn type_check(
         &mut self,
         mut parsed_modules: ParsedModules,
         tracing: Tracing,
         validate_module_name: bool,
-    ) -> Result<(), Error> {
-        let processing_sequence = parsed_modules.sequence()?;
+    ) -> Result<Vec<String>, Error> {
+        let unparsed = std::mem::take(&mut self.unparsed_modules)
+            .into_iter()
+            .filter_map(|name| {
+                let dependencies = self.module_cache.get(&name)?.dependencies.clone();
+                Some((name, dependencies))
+            })
+            .collect();
+
+        let processing_sequence = parsed_modules.sequence_with(unparsed)?;
+
+        let mut checked = Vec::new();
 
         for name in processing_sequence {
-            if let Some(ParsedModule {
-                name,
-                path,
-                code,
-                kind,
-                extra,
-                package,
-                ast,
-            }) = parsed_modules.remove(&name)
-            {
-                let mut type_warnings = Vec::new();
-
-                let ast = ast
-                    .infer(
-                        &self.id_gen,
-                        kind,
-                        &self.config.name.to_string(),
-                        &self.module_types,
+            let (fingerprint, dependencies) = match parsed_modules.get(&name) {
+                Some(module) => {
+                    let (_, dependencies) = module.deps_for_graph();
+
+                    let fingerprint = self.fingerprint(
+                        &module.hash,
+                        module.kind,
+                        &module.package,
+                        &dependencies,
                         tracing,
-                        &mut type_warnings,
-                    )
-                    .map_err(|error| Error::Type {
-                        path: path.clone(),
-                        src: code.clone(),
-                        named: NamedSource::new(path.display().to_string(), code.clone()),
-                        error,
-                    })?;
+                        validate_module_name,
+                    );
 
-                if validate_module_name {
-                    ast.validate_module_name()?;
+                    (fingerprint, dependencies)
                 }
+                None => {
+                    let module = &self.checked_modules[&name];
 
-                // Register any warnings emitted as type warnings
-                let type_warnings = type_warnings
-                    .into_iter()
-                    .map(|w| Warning::from_type_warning(w, path.clone(), code.clone()));
+                    let dependencies = self.module_cache[&name].dependencies.clone();
 
-                self.warnings.extend(type_warnings);
+                    let fingerprint = self.fingerprint(
+                        &module.hash,
+                        module.kind,
+                        &module.package,
+                        &dependencies,
+                        tracing,
+                        validate_module_name,
+                    );
 
-                // Register the types from this module so they can be imported into
-                // other modules.
-                self.module_types
-                    .insert(name.clone(), ast.type_info.clone());
+                    (fingerprint, dependencies)
+                }
+            };
 
-                let mut checked_module = CheckedModule {
-                    kind,
-                    extra,
-                    name: name.clone(),
-                    code,
-                    ast,
-                    package,
-                    input_path: path,
-                };
+            let cached = self
+                .module_cache
+                .remove(&name)
+                .filter(|cached| cached.fingerprint == fingerprint)
+                .map(|cached| cached.warnings);
+
+            let reused = cached.is_some();
+
+            let (checked_module, type_warnings) = match cached {
+                Some(type_warnings) => {
+                    let checked_module = self
+                        .checked_modules
+                        .remove(&name)
+                        .expect("Could not find a cached module in the checked modules");
+
+                    (checked_module, type_warnings)
+                }
+                None => {
+                    let module = match parsed_modules.remove(&name) {
+                        Some(module) => module,
+                        None => self.reparse(&name)?,
+                    };
+
+                    let ParsedModule {
+                        name,
+                        path,
+                        code,
+                        kind,
+                        extra,
+                        package,
+                        ast,
+                        hash,
+                    } = module;
+
+                    let mut type_warnings = Vec::new();
+
+                    let ast = ast
+                        .infer(
+                            &self.id_gen,
+                            kind,
+                            &self.config.name.to_string(),
+                            &self.module_types,
+                            tracing,
+                            &mut type_warnings,
+                        )
+                        .map_err(|error| Error::Type {
+                            path: path.clone(),
+                            src: code.clone(),
+                            named: NamedSource::new(path.display().to_string(), code.clone()),
+                            error,
+                        })?;
+
+                    if validate_module_name {
+                        ast.validate_module_name()?;
+                    }
 
-                checked_module.attach_doc_and_module_comments();
+                    let mut checked_module = CheckedModule {
+                        kind,
+                        extra,
+                        name: name.clone(),
+                        code,
+                        ast,
+                        package,
+                        input_path: path,
+                        hash,
+                    };
 
-                self.checked_modules.insert(name, checked_module);
+                    checked_module.attach_doc_and_module_comments();
+
+                    checked.push(name);
+
+                    (checked_module, type_warnings)
+                }
+            };
+
+            // Register any warnings emitted as type warnings
+            self.warnings.extend(type_warnings.iter().map(|w| {
+                Warning::from_type_warning(
+                    w.clone(),
+                    checked_module.input_path.clone(),
+                    checked_module.code.clone(),
+                )
+            }));
+
+            // Register the types from this module so they can be imported into
+            // other modules.
+            self.module_types
+                .insert(name.clone(), checked_module.ast.type_info.clone());
+
+            self.module_cache.insert(
+                name.clone(),
+                CachedModule {
+                    fingerprint,
+                    dependencies,
+                    warnings: type_warnings,
+                },
+            );
+
+            self.checked_modules.insert(name.clone(), checked_module);
+
+            if !reused {
+                self.save_cached_module(&name)?;
             }
         }
 
-        Ok(())
+        Ok(checked)
+    }
+
+    /// Parse a module `parse_sources` skipped, back from the source it was last
+    /// checked from, since it has to be checked again, e.g. because a module it
+    /// imports changed.
+    fn reparse(&mut self, name: &str) -> Result<ParsedModule, Error> {
+        let CheckedModule {
+            name,
+            code,
+            input_path: path,
+            kind,
+            package,
+            hash,
+            ..
+        } = self
+            .checked_modules
+            .remove(name)
+            .expect("Could not find an unparsed module in the checked modules");
+
+        let (mut ast, extra) =
+            aiken_lang::parser::module(&code, kind).map_err(|mut errors| Error::Parse {
+                path: path.clone(),
+                src: code.clone(),
+                named: NamedSource::new(path.display().to_string(), code.clone()),
+                error: Box::new(errors.swap_remove(0)),
+            })?;
+
+        ast.name = name.clone();
+
+        Ok(ParsedModule {
+            kind,
+            ast,
+            hash,
+            code,
+            name,
+            path,
+            extra,
+            package,
+        })
+    }
+
+    /// What type-checking a module depends on: its source, how it's checked,
+    /// and the fingerprints of the modules it imports, which come first in the
+    /// processing sequence.
+    fn fingerprint(
+        &self,
+        hash: &Hash<32>,
+        kind: ModuleKind,
+        package: &str,
+        dependencies: &[String],
+        tracing: Tracing,
+        validate_module_name: bool,
+    ) -> Hash<32> {
+        let mut bytes = hash.to_vec();
+
+        bytes.push(kind.is_validator() as u8);
+        bytes.push(matches!(tracing, Tracing::KeepTraces) as u8);
+        bytes.push(validate_module_name as u8);
+        bytes.extend(package.as_bytes());
+
+        let mut dependencies = dependencies.to_vec();
+
+        dependencies.sort();
+
+        for dependency in dependencies {
+            if let Some(cached) = self.module_cache.get(&dependency) {
+                bytes.extend(cached.fingerprint.iter());
+            }
+        }
+
+        Hasher::<256>::hash(&bytes)
     }
 
     fn collect_tests(
         &mut self,
-        verbose: bool,
+        debug: bool,
         match_tests: Option<Vec<String>>,
         exact_match: bool,
     ) -> Result<Vec<Script>, Error> {
@@ -670,7 +1084,7 @@ where
         for (input_path, module_name, func_def) in scripts {
             let Function { name, body, .. } = func_def;
 
-            if verbose {
+            if debug {
                 self.event_listener.handle_event(Event::GeneratingUPLCFor {
                     name: name.clone(),
                     path: input_path.clone(),
@@ -683,6 +1097,10 @@ where
                 &self.module_types,
             );
 
+            if let Some(version) = self.config.uplc_version {
+                generator.set_version(version);
+            }
+
             let evaluation_hint = func_def.test_hint().map(|(bin_op, left_src, right_src)| {
                 let left = generator
                     .clone()
@@ -1,18 +1,23 @@
 use crate::error::Error;
 use aiken_lang::{
     ast::{
-        DataType, Definition, Located, ModuleKind, TypedDataType, TypedFunction, TypedModule,
-        TypedValidator, UntypedModule,
+        Annotation, ClauseGuard, DataType, Definition, Function, Located, ModuleConstant,
+        ModuleKind, Pattern, Span, TypeAlias, TypedDataType, TypedFunction, TypedModule,
+        TypedValidator, UntypedModule, Use,
     },
+    expr::UntypedExpr,
     gen_uplc::{
         builder::{DataTypeKey, FunctionAccessKey},
         CodeGenerator,
     },
-    parser::extra::{comments_before, Comment, ModuleExtra},
+    parser::extra::{comments_before, ModuleExtra, SourceIndex},
     tipo::TypeInfo,
 };
 use indexmap::IndexMap;
+use miette::NamedSource;
+use pallas::crypto::hash::{Hash, Hasher};
 use petgraph::{algo, graph::NodeIndex, Direction, Graph};
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
     ops::{Deref, DerefMut},
@@ -28,6 +33,12 @@ pub struct ParsedModule {
     pub package: String,
     pub ast: UntypedModule,
     pub extra: ModuleExtra,
+    pub hash: Hash<32>,
+}
+
+/// Blake2b-256 digest of a module's source code.
+pub fn content_hash(code: &str) -> Hash<32> {
+    Hasher::<256>::hash(code.as_bytes())
 }
 
 impl ParsedModule {
@@ -35,24 +46,354 @@ impl ParsedModule {
         let name = self.name.clone();
 
         let deps: Vec<_> = self
-            .ast
-            .dependencies()
+            .dependencies_with_spans()
             .into_iter()
             .map(|(dep, _span)| dep)
             .collect();
 
         (name, deps)
     }
+
+    /// Imported modules, each with the span of the `use` statement that
+    /// imports it, so that errors about an import can point right at it.
+    pub fn dependencies_with_spans(&self) -> Vec<(String, Span)> {
+        self.ast.dependencies()
+    }
+
+    /// Modules the tests of this module refer to, through a qualified or an
+    /// unqualified import. Names bound within the tests shadow imports.
+    pub fn test_dependencies(&self) -> Vec<String> {
+        let mut imports = Imports::default();
+
+        for def in self.ast.definitions() {
+            if let Definition::Use(import) = def {
+                imports.add(import);
+            }
+        }
+
+        for def in self.ast.definitions() {
+            if let Definition::Test(Function { body, .. }) = def {
+                imports.resolve_expr(body, &mut Vec::new());
+            }
+        }
+
+        imports.used
+    }
+}
+
+/// What a module's `use` statements bring in scope, and which of those
+/// imported modules were referred to so far.
+#[derive(Debug, Default)]
+struct Imports {
+    aliases: HashMap<String, String>,
+    unqualified: HashMap<String, String>,
+    used: Vec<String>,
+}
+
+impl Imports {
+    fn add<PackageName>(&mut self, import: &Use<PackageName>) {
+        let module = import.module.join("/");
+
+        if let Some(alias) = import.as_name.as_ref().or_else(|| import.module.last()) {
+            self.aliases.insert(alias.clone(), module.clone());
+        }
+
+        for unqualified in &import.unqualified {
+            self.unqualified
+                .insert(unqualified.variable_name().to_string(), module.clone());
+        }
+    }
+
+    fn mark(&mut self, module: Option<&String>) {
+        if let Some(module) = module {
+            if !self.used.contains(module) {
+                self.used.push(module.clone());
+            }
+        }
+    }
+
+    fn resolve_alias(&mut self, alias: &str) {
+        let module = self.aliases.get(alias).cloned();
+
+        self.mark(module.as_ref());
+    }
+
+    fn resolve_name(&mut self, name: &str, locals: &[String]) {
+        if !locals.iter().any(|local| local == name) {
+            let module = self.unqualified.get(name).cloned();
+
+            self.mark(module.as_ref());
+        }
+    }
+
+    /// Resolve the names an expression refers to, `locals` being the variables
+    /// bound around it. Bindings made by a `let` stay in `locals` so that the
+    /// rest of the enclosing sequence sees them.
+    fn resolve_expr(&mut self, expr: &UntypedExpr, locals: &mut Vec<String>) {
+        match expr {
+            UntypedExpr::Int { .. }
+            | UntypedExpr::String { .. }
+            | UntypedExpr::ByteArray { .. }
+            | UntypedExpr::ErrorTerm { .. } => {}
+            UntypedExpr::Var { name, .. } => self.resolve_name(name, locals),
+            UntypedExpr::Sequence { expressions, .. } => {
+                let scope = locals.len();
+
+                for expr in expressions {
+                    self.resolve_expr(expr, locals);
+                }
+
+                locals.truncate(scope);
+            }
+            UntypedExpr::PipeLine { expressions, .. } => {
+                for expr in expressions {
+                    self.resolve_scoped(expr, locals);
+                }
+            }
+            UntypedExpr::Fn {
+                arguments,
+                body,
+                return_annotation,
+                ..
+            } => {
+                for annotation in arguments
+                    .iter()
+                    .filter_map(|arg| arg.annotation.as_ref())
+                    .chain(return_annotation)
+                {
+                    self.resolve_annotation(annotation);
+                }
+
+                let scope = locals.len();
+
+                locals.extend(
+                    arguments
+                        .iter()
+                        .filter_map(|arg| arg.arg_name.get_variable_name())
+                        .map(str::to_string),
+                );
+
+                self.resolve_scoped(body, locals);
+
+                locals.truncate(scope);
+            }
+            UntypedExpr::List { elements, tail, .. } => {
+                for expr in elements.iter().chain(tail.as_deref()) {
+                    self.resolve_scoped(expr, locals);
+                }
+            }
+            UntypedExpr::Call { arguments, fun, .. } => {
+                self.resolve_scoped(fun, locals);
+
+                for arg in arguments {
+                    self.resolve_scoped(&arg.value, locals);
+                }
+            }
+            UntypedExpr::BinOp { left, right, .. } => {
+                self.resolve_scoped(left, locals);
+                self.resolve_scoped(right, locals);
+            }
+            UntypedExpr::Assignment {
+                value,
+                pattern,
+                annotation,
+                ..
+            } => {
+                self.resolve_scoped(value, locals);
+
+                if let Some(annotation) = annotation {
+                    self.resolve_annotation(annotation);
+                }
+
+                self.resolve_pattern(pattern, locals);
+            }
+            UntypedExpr::Trace { then, text, .. } => {
+                self.resolve_scoped(text, locals);
+                self.resolve_scoped(then, locals);
+            }
+            UntypedExpr::TraceIfFalse { value, .. } => self.resolve_scoped(value, locals),
+            UntypedExpr::When {
+                subject, clauses, ..
+            } => {
+                self.resolve_scoped(subject, locals);
+
+                for clause in clauses {
+                    let scope = locals.len();
+
+                    for pattern in clause.patterns.iter() {
+                        self.resolve_pattern(pattern, locals);
+                    }
+
+                    if let Some(guard) = &clause.guard {
+                        self.resolve_guard(guard, locals);
+                    }
+
+                    self.resolve_scoped(&clause.then, locals);
+
+                    locals.truncate(scope);
+                }
+            }
+            UntypedExpr::If {
+                branches,
+                final_else,
+                ..
+            } => {
+                for branch in branches {
+                    self.resolve_scoped(&branch.condition, locals);
+                    self.resolve_scoped(&branch.body, locals);
+                }
+
+                self.resolve_scoped(final_else, locals);
+            }
+            UntypedExpr::FieldAccess { container, .. } => match container.as_ref() {
+                UntypedExpr::Var { name, .. } if !locals.contains(name) => self.resolve_alias(name),
+                _ => self.resolve_scoped(container, locals),
+            },
+            UntypedExpr::Tuple { elems, .. } => {
+                for expr in elems {
+                    self.resolve_scoped(expr, locals);
+                }
+            }
+            UntypedExpr::TupleIndex { tuple, .. } => self.resolve_scoped(tuple, locals),
+            UntypedExpr::RecordUpdate {
+                constructor,
+                spread,
+                arguments,
+                ..
+            } => {
+                self.resolve_scoped(constructor, locals);
+                self.resolve_scoped(&spread.base, locals);
+
+                for arg in arguments {
+                    self.resolve_scoped(&arg.value, locals);
+                }
+            }
+            UntypedExpr::UnOp { value, .. } => self.resolve_scoped(value, locals),
+        }
+    }
+
+    /// Resolve a sub-expression whose bindings don't outlive it.
+    fn resolve_scoped(&mut self, expr: &UntypedExpr, locals: &mut Vec<String>) {
+        let scope = locals.len();
+
+        self.resolve_expr(expr, locals);
+
+        locals.truncate(scope);
+    }
+
+    /// Resolve the constructors a pattern matches on, and bind the variables
+    /// it introduces.
+    fn resolve_pattern(&mut self, pattern: &Pattern<(), ()>, locals: &mut Vec<String>) {
+        match pattern {
+            Pattern::Int { .. }
+            | Pattern::ByteArray { .. }
+            | Pattern::String { .. }
+            | Pattern::Discard { .. } => {}
+            Pattern::Var { name, .. } => locals.push(name.clone()),
+            Pattern::Assign { name, pattern, .. } => {
+                self.resolve_pattern(pattern, locals);
+
+                locals.push(name.clone());
+            }
+            Pattern::List { elements, tail, .. } => {
+                for pattern in elements.iter().chain(tail.as_deref()) {
+                    self.resolve_pattern(pattern, locals);
+                }
+            }
+            Pattern::Constructor {
+                name,
+                arguments,
+                module,
+                ..
+            } => {
+                match module {
+                    Some(alias) => self.resolve_alias(alias),
+                    None => self.resolve_name(name, &[]),
+                }
+
+                for arg in arguments {
+                    self.resolve_pattern(&arg.value, locals);
+                }
+            }
+            Pattern::Tuple { elems, .. } => {
+                for pattern in elems {
+                    self.resolve_pattern(pattern, locals);
+                }
+            }
+        }
+    }
+
+    fn resolve_annotation(&mut self, annotation: &Annotation) {
+        match annotation {
+            Annotation::Constructor {
+                module,
+                name,
+                arguments,
+                ..
+            } => {
+                match module {
+                    Some(alias) => self.resolve_alias(alias),
+                    None => self.resolve_name(name, &[]),
+                }
+
+                for annotation in arguments {
+                    self.resolve_annotation(annotation);
+                }
+            }
+            Annotation::Fn { arguments, ret, .. } => {
+                for annotation in arguments {
+                    self.resolve_annotation(annotation);
+                }
+
+                self.resolve_annotation(ret);
+            }
+            Annotation::Tuple { elems, .. } => {
+                for annotation in elems {
+                    self.resolve_annotation(annotation);
+                }
+            }
+            Annotation::Var { .. } | Annotation::Hole { .. } => {}
+        }
+    }
+
+    fn resolve_guard(&mut self, guard: &ClauseGuard<()>, locals: &[String]) {
+        match guard {
+            ClauseGuard::Not { value, .. } => self.resolve_guard(value, locals),
+            ClauseGuard::Equals { left, right, .. }
+            | ClauseGuard::NotEquals { left, right, .. }
+            | ClauseGuard::GtInt { left, right, .. }
+            | ClauseGuard::GtEqInt { left, right, .. }
+            | ClauseGuard::LtInt { left, right, .. }
+            | ClauseGuard::LtEqInt { left, right, .. }
+            | ClauseGuard::Or { left, right, .. }
+            | ClauseGuard::And { left, right, .. } => {
+                self.resolve_guard(left, locals);
+                self.resolve_guard(right, locals);
+            }
+            ClauseGuard::Var { name, .. } => self.resolve_name(name, locals),
+            ClauseGuard::Constant(_) => {}
+        }
+    }
 }
 
 pub struct ParsedModules(HashMap<String, ParsedModule>);
 
 impl ParsedModules {
     pub fn sequence(&self) -> Result<Vec<String>, Error> {
+        self.sequence_with(Vec::new())
+    }
+
+    /// Like [`Self::sequence`], along with modules that weren't parsed, each
+    /// given by its name and the modules it imports.
+    pub fn sequence_with(
+        &self,
+        unparsed: Vec<(String, Vec<String>)>,
+    ) -> Result<Vec<String>, Error> {
         let inputs = self
             .0
             .values()
             .map(|m| m.deps_for_graph())
+            .chain(unparsed)
             .collect::<Vec<(String, Vec<String>)>>();
 
         let capacity = inputs.len();
@@ -98,15 +439,76 @@ impl ParsedModules {
 
                 find_cycle(origin, origin, &graph, &mut path, &mut HashSet::new());
 
-                let modules = path
+                let mut modules: Vec<String> = path
                     .iter()
-                    .filter_map(|index| values.remove(index))
+                    .filter_map(|index| values.get(index).cloned())
                     .collect();
 
+                // Start from the same module whichever node the cycle was found
+                // from, so that the same cycle is always reported the same way.
+                if let Some(start) = modules
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, left), (_, right)| left.cmp(right))
+                    .map(|(index, _)| index)
+                {
+                    modules.rotate_left(start);
+                    modules.push(modules[0].clone());
+                }
+
                 Err(Error::ImportCycle { modules })
             }
         }
     }
+
+    /// Modules that aren't imported, directly or not, by any of the given
+    /// validator modules nor by what the tests of another module refer to, so
+    /// that test helpers aren't reported. See [`ParsedModule::test_dependencies`].
+    pub fn unused_modules(&self, validator_modules: &[String]) -> Vec<String> {
+        let used_by_validators = self.reachable_from(validator_modules.iter().cloned());
+
+        let mut used_by_tests = HashSet::new();
+
+        for module in self.values() {
+            let mut reachable = self.reachable_from(module.test_dependencies());
+
+            reachable.remove(&module.name);
+
+            used_by_tests.extend(reachable);
+        }
+
+        let mut unused: Vec<String> = self
+            .keys()
+            .filter(|name| !used_by_validators.contains(*name) && !used_by_tests.contains(*name))
+            .cloned()
+            .collect();
+
+        unused.sort();
+
+        unused
+    }
+
+    /// Modules of this project imported, directly or not, by the given ones,
+    /// which are part of the result.
+    fn reachable_from(&self, modules: impl IntoIterator<Item = String>) -> HashSet<String> {
+        let mut reachable = HashSet::new();
+
+        let mut queue: Vec<String> = modules.into_iter().collect();
+
+        while let Some(name) = queue.pop() {
+            if !reachable.insert(name.clone()) {
+                continue;
+            }
+
+            if let Some(module) = self.get(&name) {
+                let (_, deps) = module.deps_for_graph();
+
+                queue.extend(deps.into_iter().filter(|dep| self.contains_key(dep)));
+            }
+        }
+
+        reachable
+    }
 }
 
 impl From<HashMap<String, ParsedModule>> for ParsedModules {
@@ -135,6 +537,8 @@ impl DerefMut for ParsedModules {
     }
 }
 
+/// Find a path leading from `parent` back to `origin`, with the modules on
+/// it pushed onto `path` in the order they import each other.
 fn find_cycle(
     origin: NodeIndex,
     parent: NodeIndex,
@@ -144,10 +548,10 @@ fn find_cycle(
 ) -> bool {
     seen.insert(parent);
 
+    path.push(parent);
+
     for node in graph.neighbors_directed(parent, Direction::Outgoing) {
         if node == origin {
-            path.push(node);
-
             return true;
         }
 
@@ -156,16 +560,22 @@ fn find_cycle(
         }
 
         if find_cycle(origin, node, graph, path, seen) {
-            path.push(node);
-
             return true;
         }
     }
 
+    path.pop();
+
     false
 }
 
-#[derive(Debug, Clone)]
+/// Names of validator functions that run for a given script purpose, along
+/// with how many arguments they take: a datum, a redeemer and a context when
+/// spending; a redeemer and a context otherwise.
+pub const VALIDATOR_NAMES: [(&str, usize); 4] =
+    [("spend", 3), ("mint", 2), ("withdraw", 2), ("publish", 2)];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckedModule {
     pub name: String,
     pub code: String,
@@ -174,6 +584,7 @@ pub struct CheckedModule {
     pub package: String,
     pub ast: TypedModule,
     pub extra: ModuleExtra,
+    pub hash: Hash<32>,
 }
 
 impl CheckedModule {
@@ -181,17 +592,67 @@ impl CheckedModule {
         self.ast.find_node(byte_index)
     }
 
+    /// Span of the top-level function, test, constant or type with the given
+    /// name, as needed when jumping to a definition.
+    pub fn source_span_of(&self, name: &str) -> Option<Span> {
+        self.ast.definitions().find_map(|def| match def {
+            Definition::Fn(Function {
+                name: def_name,
+                location,
+                ..
+            })
+            | Definition::Test(Function {
+                name: def_name,
+                location,
+                ..
+            })
+            | Definition::ModuleConstant(ModuleConstant {
+                name: def_name,
+                location,
+                ..
+            })
+            | Definition::DataType(DataType {
+                name: def_name,
+                location,
+                ..
+            })
+            | Definition::TypeAlias(TypeAlias {
+                alias: def_name,
+                location,
+                ..
+            }) if def_name == name => Some(*location),
+            _ => None,
+        })
+    }
+
+    /// Functions of the module's validators named after a script purpose
+    /// (see [`VALIDATOR_NAMES`]), paired with that purpose, in source order.
+    pub fn validator_functions(&self) -> Vec<(&str, &TypedFunction)> {
+        self.ast
+            .definitions()
+            .filter_map(|def| match def {
+                Definition::Validator(validator) => Some(validator),
+                _ => None,
+            })
+            .flat_map(|validator| std::iter::once(&validator.fun).chain(&validator.other_fun))
+            .filter_map(|fun| {
+                VALIDATOR_NAMES
+                    .iter()
+                    .find(|(name, _)| *name == fun.name)
+                    .map(|(name, _)| (*name, fun))
+            })
+            .collect()
+    }
+
     pub fn attach_doc_and_module_comments(&mut self) {
+        let source = SourceIndex::new(&self.code);
+
         // Module Comments
         self.ast.docs = self
             .extra
             .module_comments
             .iter()
-            .map(|span| {
-                Comment::from((span, self.code.as_str()))
-                    .content
-                    .to_string()
-            })
+            .map(|span| source.comment(span).content.to_string())
             .collect();
 
         // Order definitions to avoid dissociating doc comments from them
@@ -201,8 +662,7 @@ impl CheckedModule {
         // Doc Comments
         let mut doc_comments = self.extra.doc_comments.iter().peekable();
         for def in &mut definitions {
-            let docs: Vec<&str> =
-                comments_before(&mut doc_comments, def.location().start, &self.code);
+            let docs: Vec<&str> = comments_before(&mut doc_comments, def.location().start, &source);
             if !docs.is_empty() {
                 let doc = docs.join("\n");
                 def.put_doc(doc);
@@ -211,7 +671,7 @@ impl CheckedModule {
             if let Definition::DataType(DataType { constructors, .. }) = def {
                 for constructor in constructors {
                     let docs: Vec<&str> =
-                        comments_before(&mut doc_comments, constructor.location.start, &self.code);
+                        comments_before(&mut doc_comments, constructor.location.start, &source);
                     if !docs.is_empty() {
                         let doc = docs.join("\n");
                         constructor.put_doc(doc);
@@ -219,7 +679,7 @@ impl CheckedModule {
 
                     for argument in constructor.arguments.iter_mut() {
                         let docs: Vec<&str> =
-                            comments_before(&mut doc_comments, argument.location.start, &self.code);
+                            comments_before(&mut doc_comments, argument.location.start, &source);
                         if !docs.is_empty() {
                             let doc = docs.join("\n");
                             argument.put_doc(doc);
@@ -231,6 +691,16 @@ impl CheckedModule {
     }
 }
 
+/// How many of each kind of definition a project's modules hold, as shown
+/// in build and check summaries.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub validators: usize,
+    pub tests: usize,
+    pub public_functions: usize,
+    pub types: usize,
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct CheckedModules(HashMap<String, CheckedModule>);
 
@@ -286,6 +756,64 @@ impl CheckedModules {
         items.into_iter()
     }
 
+    pub fn total_validators(&self) -> usize {
+        self.validators().count()
+    }
+
+    /// Counts of validators, tests, public functions and types (data types
+    /// and aliases) across all modules.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            validators: self.total_validators(),
+            ..Stats::default()
+        };
+
+        for def in self.values().flat_map(|module| module.ast.definitions()) {
+            match def {
+                Definition::Test(_) => stats.tests += 1,
+                Definition::Fn(Function { public: true, .. }) => stats.public_functions += 1,
+                Definition::DataType(_) | Definition::TypeAlias(_) => stats.types += 1,
+                Definition::Fn(_)
+                | Definition::ModuleConstant(_)
+                | Definition::Validator(_)
+                | Definition::Use(_) => {}
+            }
+        }
+
+        stats
+    }
+
+    /// Check that validator functions named after a script purpose take the
+    /// arguments of that purpose, as listed in [`VALIDATOR_NAMES`].
+    pub fn check_validator_arity(&self) -> Result<(), Error> {
+        for (module, validator) in self.validators() {
+            for fun in std::iter::once(&validator.fun).chain(validator.other_fun.as_ref()) {
+                let Some(&(_, expected)) =
+                    VALIDATOR_NAMES.iter().find(|(name, _)| *name == fun.name)
+                else {
+                    continue;
+                };
+
+                if fun.arguments.len() != expected {
+                    return Err(Error::MalformedValidator {
+                        purpose: fun.name.clone(),
+                        expected,
+                        found: fun.arguments.len(),
+                        path: module.input_path.clone(),
+                        src: module.code.clone(),
+                        named: NamedSource::new(
+                            module.input_path.display().to_string(),
+                            module.code.clone(),
+                        ),
+                        location: fun.location,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn into_validators(self) -> impl Iterator<Item = CheckedModule> {
         self.0
             .into_values()
@@ -308,7 +836,12 @@ impl CheckedModules {
             data_types.insert(k.clone(), v);
         }
 
-        for module in self.values() {
+        // Modules are kept in a HashMap, so we walk them in a fixed order to
+        // keep the generated code identical from one build to another.
+        let mut modules = self.values().collect::<Vec<_>>();
+        modules.sort_by(|left, right| left.name.cmp(&right.name));
+
+        for module in modules {
             for def in module.ast.definitions() {
                 match def {
                     Definition::Fn(func) => {
@@ -1,16 +1,53 @@
 use aiken_lang::ast::Tracing;
+use std::path::PathBuf;
 
 pub struct Options {
     pub code_gen_mode: CodeGenMode,
     pub tracing: Tracing,
 }
 
+/// What [`Project::check`](crate::Project::check) does besides type checking.
+pub struct CheckOptions {
+    pub skip_tests: bool,
+    pub match_tests: Option<Vec<String>>,
+    pub debug: bool,
+    pub verbose: bool,
+    pub exact_match: bool,
+    pub max_mem: Option<i64>,
+    pub max_cpu: Option<i64>,
+    pub tracing: Tracing,
+}
+
+/// The same as a bare `aiken check`: every test is run, with traces.
+impl Default for CheckOptions {
+    fn default() -> Self {
+        CheckOptions {
+            skip_tests: false,
+            match_tests: None,
+            debug: false,
+            verbose: false,
+            exact_match: false,
+            max_mem: None,
+            max_cpu: None,
+            tracing: Tracing::KeepTraces,
+        }
+    }
+}
+
 pub enum CodeGenMode {
     Test {
         match_tests: Option<Vec<String>>,
+        debug: bool,
         verbose: bool,
         exact_match: bool,
+        max_mem: Option<i64>,
+        max_cpu: Option<i64>,
+    },
+    Build {
+        uplc: bool,
+        uplc_json: bool,
+        dual: bool,
+        uplc_dir: Option<PathBuf>,
     },
-    Build(bool),
     NoOp,
 }
@@ -1,5 +1,5 @@
 use owo_colors::{OwoColorize, Stream::Stdout};
-use serde::{de::Visitor, Deserialize, Serialize};
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
 use std::{
     fmt::{self, Display},
     str::FromStr,
@@ -8,21 +8,65 @@ use thiserror::Error;
 
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct PackageName {
+    /// Empty for projects created without an owner, see
+    /// [`PackageName::parse_project_name`].
     pub owner: String,
     pub repo: String,
 }
 
 impl PackageName {
+    /// Parse the name of a new project, whose owner is optional (as in `repo`
+    /// rather than `owner/repo`). Any other package must have an owner.
+    pub fn parse_project_name(name: &str) -> Result<Self, Error> {
+        if name.contains('/') {
+            return PackageName::from_str(name);
+        }
+
+        let package_name = PackageName {
+            owner: String::new(),
+            repo: name.to_string(),
+        };
+        package_name.validate()?;
+        Ok(package_name)
+    }
+
+    /// Deserialize the name of the project itself, which may have been created
+    /// without an owner, unlike its dependencies.
+    pub fn deserialize_project_name<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+
+        PackageName::parse_project_name(&name).map_err(serde::de::Error::custom)
+    }
+
     fn validate(&self) -> Result<(), Error> {
         let r = regex::Regex::new("^[a-z0-9_-]+$").expect("regex could not be compiled");
 
-        if !(r.is_match(&self.owner) && r.is_match(&self.repo)) {
+        if !((self.owner.is_empty() || r.is_match(&self.owner)) && r.is_match(&self.repo)) {
             return Err(Error::InvalidProjectName {
                 reason: InvalidProjectNameReason::Format,
                 name: self.to_string(),
             });
         }
 
+        // Both parts end up as directory names, which some platforms (i.e. Windows)
+        // refuse to create for these, whatever the extension.
+        let is_device_name = |part: &str| {
+            matches!(part, "con" | "prn" | "aux" | "nul")
+                || ((part.starts_with("com") || part.starts_with("lpt"))
+                    && part.len() == 4
+                    && matches!(part.as_bytes()[3], b'1'..=b'9'))
+        };
+
+        if is_device_name(&self.owner) || is_device_name(&self.repo) {
+            return Err(Error::InvalidProjectName {
+                reason: InvalidProjectNameReason::SystemReserved,
+                name: self.to_string(),
+            });
+        }
+
         Ok(())
     }
 }
@@ -31,22 +75,31 @@ impl FromStr for PackageName {
     type Err = Error;
 
     fn from_str(name: &str) -> Result<Self, Error> {
-        let mut name_split = name.split('/');
-        let owner = name_split
-            .next()
-            .ok_or_else(|| Error::InvalidProjectName {
-                name: name.to_string(),
-                reason: InvalidProjectNameReason::Format,
-            })?
-            .to_string();
-        let repo = name_split
-            .next()
-            .ok_or_else(|| Error::InvalidProjectName {
-                name: name.to_string(),
-                reason: InvalidProjectNameReason::Format,
-            })?
-            .to_string();
-        let package_name = PackageName { owner, repo };
+        let malformed = || Error::InvalidProjectName {
+            name: name.to_string(),
+            reason: InvalidProjectNameReason::Format,
+        };
+
+        let (owner, repo) = match name.split_once('/') {
+            Some(("", _)) => return Err(malformed()),
+            Some((owner, repo)) => (owner, repo),
+            None if name.is_empty() => return Err(malformed()),
+            None => {
+                return Err(Error::InvalidProjectName {
+                    name: name.to_string(),
+                    reason: InvalidProjectNameReason::MissingOwner,
+                })
+            }
+        };
+
+        if repo.contains('/') {
+            return Err(malformed());
+        }
+
+        let package_name = PackageName {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        };
         package_name.validate()?;
         Ok(package_name)
     }
@@ -54,7 +107,11 @@ impl FromStr for PackageName {
 
 impl Display for PackageName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}/{}", self.owner, self.repo)
+        if self.owner.is_empty() {
+            write!(f, "{}", self.repo)
+        } else {
+            write!(f, "{}/{}", self.owner, self.repo)
+        }
     }
 }
 
@@ -128,13 +185,19 @@ pub enum Error {
 #[derive(Debug, Clone, Copy)]
 pub enum InvalidProjectNameReason {
     Reserved,
+    SystemReserved,
     Format,
+    MissingOwner,
 }
 
 impl fmt::Display for InvalidProjectNameReason {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             InvalidProjectNameReason::Reserved => write!(f, "It's a reserved word in Aiken."),
+            InvalidProjectNameReason::SystemReserved => write!(
+                f,
+                "It's reserved by some operating systems and cannot be used as a directory name."
+            ),
             InvalidProjectNameReason::Format => write!(
                 f,
                 "It is malformed.\n\nProjects must be named as:\n\n\t\
@@ -145,6 +208,97 @@ impl fmt::Display for InvalidProjectNameReason {
                 "{project}".if_supports_color(Stdout, |s| s.bright_blue()),
                 "aiken-lang/stdlib".if_supports_color(Stdout, |s| s.bright_blue()),
             ),
+            InvalidProjectNameReason::MissingOwner => write!(
+                f,
+                "It has no owner.\n\nPackages must be named as:\n\n\t{}/{}",
+                "{owner}".if_supports_color(Stdout, |s| s.bright_blue()),
+                "{project}".if_supports_color(Stdout, |s| s.bright_blue()),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::de::IntoDeserializer;
+
+    fn invalid_reason(name: &str) -> String {
+        match PackageName::from_str(name) {
+            Ok(_) => panic!("{name} should not be a valid project name"),
+            Err(err @ Error::InvalidProjectName { .. }) => err.to_string(),
+            Err(err) => panic!("unexpected error for {name}: {err}"),
+        }
+    }
+
+    #[test]
+    fn valid_names() {
+        for name in [
+            "aiken-lang/stdlib",
+            "foo/bar_baz",
+            "foo/console",
+            "foo/com0",
+        ] {
+            assert!(PackageName::from_str(name).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn packages_require_an_owner() {
+        assert!(invalid_reason("stdlib").contains("It has no owner"));
+
+        let deserialized: Result<PackageName, serde::de::value::Error> =
+            PackageName::deserialize("stdlib".into_deserializer());
+        assert!(deserialized.is_err());
+    }
+
+    #[test]
+    fn project_names_have_an_optional_owner() {
+        let qualified = PackageName::parse_project_name("aiken-lang/stdlib").unwrap();
+        assert_eq!(qualified.owner, "aiken-lang");
+        assert_eq!(qualified.repo, "stdlib");
+        assert_eq!(qualified.to_string(), "aiken-lang/stdlib");
+
+        let bare = PackageName::parse_project_name("stdlib").unwrap();
+        assert_eq!(bare.owner, "");
+        assert_eq!(bare.repo, "stdlib");
+        assert_eq!(bare.to_string(), "stdlib");
+
+        let deserialized: Result<PackageName, serde::de::value::Error> =
+            PackageName::deserialize_project_name("stdlib".into_deserializer());
+        assert!(deserialized.unwrap() == bare);
+
+        for name in ["", "Stdlib", "con", "foo/"] {
+            assert!(PackageName::parse_project_name(name).is_err(), "{name}");
+
+            let deserialized: Result<PackageName, serde::de::value::Error> =
+                PackageName::deserialize_project_name(name.into_deserializer());
+            assert!(deserialized.is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn malformed_names() {
+        for name in [
+            "",
+            "foo/",
+            "/bar",
+            "foo/..",
+            "../bar",
+            "foo/bar/baz",
+            "Foo/bar",
+        ] {
+            assert!(invalid_reason(name).contains("It is malformed"), "{name}");
+        }
+    }
+
+    #[test]
+    fn system_reserved_names() {
+        for name in ["foo/con", "nul/bar", "foo/aux", "foo/com1", "lpt9/bar"] {
+            assert!(
+                invalid_reason(name).contains("reserved by some operating systems"),
+                "{name}"
+            );
         }
     }
 }
@@ -15,6 +15,18 @@ pub fn build() -> PathBuf {
     PathBuf::from("build")
 }
 
+pub fn uplc() -> PathBuf {
+    build().join("uplc")
+}
+
+/// Where a checked module is kept between runs, see `Project::type_check`.
+pub fn module_cache(package: &str, module: &str) -> PathBuf {
+    build()
+        .join("cache")
+        .join(package)
+        .join(format!("{module}.json"))
+}
+
 pub fn packages() -> PathBuf {
     build().join("packages")
 }
@@ -49,3 +49,12 @@ pub struct EvalInfo {
 }
 
 unsafe impl Send for EvalInfo {}
+
+impl EvalInfo {
+    /// Whether the evaluation spent more memory or CPU than allowed. A missing
+    /// limit allows any amount.
+    pub fn exceeds(&self, max_mem: Option<i64>, max_cpu: Option<i64>) -> bool {
+        max_mem.is_some_and(|mem| self.spent_budget.mem > mem)
+            || max_cpu.is_some_and(|cpu| self.spent_budget.cpu > cpu)
+    }
+}
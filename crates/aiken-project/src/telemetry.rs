@@ -5,6 +5,11 @@ pub trait EventListener {
     fn handle_event(&self, _event: Event) {}
 }
 
+/// Ignores every event.
+pub struct Silent;
+
+impl EventListener for Silent {}
+
 pub enum Event {
     StartingCompilation {
         name: String,
@@ -22,9 +27,15 @@ pub enum Event {
     GeneratingBlueprint {
         path: PathBuf,
     },
+    ReportingValidatorSizes {
+        validators: Vec<ValidatorSize>,
+    },
     DumpingUPLC {
         path: PathBuf,
     },
+    DumpingUPLCJson {
+        path: PathBuf,
+    },
     GeneratingUPLCFor {
         name: String,
         path: PathBuf,
@@ -35,6 +46,7 @@ pub enum Event {
     RunningTests,
     FinishedTests {
         tests: Vec<EvalInfo>,
+        verbose: bool,
     },
     WaitingForBuildDirLock,
     DownloadingPackage {
@@ -46,3 +58,9 @@ pub enum Event {
     },
     ResolvingVersions,
 }
+
+pub struct ValidatorSize {
+    pub title: String,
+    pub term_size: usize,
+    pub serialized_size: usize,
+}
@@ -0,0 +1,98 @@
+//! Golden-output suite over the small Aiken modules found in `corpus/`.
+//!
+//! Every zero-argument function of a module is compiled and evaluated, and
+//! the outcome of each is compared against the module's `.expected` file,
+//! so that changes to code generation which alter results show up here.
+//! Run with `UPDATE_CORPUS=1` to write the current outcomes instead.
+
+use std::{fs, path::Path};
+
+use aiken_lang::ast::Definition;
+use uplc::{
+    ast::{Name, NamedDeBruijn, Program, Term},
+    describe::describe_term,
+    machine::cost_model::ExBudget,
+};
+
+use crate::module::CheckedModules;
+
+use super::TestProject;
+
+/// Compile and evaluate every zero-argument function of the given module,
+/// describing the outcome of each on its own line.
+fn run_module(source_code: &str) -> String {
+    let mut project = TestProject::new();
+
+    let modules = CheckedModules::singleton(project.check(project.parse(source_code)));
+
+    let mut generator = modules.new_generator(
+        &project.functions,
+        &project.data_types,
+        &project.module_types,
+    );
+
+    let mut outcomes = String::new();
+
+    for def in modules.values().flat_map(|module| module.ast.definitions()) {
+        let func = match def {
+            Definition::Fn(func) if func.arguments.is_empty() => func,
+            _ => continue,
+        };
+
+        let outcome = match generator.try_generate_test(&func.body) {
+            Ok(program) => {
+                let program: Program<NamedDeBruijn> = program.try_into().unwrap();
+
+                match program.eval(ExBudget::default()).result() {
+                    Ok(term) => describe_term(&Term::<Name>::try_from(term).unwrap()),
+                    Err(_) => "error".to_string(),
+                }
+            }
+            Err(error) => format!("codegen error: {error}"),
+        };
+
+        outcomes.push_str(&format!("{}: {outcome}\n", func.name));
+    }
+
+    outcomes
+}
+
+#[test]
+fn corpus() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/corpus");
+
+    let update = std::env::var_os("UPDATE_CORPUS").is_some();
+
+    let mut sources = fs::read_dir(&corpus)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("ak".as_ref()))
+        .collect::<Vec<_>>();
+
+    sources.sort();
+
+    assert!(!sources.is_empty(), "no modules in {}", corpus.display());
+
+    let mut mismatches = vec![];
+
+    for source in sources {
+        let actual = run_module(&fs::read_to_string(&source).unwrap());
+
+        let expected_path = source.with_extension("expected");
+
+        if update {
+            fs::write(&expected_path, &actual).unwrap();
+        } else {
+            let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+
+            if expected != actual {
+                mismatches.push(format!(
+                    "{}\n--- expected\n{expected}--- actual\n{actual}",
+                    source.display()
+                ));
+            }
+        }
+    }
+
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
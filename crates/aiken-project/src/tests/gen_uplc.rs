@@ -0,0 +1,2674 @@
+use std::rc::Rc;
+
+use aiken_lang::{
+    ast::{Definition, ModuleKind, Tracing, TypedValidator},
+    expr::TypedExpr,
+    gen_uplc::{
+        self,
+        error::CodeGenError,
+        supported::{self, UnsupportedFeature, UnsupportedKind},
+        CodeGenerator, OptLevel,
+    },
+};
+use indexmap::IndexMap;
+use uplc::{
+    ast::{Constant, Data, DeBruijn, Name, NamedDeBruijn, Program, Term},
+    builtins::DefaultFunction,
+    machine::{cost_model::ExBudget, eval_result::EvalResult},
+};
+
+use crate::module::CheckedModules;
+
+use super::TestProject;
+
+/// Compile and evaluate the given test, after type-checking the given
+/// `(name, source)` library modules so that they can be imported.
+fn eval_test_with_dependencies(
+    dependencies: &[(&str, &str)],
+    source_code: &str,
+    test_name: &str,
+) -> EvalResult {
+    eval_test_with_generator(dependencies, source_code, test_name, |_| {})
+}
+
+/// Like [`eval_test_with_dependencies`], with a generator set up by
+/// `configure` beforehand.
+fn eval_test_with_generator(
+    dependencies: &[(&str, &str)],
+    source_code: &str,
+    test_name: &str,
+    configure: impl FnOnce(&mut CodeGenerator),
+) -> EvalResult {
+    let mut project = TestProject::new();
+
+    let mut modules = CheckedModules::default();
+
+    for (name, dependency) in dependencies {
+        let module = project.check_with_tracing(
+            project.parse_module(name, ModuleKind::Lib, dependency),
+            Tracing::KeepTraces,
+        );
+
+        modules.insert(module.name.clone(), module);
+    }
+
+    let module = project.check_with_tracing(project.parse(source_code), Tracing::KeepTraces);
+
+    modules.insert(module.name.clone(), module);
+
+    let mut generator = modules.new_generator(
+        &project.functions,
+        &project.data_types,
+        &project.module_types,
+    );
+
+    configure(&mut generator);
+
+    let program: Program<NamedDeBruijn> = generator
+        .generate_test(find_body(&modules, test_name))
+        .try_into()
+        .unwrap();
+
+    program.eval(ExBudget::default())
+}
+
+/// Compile the given test without running the optimizer over it, so that
+/// the generated code can be inspected as is.
+fn compile_unoptimized_test(source_code: &str, test_name: &str) -> Program<Name> {
+    compile_test(source_code, test_name, false)
+}
+
+/// Compile the given test, with or without optimizations.
+fn compile_test(source_code: &str, test_name: &str, optimize: bool) -> Program<Name> {
+    with_generator(source_code, |mut generator, modules| {
+        generator.set_optimize(optimize);
+
+        generator.generate_test(find_body(modules, test_name))
+    })
+}
+
+/// Type-check the given source code, then hand `f` a generator set up like
+/// the project's, along with the checked module.
+fn with_generator<T>(source_code: &str, f: impl FnOnce(CodeGenerator, &CheckedModules) -> T) -> T {
+    let mut project = TestProject::new();
+
+    let modules = CheckedModules::singleton(
+        project.check_with_tracing(project.parse(source_code), Tracing::KeepTraces),
+    );
+
+    let generator = modules.new_generator(
+        &project.functions,
+        &project.data_types,
+        &project.module_types,
+    );
+
+    f(generator, &modules)
+}
+
+/// Like [`with_generator`], with the first validator of the source code.
+fn with_validator<T>(source_code: &str, f: impl FnOnce(CodeGenerator, &TypedValidator) -> T) -> T {
+    with_generator(source_code, |generator, modules| {
+        let (_, validator) = modules
+            .validators()
+            .next()
+            .expect("source code did not yield a validator");
+
+        f(generator, validator)
+    })
+}
+
+/// The body of the test or function of that name.
+fn find_body<'a>(modules: &'a CheckedModules, name: &str) -> &'a TypedExpr {
+    modules
+        .values()
+        .flat_map(|module| module.ast.definitions())
+        .find_map(|def| match def {
+            Definition::Test(func) | Definition::Fn(func) if func.name == name => Some(&func.body),
+            _ => None,
+        })
+        .expect("source code did not yield the requested test or function")
+}
+
+/// Compile the given test, with a generator that is either set up like the
+/// project's or that doesn't know about any function nor data type.
+fn try_generate_test(
+    source_code: &str,
+    test_name: &str,
+    known_definitions: bool,
+) -> Result<Program<Name>, CodeGenError> {
+    if known_definitions {
+        return with_generator(source_code, |mut generator, modules| {
+            generator.try_generate_test(find_body(modules, test_name))
+        });
+    }
+
+    let mut project = TestProject::new();
+
+    let modules = CheckedModules::singleton(
+        project.check_with_tracing(project.parse(source_code), Tracing::KeepTraces),
+    );
+
+    CodeGenerator::new(
+        IndexMap::new(),
+        IndexMap::new(),
+        project.module_types.iter().collect(),
+    )
+    .try_generate_test(find_body(&modules, test_name))
+}
+
+/// Run the ahead-of-time support check on the given test.
+fn check_supported_test(source_code: &str, test_name: &str) -> Result<(), UnsupportedFeature> {
+    with_generator(source_code, |_, modules| {
+        supported::check_supported(find_body(modules, test_name))
+    })
+}
+
+/// Compile and run the given test, asserting that it evaluates to `True`.
+/// Returns the traces collected during evaluation.
+fn assert_test(source_code: &str, test_name: &str) -> Vec<String> {
+    assert_test_with_dependencies(&[], source_code, test_name)
+}
+
+fn assert_test_with_dependencies(
+    dependencies: &[(&str, &str)],
+    source_code: &str,
+    test_name: &str,
+) -> Vec<String> {
+    let mut eval_result = eval_test_with_dependencies(dependencies, source_code, test_name);
+
+    let logs = eval_result.logs();
+
+    assert_eq!(
+        eval_result.result().expect("evaluation failed"),
+        Term::bool(true),
+        "logs: {logs:#?}"
+    );
+
+    logs
+}
+
+#[test]
+fn discard_assignment_still_evaluates_value() {
+    let logs = assert_test(
+        r#"
+        fn noisy(n: Int) -> Int {
+          trace "discarded"
+          n + 1
+        }
+
+        test discard() {
+          let _ = noisy(41)
+          42 == 42
+        }
+        "#,
+        "discard",
+    );
+
+    assert_eq!(logs, vec!["discarded".to_string()]);
+}
+
+#[test]
+fn nested_record_update() {
+    assert_test(
+        r#"
+        type Inner {
+          a: Int,
+          b: ByteArray,
+        }
+
+        type Thing {
+          inner: Inner,
+          label: ByteArray,
+          count: Int,
+        }
+
+        test nested_update() {
+          let x = Thing { inner: Inner { a: 0, b: #"ff" }, label: #"00", count: 42 }
+          let y = Thing { ..x, inner: Inner { ..x.inner, a: 1 } }
+          y.inner.a == 1 && y.inner.b == #"ff" && y.label == #"00" && y.count == 42
+        }
+        "#,
+        "nested_update",
+    );
+}
+
+#[test]
+fn let_nested_constructor_pattern() {
+    assert_test(
+        r#"
+        type Pair {
+          Pair(Int, ByteArray)
+        }
+
+        type Wrapper {
+          Wrapper(Pair, Int)
+        }
+
+        test nested_constructor() {
+          let x = Wrapper(Pair(1, #"ff"), 2)
+          let Wrapper(Pair(a, b), c) = x
+          expect Some(Pair(d, _)) = Some(Pair(3, #""))
+          a == 1 && b == #"ff" && c == 2 && d == 3
+        }
+        "#,
+        "nested_constructor",
+    );
+}
+
+#[test]
+fn when_nested_constructor_pattern() {
+    assert_test(
+        r#"
+        type Wrapped {
+          Wrapped(Int)
+        }
+
+        fn unwrap(opt: Option<Wrapped>) -> Int {
+          when opt is {
+            Some(Wrapped(x)) -> x
+            None -> 0
+          }
+        }
+
+        fn flatten(opt: Option<Option<Int>>) -> Int {
+          when opt is {
+            Some(Some(x)) -> x
+            Some(None) -> 1
+            None -> 0
+          }
+        }
+
+        test nested_constructor() {
+          unwrap(Some(Wrapped(42))) == 42 && unwrap(None) == 0 && flatten(
+            Some(Some(42)),
+          ) == 42 && flatten(Some(None)) == 1 && flatten(None) == 0
+        }
+        "#,
+        "nested_constructor",
+    );
+}
+
+#[test]
+fn module_select_record_constructor() {
+    assert_test_with_dependencies(
+        &[(
+            "other",
+            r#"
+            pub type Color {
+              Red
+              Green
+            }
+
+            pub type Thing {
+              Thing { label: ByteArray, count: Int }
+            }
+            "#,
+        )],
+        r#"
+        use other
+
+        fn make(f: fn(ByteArray, Int) -> other.Thing) -> other.Thing {
+          f(#"ff", 42)
+        }
+
+        test module_record() {
+          let thing = make(other.Thing)
+          let color = other.Green
+          thing.count == 42 && thing.label == #"ff" && color == other.Green && color != other.Red
+        }
+        "#,
+        "module_record",
+    );
+}
+
+#[test]
+fn record_constructor_as_function() {
+    assert_test(
+        r#"
+        type Thing {
+          Thing { label: ByteArray, count: Int }
+        }
+
+        fn make(f: fn(ByteArray, Int) -> Thing) -> Thing {
+          f(#"ff", 42)
+        }
+
+        test local_record() {
+          let thing = make(Thing)
+          thing.count == 42 && thing.label == #"ff"
+        }
+        "#,
+        "local_record",
+    );
+}
+
+#[test]
+fn let_nested_pattern_var_leaves() {
+    assert_test(
+        r#"
+        type Pair {
+          Pair { left: Int, right: List<Int> }
+        }
+
+        test nested_leaves() {
+          let (Pair { left, right: [first, ..] }, second) = (Pair(1, [2, 3]), 4)
+          left == 1 && first == 2 && second == 4
+        }
+        "#,
+        "nested_leaves",
+    );
+}
+
+#[test]
+fn let_nested_list_discarded_tail() {
+    assert_test(
+        r#"
+        test discarded_tail() {
+          let (a, [first, ..], [_, second, ..]) = (1, [2, 3], [4, 5, 6])
+          a == 1 && first == 2 && second == 5
+        }
+        "#,
+        "discarded_tail",
+    );
+}
+
+#[test]
+fn record_with_many_fields() {
+    let expected = Data::constr(
+        1,
+        vec![
+            Data::integer(42.into()),
+            Data::bytestring(vec![0xff]),
+            Data::list(vec![Data::integer(1.into()), Data::integer(2.into())]),
+            Data::constr(0, vec![]),
+        ],
+    );
+
+    let expected = hex::encode(uplc::plutus_data_to_bytes(&expected).unwrap());
+
+    assert_test(
+        &format!(
+            r#"
+            use aiken/builtin
+
+            type Thing {{
+              Nothing
+              Thing {{ count: Int, label: ByteArray, items: List<Int>, flag: Bool }}
+            }}
+
+            fn make(count: Int, flag: Bool) -> Thing {{
+              Thing {{ count, label: #"ff", items: [1, 2], flag }}
+            }}
+
+            test many_fields() {{
+              let thing = Thing {{ count: 42, label: #"ff", items: [1, 2], flag: False }}
+              let expected = #"{expected}"
+              builtin.serialise_data(thing) == expected && builtin.serialise_data(
+                make(42, False),
+              ) == expected
+            }}
+            "#
+        ),
+        "many_fields",
+    );
+}
+
+#[test]
+fn list_literal_with_tail() {
+    assert_test(
+        r#"
+        fn prepend(rest: List<Int>) -> List<Int> {
+          [1, 2, ..rest]
+        }
+
+        test with_tail() {
+          let rest = [3]
+          prepend(rest) == [1, 2, 3] && [1, 2, ..rest] == [1, 2, 3] && [0, ..[]] == [0]
+        }
+        "#,
+        "with_tail",
+    );
+}
+
+#[test]
+fn when_binop_subject() {
+    let logs = assert_test(
+        r#"
+        fn add(a: Int, b: Int) -> Int {
+          trace "add"
+          a + b
+        }
+
+        fn describe(a: Int, b: Int) -> ByteArray {
+          when add(a, b) + 0 is {
+            0 -> "zero"
+            1 -> "one"
+            _ -> "many"
+          }
+        }
+
+        test binop_subject() {
+          describe(0, 0) == "zero" && describe(1, 0) == "one" && describe(1, 2) == "many"
+        }
+        "#,
+        "binop_subject",
+    );
+
+    assert_eq!(logs, vec!["add", "add", "add"]);
+}
+
+#[test]
+fn when_bool_subject_checks_each_clause() {
+    assert_test(
+        r#"
+        fn to_int(b: Bool) -> Int {
+          when b is {
+            True -> 1
+            False -> 0
+          }
+        }
+
+        fn negate(b: Bool) -> Bool {
+          when b is {
+            False -> True
+            True -> False
+          }
+        }
+
+        test foo() {
+          to_int(True) == 1 && to_int(False) == 0 && negate(False) && !negate(True)
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn fields_expose_skipping_fields() {
+    assert_test(
+        r#"
+        type Thing {
+          Nothing
+          Thing { a: Int, b: ByteArray, c: Int, d: List<Int> }
+        }
+
+        fn third(thing: Thing) -> Int {
+          when thing is {
+            Thing { c, .. } -> c
+            Nothing -> 0
+          }
+        }
+
+        test skipped_fields() {
+          expect Thing(_, _, _, d) = Thing(1, #"", 3, [4])
+          expect Thing { c, .. } = Thing(1, #"", 3, [4])
+          third(Thing(1, #"", 3, [4])) == 3 && c == 3 && d == [4]
+        }
+        "#,
+        "skipped_fields",
+    );
+}
+
+#[test]
+fn fields_expose_single_field_is_read_directly() {
+    let src = |pattern: &str| {
+        format!(
+            r#"
+            type Thing {{
+              Nothing
+              Thing {{ a: Int, b: ByteArray, c: Int, d: Int }}
+            }}
+
+            fn last(thing: Thing) -> Int {{
+              when thing is {{
+                {pattern} -> d
+                Nothing -> 0
+              }}
+            }}
+
+            test foo() {{
+              last(Thing(1, #"", 3, 4)) == 4
+            }}
+            "#
+        )
+    };
+
+    let single = src("Thing { d, .. }");
+    let several = src("Thing { c, d, .. }");
+
+    assert_test(&single, "foo");
+    assert_test(&several, "foo");
+
+    let single = compile_unoptimized_test(&single, "foo");
+    let several = compile_unoptimized_test(&several, "foo");
+
+    assert!(
+        !single.to_pretty().contains("__tail_index"),
+        "{}",
+        single.to_pretty()
+    );
+    assert!(gen_uplc::term_size(&single) < gen_uplc::term_size(&several));
+}
+
+#[test]
+fn when_fallthrough_clause() {
+    assert_test(
+        r#"
+        fn flatten(opt: Option<Option<Int>>) -> Int {
+          when opt is {
+            None -> 0
+            Some(None) -> 1
+            Some(Some(x)) -> x
+          }
+        }
+
+        fn classify(n: Int) -> ByteArray {
+          when n is {
+            0 -> "zero"
+            1 -> "one"
+            _ -> "many"
+          }
+        }
+
+        test fallthrough() {
+          flatten(Some(Some(42))) == 42 && flatten(Some(None)) == 1 && flatten(None) == 0 && classify(
+            14,
+          ) == "many"
+        }
+        "#,
+        "fallthrough",
+    );
+}
+
+#[test]
+fn annotated_validator() {
+    with_validator(
+        r#"
+        fn is_positive(amount: Int) -> Bool {
+          amount > 0
+        }
+
+        validator {
+          fn policy(amount: Int, _ctx: Data) -> Bool {
+            is_positive(amount) && is_positive(amount - 1)
+          }
+        }
+        "#,
+        |mut generator, validator| {
+            let (program, annotated) = generator.generate_annotated(validator);
+
+            assert_eq!(program, generator.generate(validator));
+            assert!(annotated.contains("-- "), "{annotated}");
+            assert!(annotated.contains("is_positive"), "{annotated}");
+            assert!(annotated.contains("Let amount"), "{annotated}");
+            assert!(annotated.ends_with(&program.to_pretty()), "{annotated}");
+        },
+    );
+}
+
+#[test]
+fn source_map_of_validator() {
+    let src = r#"
+        validator {
+          fn policy(amount: Int, _ctx: Data) -> Bool {
+            amount > 10 || amount == 0
+          }
+        }
+        "#;
+
+    let (program, source_map, expected) = with_validator(src, |mut generator, validator| {
+        let (program, source_map) = generator.generate_with_source_map(validator).unwrap();
+
+        // the program is the same as without a source map, unoptimized
+        generator.set_optimize(false);
+
+        (program, source_map, generator.generate(validator))
+    });
+
+    let (path, _) = source_map
+        .iter()
+        .find(|(_, span)| &src[span.start..span.end] == "amount > 10")
+        .expect("the comparison is in the source map");
+
+    let term = gen_uplc::term_at(&program.term, path).expect("the path leads to a term");
+
+    assert!(
+        matches!(
+            term,
+            Term::Apply { function, .. }
+                if matches!(function.as_ref(), Term::Apply { function, .. }
+                    if matches!(function.as_ref(), Term::Builtin(DefaultFunction::LessThanInteger)))
+        ),
+        "{}",
+        term.to_pretty()
+    );
+
+    assert!(source_map
+        .iter()
+        .any(|(_, span)| &src[span.start..span.end] == "amount == 0"));
+
+    let expected: Program<DeBruijn> = expected.try_into().unwrap();
+    let program: Program<DeBruijn> = program.try_into().unwrap();
+
+    assert_eq!(program, expected);
+}
+
+#[test]
+fn source_map_of_unsupported_validator() {
+    let src = r#"
+        validator {
+          fn policy(amount: Int, _ctx: Data) -> Bool {
+            expect 1 = amount
+            True
+          }
+        }
+        "#;
+
+    let error = with_validator(src, |mut generator, validator| {
+        generator.generate_with_source_map(validator).unwrap_err()
+    });
+
+    assert!(
+        matches!(
+            &error,
+            CodeGenError::UnsupportedExpression { construct, location }
+                if construct == "integer patterns" && &src[location.start..location.end] == "1"
+        ),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn optimized_and_unoptimized_validators() {
+    let (optimized, unoptimized) = with_validator(
+        r#"
+        type Action {
+          Mint { amount: Int, owners: List<ByteArray> }
+          Burn
+        }
+
+        fn has_owner(owners: List<ByteArray>, owner: ByteArray) -> Bool {
+          when owners is {
+            [] -> False
+            [x, ..rest] -> x == owner || has_owner(rest, owner)
+          }
+        }
+
+        fn is_valid(amount: Int, owners: List<ByteArray>) -> Bool {
+          let double = amount * 2
+          let triple = amount * 3
+          double > 0 && triple > double && has_owner(owners, #"00") && has_owner(
+            owners,
+            #"01",
+          )
+        }
+
+        validator {
+          fn policy(action: Action, _ctx: Data) -> Bool {
+            when action is {
+              Mint { amount, owners } -> is_valid(amount, owners)
+              Burn -> True
+            }
+          }
+        }
+        "#,
+        |mut generator, validator| {
+            let optimized: Program<DeBruijn> = generator.generate(validator).try_into().unwrap();
+
+            generator.set_optimize(false);
+
+            let unoptimized: Program<DeBruijn> = generator.generate(validator).try_into().unwrap();
+
+            (optimized, unoptimized)
+        },
+    );
+
+    assert_ne!(optimized, unoptimized);
+
+    assert!(
+        unoptimized.to_flat().unwrap().len() >= optimized.to_flat().unwrap().len(),
+        "optimized:\n{}\n\nunoptimized:\n{}",
+        optimized.to_pretty(),
+        unoptimized.to_pretty(),
+    );
+
+    let redeemer = Data::constr(
+        0,
+        vec![
+            Data::integer(1.into()),
+            Data::list(vec![Data::bytestring(vec![0]), Data::bytestring(vec![1])]),
+        ],
+    );
+
+    let eval = |program: &Program<DeBruijn>| {
+        let program: Program<NamedDeBruijn> = program
+            .apply_data(redeemer.clone())
+            .apply_data(Data::constr(0, vec![]))
+            .into();
+
+        program
+            .eval(ExBudget::default())
+            .result()
+            .expect("evaluation failed")
+    };
+
+    assert_eq!(eval(&optimized), Term::Constant(Constant::Unit.into()));
+    assert_eq!(eval(&optimized), eval(&unoptimized));
+}
+
+#[test]
+fn validator_arguments_are_cast_from_data() {
+    let program: Program<DeBruijn> = with_validator(
+        r#"
+        type Datum {
+          Locked { owner: ByteArray, amounts: List<Int> }
+          Open
+        }
+
+        validator {
+          fn spend(datum: Datum, redeemer: List<Int>, _ctx: Data) -> Bool {
+            when datum is {
+              Locked { owner, amounts } -> owner == #"00" && amounts == redeemer
+              Open -> True
+            }
+          }
+        }
+        "#,
+        |mut generator, validator| generator.generate(validator),
+    )
+    .try_into()
+    .unwrap();
+
+    let eval = |datum, redeemer| {
+        let program: Program<NamedDeBruijn> = program
+            .apply_data(datum)
+            .apply_data(redeemer)
+            .apply_data(Data::constr(0, vec![]))
+            .into();
+
+        program.eval(ExBudget::default()).result()
+    };
+
+    let amounts = || Data::list(vec![Data::integer(1.into()), Data::integer(2.into())]);
+
+    let locked = |owner: u8| Data::constr(0, vec![Data::bytestring(vec![owner]), amounts()]);
+
+    assert_eq!(
+        eval(locked(0), amounts()).unwrap(),
+        Term::Constant(Constant::Unit.into())
+    );
+    assert_eq!(
+        eval(Data::constr(1, vec![]), Data::list(vec![])).unwrap(),
+        Term::Constant(Constant::Unit.into())
+    );
+    assert!(eval(locked(1), amounts()).is_err());
+    assert!(eval(locked(0), Data::list(vec![])).is_err());
+    // a datum that isn't a `Datum` is rejected before the body runs
+    assert!(eval(Data::integer(0.into()), amounts()).is_err());
+    assert!(eval(Data::constr(2, vec![]), amounts()).is_err());
+}
+
+#[test]
+fn deterministic_validator_code() {
+    let compile = || {
+        let mut project = TestProject::new();
+
+        let mut modules = CheckedModules::default();
+
+        for (name, dependency) in [
+            ("foo/bar", "pub fn double(n: Int) -> Int { n * 2 }"),
+            ("foo/baz", "pub fn triple(n: Int) -> Int { n * 3 }"),
+            ("foo/qux", "pub type Choice { Yes(Int) No }"),
+        ] {
+            let module = project.check(project.parse_module(name, ModuleKind::Lib, dependency));
+
+            modules.insert(module.name.clone(), module);
+        }
+
+        let module = project.check(project.parse(
+            r#"
+            use foo/bar
+            use foo/baz
+            use foo/qux.{Choice, Yes, No}
+
+            validator {
+              fn spend(choice: Choice, n: Int, _ctx: Data) -> Bool {
+                when choice is {
+                  Yes(m) -> bar.double(n) + baz.triple(m) > 0
+                  No -> bar.double(n) < baz.triple(n)
+                }
+              }
+            }
+            "#,
+        ));
+
+        modules.insert(module.name.clone(), module);
+
+        let mut generator = modules.new_generator(
+            &project.functions,
+            &project.data_types,
+            &project.module_types,
+        );
+
+        let (_, validator) = modules.validators().next().unwrap();
+
+        let program: Program<DeBruijn> = generator.generate(validator).try_into().unwrap();
+
+        program.to_flat().unwrap()
+    };
+
+    let expected = compile();
+
+    for _ in 0..10 {
+        assert_eq!(compile(), expected);
+    }
+}
+
+/// Evaluate the given test both with `EqualsData` and with the element-wise
+/// list equality, asserting that it evaluates to `True` either way. Returns
+/// the cost of each, in that order.
+fn assert_list_equality_test(source_code: &str, test_name: &str) -> (ExBudget, ExBudget) {
+    let eval = |element_wise| {
+        let result = eval_test_with_generator(&[], source_code, test_name, |generator| {
+            generator.set_element_wise_list_equality(element_wise)
+        });
+
+        let cost = result.cost();
+
+        assert_eq!(
+            result.result().unwrap(),
+            Term::Constant(Constant::Bool(true).into()),
+            "element-wise: {element_wise}"
+        );
+
+        cost
+    };
+
+    (eval(false), eval(true))
+}
+
+#[test]
+fn int_list_equality() {
+    assert_list_equality_test(
+        r#"
+        test foo() {
+          let xs = [1, 2, 3]
+          let empty: List<Int> = []
+          xs == [1, 2, 3] && xs != [1, 2, 4] && xs != [1, 2] && [1, 2] != xs && empty == [] && empty != xs && xs != empty
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn bytearray_list_equality() {
+    assert_list_equality_test(
+        r#"
+        test foo() {
+          let xs = [#"00", #"01"]
+          xs == [#"00", #"01"] && xs != [#"01", #"00"] && xs != [#"00"]
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn list_equality_through_data_is_cheaper() {
+    let (through_data, element_wise) = assert_list_equality_test(
+        r#"
+        test foo() {
+          let xs = [1, 2, 3, 4, 5, 6, 7, 8]
+          let ys = [1, 2, 3, 4, 5, 6, 7, 8]
+          let zs = [1, 2, 3, 4, 5, 6, 7, 9]
+          xs == ys && xs != zs && xs != [1]
+        }
+        "#,
+        "foo",
+    );
+
+    assert!(
+        through_data.cpu < element_wise.cpu && through_data.mem < element_wise.mem,
+        "{through_data:?} vs {element_wise:?}"
+    );
+}
+
+#[test]
+fn tuple_pattern_discarded_element_is_not_bound() {
+    let src = r#"
+        fn second(pair: (Int, Int)) -> Int {
+          let (_, y) = pair
+          y
+        }
+
+        test foo() {
+          second((1, 2)) == 2
+        }
+        "#;
+
+    assert_test(src, "foo");
+
+    let program = compile_unoptimized_test(src, "foo").to_pretty();
+
+    assert!(program.contains("sndPair"), "{program}");
+    assert!(!program.contains("fstPair"), "{program}");
+}
+
+#[test]
+fn folded_constants_evaluate_the_same() {
+    assert_test(
+        r#"
+        fn id(x: Int) -> Int {
+          x
+        }
+
+        test foo() {
+          let x = 1 + 2 * 3 - 4
+          let y = id(3) + 2 * 5
+          x == 3 && y == 13 && 10 - 4 > 5 && 2 * 2 <= 4 && 1 - 3 < 0 && !(7 != 7)
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn bool_constant_comparison_is_folded() {
+    let src = r#"
+        test foo() {
+          True == True
+        }
+        "#;
+
+    assert_test(src, "foo");
+
+    let program = try_generate_test(src, "foo", true).unwrap();
+
+    assert_eq!(program.term, Term::bool(true), "{}", program.to_pretty());
+}
+
+#[test]
+fn when_tuple_subject_is_bound_directly() {
+    let src = r#"
+        fn swap(pair: (Int, Int)) -> (Int, Int) {
+          when pair is {
+            (a, b) if a == 0 -> (b, a)
+            (a, b) -> (b, a)
+          }
+        }
+
+        test foo() {
+          swap((1, 2)) == (2, 1) && swap((0, 3)) == (3, 0)
+        }
+        "#;
+
+    assert_test(src, "foo");
+
+    let program = compile_unoptimized_test(src, "foo").to_pretty();
+
+    assert!(!program.contains("__constr_index_exposer"), "{program}");
+}
+
+#[test]
+fn when_record_subject_with_guard() {
+    assert_test(
+        r#"
+        type Point {
+          x: Int,
+          y: Int,
+        }
+
+        fn quadrant(point: Point) -> Int {
+          when point is {
+            Point { x, y } if x >= 0 && y >= 0 -> 1
+            Point { x, .. } if x < 0 -> 2
+            _ -> 3
+          }
+        }
+
+        test foo() {
+          quadrant(Point { x: 1, y: 2 }) == 1 && quadrant(Point { x: -1, y: 2 }) == 2 && quadrant(Point { x: 1, y: -2 }) == 3
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn labeled_arguments_in_any_order() {
+    assert_test(
+        r#"
+        fn sub(a: Int, b: Int) -> Int {
+          a - b
+        }
+
+        fn sub3(a: Int, b: Int, c: Int) -> Int {
+          a - b - c
+        }
+
+        type Point {
+          x: Int,
+          y: Int,
+        }
+
+        test foo() {
+          let point = Point { y: 2, x: 1 }
+          sub(b: 2, a: 10) == 8 && sub(10, b: 2) == 8 && sub3(10, c: 1, b: 2) == 7 && point.x == 1
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn expect_fails_on_mismatch_and_binds_on_match() {
+    assert_test(
+        r#"
+        test foo() {
+          let opt = Some(3)
+          expect Some(x) = opt
+          x == 3
+        }
+        "#,
+        "foo",
+    );
+
+    let mut result = eval_test_with_dependencies(
+        &[],
+        r#"
+        test foo() {
+          let opt: Option<Int> = None
+          expect Some(x) = opt
+          x == 3
+        }
+        "#,
+        "foo",
+    );
+
+    assert!(result.failed(), "{:?}", result.logs());
+
+    for xs in ["[]", "[1]", "[1, 2, 3]"] {
+        let mut result = eval_test_with_dependencies(
+            &[],
+            &format!(
+                r#"
+                test foo() {{
+                  let xs: List<Int> = {xs}
+                  expect [a, b] = xs
+                  a + b == 3
+                }}
+                "#
+            ),
+            "foo",
+        );
+        assert!(result.failed(), "{xs}: {:?}", result.logs());
+    }
+
+    assert_test(
+        r#"
+        test foo() {
+          let xs = [1, 2]
+          expect [a, b] = xs
+          expect Some(Some(c)) = Some(Some(a + b))
+          c == 3
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn optimization_levels() {
+    let src = r#"
+        test foo() {
+          1 + 2 == 3
+        }
+        "#;
+
+    let generate = |level| {
+        with_generator(src, |generator, modules| {
+            generator
+                .with_optimizations(level)
+                .generate_test(find_body(modules, "foo"))
+        })
+    };
+
+    let none = generate(OptLevel::None);
+    let basic = generate(OptLevel::Basic);
+    let full = generate(OptLevel::Full);
+
+    assert!(
+        none.to_pretty().contains("addInteger"),
+        "{}",
+        none.to_pretty()
+    );
+    assert_eq!(basic.term, Term::bool(true), "{}", basic.to_pretty());
+    assert_eq!(full.term, Term::bool(true), "{}", full.to_pretty());
+}
+
+#[test]
+fn zero_argument_calls() {
+    assert_test(
+        r#"
+        fn three() -> Int {
+          1 + 2
+        }
+
+        fn six() -> Int {
+          three() + three()
+        }
+
+        fn adder() -> fn(Int) -> Int {
+          fn(x) { x + three() }
+        }
+
+        test foo() {
+          let thunk = fn() { six() * 2 }
+          six() == 6 && thunk() == 12 && adder()(1) == 4
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn concat_strings_and_byte_arrays() {
+    assert_test(
+        r#"
+        fn greet(name: String) -> String {
+          @"Hello, " <> name <> @"!"
+        }
+
+        test foo() {
+          let bytes = #"de" <> #"adbeef"
+          greet(@"Aiken") == @"Hello, Aiken!" && bytes == #"deadbeef"
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn helper_used_in_several_branches_is_defined_once() {
+    let src = r#"
+        fn double(x: Int) -> Int {
+          x * 2
+        }
+
+        fn quadruple(x: Int) -> Int {
+          double(double(x))
+        }
+
+        fn pick(n: Int, flag: Bool) -> Int {
+          if flag {
+            double(n)
+          } else {
+            when n is {
+              0 -> quadruple(n)
+              _ -> double(quadruple(n))
+            }
+          }
+        }
+
+        test foo() {
+          pick(1, True) == 2 && pick(0, False) == 0 && pick(1, False) == 8
+        }
+        "#;
+
+    assert_test(src, "foo");
+
+    let program = compile_unoptimized_test(src, "foo");
+
+    for name in ["test_module_double", "test_module_quadruple"] {
+        assert_eq!(
+            count_binders(&program.term, name),
+            1,
+            "{}",
+            program.to_pretty()
+        );
+    }
+
+    let program = with_validator(
+        r#"
+        fn double(x: Int) -> Int {
+          x * 2
+        }
+
+        validator {
+          fn spend(_datum: Data, redeemer: Int, _ctx: Data) -> Bool {
+            double(redeemer) == 4
+          }
+
+          fn mint(redeemer: Int, _ctx: Data) -> Bool {
+            if redeemer > 0 {
+              double(redeemer) == 2
+            } else {
+              double(double(redeemer)) == 0
+            }
+          }
+        }
+        "#,
+        |mut generator, validator| {
+            generator.set_optimize(false);
+
+            generator.generate(validator)
+        },
+    );
+
+    // Once for each handler, even though `mint` calls it from both branches.
+    assert_eq!(
+        count_binders(&program.term, "test_module_double"),
+        2,
+        "{}",
+        program.to_pretty()
+    );
+}
+
+#[test]
+fn recursive_data_type_nested_patterns() {
+    assert_test(
+        r#"
+        type MyList {
+          Nil
+          Cons(Int, MyList)
+        }
+
+        fn first_two(xs: MyList) -> Int {
+          when xs is {
+            Cons(a, Cons(b, _)) -> a + b
+            Cons(a, Nil) -> a
+            Nil -> 0
+          }
+        }
+
+        fn sum(xs: MyList) -> Int {
+          when xs is {
+            Nil -> 0
+            Cons(x, rest) -> x + sum(rest)
+          }
+        }
+
+        test foo() {
+          let xs = Cons(1, Cons(2, Cons(3, Nil)))
+          expect Cons(_, Cons(second, _)) = xs
+          first_two(xs) == 3 && first_two(Cons(4, Nil)) == 4 && first_two(Nil) == 0 && sum(xs) == 6 && second == 2
+        }
+        "#,
+        "foo",
+    );
+
+    assert_test(
+        r#"
+        type Tree<a> {
+          Leaf
+          Node { left: Tree<a>, value: a, right: Tree<a> }
+        }
+
+        fn depth(t: Tree<a>) -> Int {
+          when t is {
+            Leaf -> 0
+            Node { left: Node { left: Leaf, .. }, .. } -> 2
+            Node { left, .. } -> 1 + depth(left)
+          }
+        }
+
+        test foo() {
+          let d: Data = Node(Node(Leaf, 1, Leaf), 2, Leaf)
+          expect t: Tree<Int> = d
+          depth(t) == 2
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn sequence_effects_run_in_order() {
+    let logs = assert_test(
+        r#"
+        fn noisy(x: Int) -> Int {
+          trace @"piped"
+          x
+        }
+
+        test foo() {
+          trace @"first"
+          let _ = 1 |> noisy
+          let x = 1
+          trace @"last"
+          x == 1
+        }
+        "#,
+        "foo",
+    );
+
+    assert_eq!(logs, vec!["first", "piped", "last"]);
+}
+
+#[test]
+fn when_list_literal_clauses() {
+    assert_test(
+        r#"
+        fn classify(xs: List<Int>) -> Int {
+          when xs is {
+            [] -> 0
+            [1] -> 1
+            [1, 2, 3] -> 3
+            [1, ..] -> 4
+            [_, 2] -> 5
+            _ -> 6
+          }
+        }
+
+        fn names(xs: List<String>) -> Int {
+          when xs is {
+            [@"foo", @"bar"] -> 1
+            [@"foo", ..] -> 2
+            _ -> 3
+          }
+        }
+
+        fn hashes(x: Option<ByteArray>) -> Int {
+          when x is {
+            Some(#"ff") -> 1
+            Some(_) -> 2
+            None -> 3
+          }
+        }
+
+        test foo() {
+          classify([]) == 0 &&
+            classify([1]) == 1 &&
+            classify([1, 2, 3]) == 3 &&
+            classify([1, 2]) == 4 &&
+            classify([1, 2, 3, 4]) == 4 &&
+            classify([0, 2]) == 5 &&
+            classify([2]) == 6 &&
+            classify([0, 2, 3]) == 6 &&
+            names([@"foo", @"bar"]) == 1 &&
+            names([@"foo"]) == 2 &&
+            names([@"bar"]) == 3 &&
+            hashes(Some(#"ff")) == 1 &&
+            hashes(Some(#"00")) == 2 &&
+            hashes(None) == 3
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn when_list_clauses_with_nested_checks_fall_through() {
+    assert_test(
+        r#"
+        fn length(xs: List<a>) -> Int {
+          when xs is {
+            [] -> 0
+            [_, ..rest] -> 1 + length(rest)
+          }
+        }
+
+        fn classify(xs: List<Option<Int>>) -> Int {
+          when xs is {
+            [None] -> 1
+            [Some(x), ..] if x > 10 -> 2
+            [None, None] -> 3
+            [_, Some(_)] as ys -> length(ys) + 2
+            _ -> 6
+          }
+        }
+
+        test foo() {
+          classify([None]) == 1 &&
+            classify([Some(2)]) == 6 &&
+            classify([Some(11), None]) == 2 &&
+            classify([None, None]) == 3 &&
+            classify([None, Some(1)]) == 4 &&
+            classify([None, None, None]) == 6 &&
+            classify([]) == 6
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn closures_passed_to_functions() {
+    assert_test(
+        r#"
+        fn apply(f: fn(a) -> b, x: a) -> b {
+          f(x)
+        }
+
+        fn map(xs: List<a>, f: fn(a) -> b) -> List<b> {
+          when xs is {
+            [] -> []
+            [x, ..rest] -> [f(x), ..map(rest, f)]
+          }
+        }
+
+        fn compose(f: fn(b) -> c, g: fn(a) -> b) -> fn(a) -> c {
+          fn(x) { f(g(x)) }
+        }
+
+        test foo() {
+          let offset = 10
+          let prefix = #"ff"
+          apply(fn(n) { n + offset }, 1) == 11 &&
+            map([1, 2, 3], fn(n) { n * offset }) == [10, 20, 30] &&
+            map([#"00"], fn(bytes) { prefix <> bytes }) == [#"ff00"] &&
+            apply(compose(fn(n) { n - offset }, fn(n) { n * 2 }), 7) == 4 &&
+            apply(fn(xs) { map(xs, fn(x) { apply(fn(y) { y + offset }, x) }) }, [0]) == [10]
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn every_binary_operator() {
+    let src = r#"
+        fn check(a: Int, b: Int, t: Bool, f: Bool, s: String, bytes: ByteArray) -> Bool {
+          (t && t) == True &&
+            (f || t) == True &&
+            a != b &&
+            a < b &&
+            a <= b &&
+            b > a &&
+            b >= a &&
+            a + b == 10 &&
+            b - a == 4 &&
+            a * b == 21 &&
+            b / a == 2 &&
+            b % a == 1 &&
+            s <> @"!" == @"hi!" &&
+            bytes <> #"01" == #"0001"
+        }
+
+        test foo() {
+          check(3, 7, True, False, @"hi", #"00")
+        }
+        "#;
+
+    assert_test(src, "foo");
+
+    let program: Program<NamedDeBruijn> = compile_unoptimized_test(src, "foo").try_into().unwrap();
+
+    assert_eq!(
+        program.eval(ExBudget::default()).result().unwrap(),
+        Term::Constant(Constant::Bool(true).into())
+    );
+}
+
+#[test]
+fn when_last_clause_with_nested_literal_is_checked() {
+    let src = r#"
+        fn first(pair: (Int, Int)) -> Int {
+          when pair is {
+            (0, _) -> 0
+            (1, _) -> 1
+          }
+        }
+
+        fn amount(x: Option<Int>) -> Int {
+          when x is {
+            None -> 0
+            Some(1) -> 1
+          }
+        }
+
+        test matched() {
+          first((0, 5)) == 0 && first((1, 5)) == 1 && amount(None) == 0 && amount(Some(1)) == 1
+        }
+
+        test unmatched_tuple() {
+          first((2, 0)) == 1
+        }
+
+        test unmatched_constructor() {
+          amount(Some(2)) == 1
+        }
+    "#;
+
+    assert_test(src, "matched");
+
+    for test_name in ["unmatched_tuple", "unmatched_constructor"] {
+        let mut result = eval_test_with_dependencies(&[], src, test_name);
+
+        assert!(result.failed(), "{test_name}");
+        assert_eq!(result.logs(), vec!["unmatched pattern".to_string()]);
+    }
+}
+
+#[test]
+fn when_last_constructor_clause_is_checked() {
+    let src = r#"
+        type Choice {
+          A
+          B
+          C
+        }
+
+        fn pick(choice: Choice) -> Int {
+          when choice is {
+            A -> 1
+            C -> 3
+            B -> 2
+          }
+        }
+
+        fn picker() -> fn(Choice) -> Int {
+          pick
+        }
+
+        test known_constructors() {
+          pick(A) == 1 && pick(B) == 2 && pick(C) == 3
+        }
+        "#;
+
+    assert_test(src, "known_constructors");
+
+    let pick: Program<DeBruijn> = with_generator(src, |mut generator, modules| {
+        generator.generate_test(find_body(modules, "picker"))
+    })
+    .try_into()
+    .unwrap();
+
+    let eval = |index: u64| {
+        let program: Program<NamedDeBruijn> = pick.apply_data(Data::constr(index, vec![])).into();
+
+        program.eval(ExBudget::default())
+    };
+
+    assert_eq!(
+        eval(1).result().expect("evaluation failed"),
+        Term::Constant(Constant::Integer(2.into()).into())
+    );
+
+    // Not a constructor of `Choice`, so it must not be taken for the last clause.
+    let mut unmatched = eval(3);
+    assert!(unmatched.failed());
+    assert_eq!(unmatched.logs(), vec!["unmatched pattern".to_string()]);
+}
+
+#[test]
+fn when_unmatched_integer_fails() {
+    let src = r#"
+        fn name(n: Int) -> ByteArray {
+          when n is {
+            1 -> "one"
+            2 -> "two"
+          }
+        }
+
+        test matched() {
+          name(1) == "one" && name(2) == "two"
+        }
+
+        test unmatched() {
+          name(3) == "two"
+        }
+    "#;
+
+    assert_test(src, "matched");
+
+    let mut unmatched = eval_test_with_dependencies(&[], src, "unmatched");
+
+    assert!(unmatched.failed());
+    assert_eq!(unmatched.logs(), vec!["unmatched pattern".to_string()]);
+}
+
+#[test]
+fn single_use_function_is_inlined() {
+    let src = r#"
+        fn double(n: Int) -> Int {
+          n * 2
+        }
+
+        test single_use() {
+          double(21) == 42
+        }
+
+        test hand_inlined() {
+          fn(n: Int) { n * 2 }(21) == 42
+        }
+        "#;
+
+    assert_test(src, "single_use");
+    assert_test(src, "hand_inlined");
+
+    let size = |test_name| {
+        let program: Program<DeBruijn> = compile_test(src, test_name, true).try_into().unwrap();
+
+        program.to_flat().unwrap().len()
+    };
+
+    assert_eq!(size("single_use"), size("hand_inlined"));
+
+    let optimized = compile_test(src, "single_use", true);
+
+    assert_eq!(count_binders(&optimized.term, "test_module_double"), 0);
+}
+
+#[test]
+fn single_use_function_is_kept_when_not_optimizing() {
+    let src = r#"
+        fn double(n: Int) -> Int {
+          n * 2
+        }
+
+        test single_use() {
+          double(21) == 42
+        }
+        "#;
+
+    let program = compile_unoptimized_test(src, "single_use");
+
+    assert_eq!(
+        count_binders(&program.term, "test_module_double"),
+        1,
+        "{}",
+        program.to_pretty()
+    );
+}
+
+#[test]
+fn try_generate_unsupported_expression() {
+    let result = try_generate_test(
+        r#"
+        test foo() {
+          expect Some(1) = Some(1)
+          True
+        }
+        "#,
+        "foo",
+        true,
+    );
+
+    assert!(
+        matches!(
+            result,
+            Err(CodeGenError::UnsupportedExpression { ref construct, .. })
+                if construct == "nested integer patterns"
+        ),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn try_generate_unsupported_constructs() {
+    let cases = [
+        (
+            "expect Some(x) as y = Some(1)\n  x == 1",
+            "assign patterns",
+            "Some(x) as y",
+        ),
+        (
+            "expect [Some(x) as y] = [Some(1)]\n  x == 1",
+            "nested assign patterns",
+            "Some(x) as y",
+        ),
+        ("expect [1] = [1]\n  True", "nested integer patterns", "1"),
+        (
+            "let f = builtin.if_then_else\n  f(True, 1, 2) == 1",
+            "the builtin 'if_then_else'",
+            "builtin.if_then_else",
+        ),
+        (
+            "builtin.cons_list(1, []) == [1]",
+            "the builtin 'cons_list'",
+            "builtin.cons_list",
+        ),
+    ];
+
+    for (body, expected_construct, expected_source) in cases {
+        let src = format!("use aiken/builtin\n\ntest foo() {{\n  {body}\n}}");
+
+        let result = try_generate_test(&src, "foo", true);
+
+        match &result {
+            Err(CodeGenError::UnsupportedExpression {
+                construct,
+                location,
+            }) => {
+                assert_eq!(construct, expected_construct, "{src}");
+                assert_eq!(&src[location.start..location.end], expected_source, "{src}");
+            }
+            result => panic!("{src}\n{result:?}"),
+        }
+
+        assert_eq!(
+            check_supported_test(&src, "foo").map_err(CodeGenError::from),
+            result.map(|_| ()),
+            "{src}"
+        );
+    }
+}
+
+#[test]
+fn check_supported_accepts_what_generates() {
+    let src = r#"
+        use aiken/builtin
+
+        test foo() {
+          let pair = builtin.mk_pair_data(builtin.i_data(1), builtin.i_data(2))
+          when [Some(1)] is {
+            [Some(1), ..] -> builtin.fst_pair(pair) == builtin.i_data(1)
+            _ -> False
+          }
+        }
+    "#;
+
+    assert!(try_generate_test(src, "foo", true).is_ok());
+    assert_eq!(check_supported_test(src, "foo"), Ok(()));
+}
+
+#[test]
+fn supported_features_lists_unsupported_builtins() {
+    let features = supported::supported_features();
+
+    assert!(!features.supports(UnsupportedKind::Builtin(DefaultFunction::IfThenElse)));
+    assert!(!features.supports(UnsupportedKind::NestedAssignPattern));
+    assert!(features.supports(UnsupportedKind::Builtin(DefaultFunction::HeadList)));
+}
+
+#[test]
+fn try_generate_missing_data_type() {
+    let result = try_generate_test(
+        r#"
+        type Choice {
+          A
+          B
+        }
+
+        test foo() {
+          let choice = A
+          when choice is {
+            A -> True
+            B -> False
+          }
+        }
+        "#,
+        "foo",
+        false,
+    );
+
+    assert_eq!(
+        result,
+        Err(CodeGenError::MissingDataType {
+            name: "Choice".to_string()
+        })
+    );
+}
+
+#[test]
+fn try_generate_missing_data_type_in_nested_patterns() {
+    for body in [
+        "when [A] is {\n    [A] -> True\n    _ -> False\n  }",
+        "when [A] is {\n    [A as a, ..] -> a == A\n    _ -> False\n  }",
+    ] {
+        let src = format!("type Choice {{\n  A\n  B\n}}\n\ntest foo() {{\n  {body}\n}}");
+
+        assert_eq!(
+            try_generate_test(&src, "foo", false),
+            Err(CodeGenError::MissingDataType {
+                name: "Choice".to_string()
+            }),
+            "{src}"
+        );
+    }
+}
+
+#[test]
+fn try_generate_bool_without_data_types() {
+    let program = try_generate_test(
+        r#"
+        test foo() {
+          True
+        }
+        "#,
+        "foo",
+        false,
+    )
+    .unwrap();
+
+    assert_eq!(program.term, Term::bool(true));
+
+    let result = try_generate_test(
+        r#"
+        type Choice {
+          A
+          B
+        }
+
+        test foo() {
+          A != B
+        }
+        "#,
+        "foo",
+        false,
+    );
+
+    assert_eq!(
+        result,
+        Err(CodeGenError::MissingDataType {
+            name: "Choice".to_string()
+        })
+    );
+}
+
+#[test]
+fn try_generate_missing_function() {
+    let result = try_generate_test(
+        r#"
+        fn double(n: Int) -> Int {
+          n * 2
+        }
+
+        test foo() {
+          double(21) == 42
+        }
+        "#,
+        "foo",
+        false,
+    );
+
+    assert_eq!(
+        result,
+        Err(CodeGenError::MissingFunction {
+            module: "test_module".to_string(),
+            name: "double".to_string()
+        })
+    );
+}
+
+#[test]
+fn int_literals_with_underscores_and_radix() {
+    let src = r#"
+        test foo() {
+          1_000 == 1000 && 0xff == 255 && 0b101 == 5
+        }
+        "#;
+
+    assert_test(src, "foo");
+
+    let program = compile_unoptimized_test(src, "foo").to_pretty();
+
+    for constant in ["1000", "255", "5"] {
+        assert!(
+            program.contains(&format!("(con integer {constant})")),
+            "{program}"
+        );
+    }
+}
+
+#[test]
+fn record_access_string_field() {
+    assert_test(
+        r#"
+        type Person {
+          Person { name: String, age: Int }
+        }
+
+        test foo() {
+          let person = Person { name: @"Alice", age: 42 }
+          person.name == @"Alice"
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn record_access_int_list_field() {
+    assert_test(
+        r#"
+        type Person {
+          Person { name: String, scores: List<Int> }
+        }
+
+        fn sum(xs: List<Int>) -> Int {
+          when xs is {
+            [] -> 0
+            [x, ..rest] -> x + sum(rest)
+          }
+        }
+
+        test foo() {
+          let person = Person { name: @"Alice", scores: [1, 2, 3] }
+          let first =
+            when person.scores is {
+              [x, ..] -> x
+              [] -> 0
+            }
+          first + 1 == 2 && sum(person.scores) == 6 && person.scores == [1, 2, 3]
+        }
+        "#,
+        "foo",
+    );
+}
+
+#[test]
+fn expect_deeper_than_limit_fails_to_compile() {
+    let src = r#"
+        use aiken/builtin
+
+        test shallow() {
+          let data = builtin.list_data([])
+          expect xs: List<List<Int>> = data
+          xs == []
+        }
+
+        test deep() {
+          let data = builtin.list_data([])
+          expect xs: List<List<List<List<Int>>>> = data
+          xs == []
+        }
+        "#;
+
+    assert_test(src, "shallow");
+
+    let (shallow, deep) = with_generator(src, |mut generator, modules| {
+        generator.set_expect_depth_limit(3);
+
+        (
+            generator.try_generate_test(find_body(modules, "shallow")),
+            generator.try_generate_test(find_body(modules, "deep")),
+        )
+    });
+
+    assert!(shallow.is_ok());
+
+    let error = deep.unwrap_err();
+
+    assert_eq!(
+        error,
+        CodeGenError::ExpectTooDeep {
+            tipo: "List<Int>".to_string(),
+            limit: 3
+        }
+    );
+
+    assert_eq!(
+        error.to_string(),
+        "I can't expect a 'List<Int>' more than 3 levels deep into a type."
+    );
+}
+
+#[test]
+fn record_access_bool_field() {
+    let src = r#"
+        type Flags {
+          Flags { enabled: Bool, count: Int }
+        }
+
+        fn enabled(flags: Flags) -> Int {
+          if flags.enabled {
+            flags.count
+          } else {
+            0
+          }
+        }
+
+        test foo() {
+          enabled(Flags { enabled: True, count: 3 }) == 3 && enabled(Flags { enabled: False, count: 3 }) == 0
+        }
+        "#;
+
+    assert_test(src, "foo");
+}
+
+#[test]
+fn record_access_chained_fields() {
+    let src = r#"
+        type Inner {
+          Inner { b: Int, flag: Bool }
+        }
+
+        type Outer {
+          Outer { a: Inner, label: ByteArray }
+        }
+
+        test foo() {
+          let outer = Outer { a: Inner { b: 7, flag: True }, label: "x" }
+          let Inner { b, .. } = outer.a
+          outer.a.b == 7 && outer.a.flag && b == 7
+        }
+        "#;
+
+    assert_test(src, "foo");
+}
+
+#[test]
+fn generate_test_evaluates_to_bool() {
+    let src = r#"
+        test foo() {
+          1 + 1 == 2
+        }
+
+        test bar() {
+          1 + 1 == 3
+        }
+        "#;
+
+    assert_test(src, "foo");
+
+    assert_eq!(
+        eval_test_with_dependencies(&[], src, "bar")
+            .result()
+            .expect("evaluation failed"),
+        Term::bool(false)
+    );
+}
+
+#[test]
+fn integer_if_chain_compiles_like_when() {
+    let src = r#"
+        fn with_if(x: Int) -> Int {
+          if x == 0 {
+            10
+          } else if x == 1 {
+            11
+          } else if 2 == x {
+            12
+          } else if x == 3 {
+            13
+          } else {
+            0
+          }
+        }
+
+        fn with_when(x: Int) -> Int {
+          when x is {
+            0 -> 10
+            1 -> 11
+            2 -> 12
+            3 -> 13
+            _ -> 0
+          }
+        }
+
+        test if_chain() {
+          with_if(0) == 10 && with_if(2) == 12 && with_if(3) == 13 && with_if(7) == 0
+        }
+
+        test when_chain() {
+          with_when(0) == 10 && with_when(2) == 12 && with_when(3) == 13 && with_when(7) == 0
+        }
+        "#;
+
+    assert_test(src, "if_chain");
+    assert_test(src, "when_chain");
+
+    let flat = |test_name| {
+        let program: Program<DeBruijn> = try_generate_test(src, test_name, true)
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        program.to_flat().unwrap()
+    };
+
+    assert_eq!(flat("if_chain"), flat("when_chain"));
+}
+
+#[test]
+fn generated_program_uses_configured_version() {
+    let src = r#"
+        test foo() {
+          True
+        }
+        "#;
+
+    with_generator(src, |mut generator, modules| {
+        let body = find_body(modules, "foo");
+
+        assert_eq!(generator.generate_test(body).version, (1, 0, 0));
+
+        generator.set_version((1, 1, 0));
+
+        assert_eq!(generator.generate_test(body).version, (1, 1, 0));
+    });
+}
+
+#[test]
+fn validator_leading_trace() {
+    let program: Program<DeBruijn> = with_validator(
+        r#"
+        validator {
+          fn mint(n: Int, _ctx: Data) -> Bool {
+            trace "entering mint"
+            n > 0
+          }
+        }
+        "#,
+        |mut generator, validator| generator.generate(validator),
+    )
+    .try_into()
+    .unwrap();
+
+    let eval = |n: i64| {
+        let program: Program<NamedDeBruijn> = program
+            .apply_data(Data::integer(n.into()))
+            .apply_data(Data::constr(0, vec![]))
+            .into();
+
+        program.eval(ExBudget::default())
+    };
+
+    let mut success = eval(1);
+    assert_eq!(success.logs(), vec!["entering mint".to_string()]);
+    assert_eq!(
+        success.result().expect("evaluation failed"),
+        Term::Constant(Constant::Unit.into())
+    );
+
+    let mut failure = eval(0);
+    assert_eq!(failure.logs(), vec!["entering mint".to_string()]);
+    assert!(failure.result().is_err());
+}
+
+#[test]
+fn aliased_data_type_in_pattern() {
+    assert_test(
+        r#"
+        type Shape {
+          Circle(Int)
+          Rectangle(Int, Int)
+        }
+
+        type Figure =
+          Shape
+
+        type MaybeInt =
+          Option<Int>
+
+        fn area(figure: Figure) -> Int {
+          when figure is {
+            Circle(r) -> 3 * r * r
+            Rectangle(w, h) -> w * h
+          }
+        }
+
+        fn or_zero(n: MaybeInt) -> Int {
+          when n is {
+            Some(n) -> n
+            None -> 0
+          }
+        }
+
+        test foo() {
+          let figure: Figure = Rectangle(2, 3)
+          area(figure) == 6 && area(Circle(1)) == 3 && or_zero(Some(4)) == 4 && or_zero(None) == 0
+        }
+        "#,
+        "foo",
+    );
+}
+
+/// The term a hoisted definition is bound to, found by the name it's bound to.
+fn find_definition<'a>(term: &'a Term<Name>, name: &str) -> Option<&'a Term<Name>> {
+    match term {
+        Term::Apply { function, argument } => match function.as_ref() {
+            Term::Lambda { parameter_name, .. } if parameter_name.text == name => Some(argument),
+            _ => find_definition(function, name).or_else(|| find_definition(argument, name)),
+        },
+        Term::Lambda { body, .. } => find_definition(body, name),
+        Term::Delay(term) | Term::Force(term) => find_definition(term, name),
+        Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) => None,
+    }
+}
+
+/// How many lambdas bind the given name.
+fn count_binders(term: &Term<Name>, name: &str) -> usize {
+    match term {
+        Term::Apply { function, argument } => {
+            count_binders(function, name) + count_binders(argument, name)
+        }
+        Term::Lambda {
+            parameter_name,
+            body,
+        } => usize::from(parameter_name.text == name) + count_binders(body, name),
+        Term::Delay(term) | Term::Force(term) => count_binders(term, name),
+        Term::Var(_) | Term::Constant(_) | Term::Error | Term::Builtin(_) => 0,
+    }
+}
+
+#[test]
+fn generated_names_do_not_depend_on_unrelated_functions() {
+    let src = r#"
+        fn f(x: Option<(Int, Int)>) -> Int {
+          when x is {
+            Some((a, b)) -> a + b
+            None -> 0
+          }
+        }
+
+        fn g(xs: List<Option<Int>>) -> Int {
+          when xs is {
+            [Some(y), ..] -> y
+            _ -> 1
+          }
+        }
+
+        test without_g() {
+          f(Some((1, 2))) + f(None) == 3
+        }
+
+        test with_g() {
+          g([None]) + f(Some((1, 2))) + f(None) == 4
+        }
+    "#;
+
+    let without_g = compile_unoptimized_test(src, "without_g");
+    let with_g = compile_unoptimized_test(src, "with_g");
+
+    let f_without_g = find_definition(&without_g.term, "test_module_f").unwrap();
+    let f_with_g = find_definition(&with_g.term, "test_module_f").unwrap();
+
+    assert_eq!(f_without_g.to_pretty(), f_with_g.to_pretty());
+    assert!(f_with_g.to_pretty().contains("__subject_name_"));
+}
+
+#[test]
+fn generator_is_reusable_across_validators() {
+    let mut project = TestProject::new();
+
+    let mut modules = CheckedModules::default();
+
+    for (name, src) in [
+        (
+            "multi",
+            r#"
+            validator {
+              fn spend(_datum: Data, redeemer: List<Int>, _ctx: Data) -> Bool {
+                redeemer == [1, 2]
+              }
+
+              fn mint(_redeemer: Data, _ctx: Data) -> Bool {
+                True
+              }
+            }
+            "#,
+        ),
+        (
+            "single",
+            r#"
+            fn is_positive(n: Int) -> Bool {
+              n > 0
+            }
+
+            validator {
+              fn mint(redeemer: Int, _ctx: Data) -> Bool {
+                is_positive(redeemer)
+              }
+            }
+            "#,
+        ),
+    ] {
+        let module = project.check(project.parse_module(name, ModuleKind::Validator, src));
+        modules.insert(module.name.clone(), module);
+    }
+
+    let new_generator = || {
+        modules.new_generator(
+            &project.functions,
+            &project.data_types,
+            &project.module_types,
+        )
+    };
+
+    let mut shared = new_generator();
+
+    for _ in 0..2 {
+        for (_, validator) in modules.validators() {
+            let reused: Program<DeBruijn> = shared
+                .generate(validator)
+                .try_into()
+                .expect("generated program has free variables");
+
+            let fresh: Program<DeBruijn> = new_generator().generate(validator).try_into().unwrap();
+
+            assert_eq!(reused, fresh);
+        }
+    }
+}
+
+#[test]
+fn when_bytearray_and_string_literals() {
+    let src = r#"
+        fn describe(hash: ByteArray) -> Int {
+          when hash is {
+            #"deadbeef" -> 1
+            "foo" -> 2
+            _ -> 3
+          }
+        }
+
+        fn greet(name: String) -> String {
+          when name is {
+            @"alice" -> @"hi alice"
+            _ -> @"who?"
+          }
+        }
+
+        test matches_literals() {
+          describe(#"deadbeef") == 1 && describe("foo") == 2 && describe(#"00") == 3 && greet(
+            @"alice",
+          ) == @"hi alice" && greet(@"bob") == @"who?"
+        }
+
+        fn only(hash: ByteArray) -> Int {
+          when hash is {
+            #"deadbeef" -> 1
+          }
+        }
+
+        test unmatched() {
+          only(#"00") == 1
+        }
+    "#;
+
+    assert_test(src, "matches_literals");
+
+    let mut unmatched = eval_test_with_dependencies(&[], src, "unmatched");
+
+    assert!(unmatched.failed());
+    assert_eq!(unmatched.logs(), vec!["unmatched pattern".to_string()]);
+}
+
+#[test]
+fn generator_from_owned_context() {
+    let src = r#"
+        fn double(n: Int) -> Int {
+          n * 2
+        }
+
+        test doubled() {
+          double(21) == 42
+        }
+    "#;
+
+    // Nothing borrowed from the project outlives this block: the context owns
+    // everything the generator needs.
+    let (context, body) = with_generator(src, |generator, modules| {
+        (
+            Rc::new(generator.context()),
+            find_body(modules, "doubled").clone(),
+        )
+    });
+
+    let shared = Rc::clone(&context);
+
+    let program: Program<NamedDeBruijn> =
+        shared.generator().generate_test(&body).try_into().unwrap();
+
+    let eval = program.eval(ExBudget::default());
+
+    assert!(!eval.failed());
+    assert_eq!(eval.result().unwrap(), Term::bool(true));
+}
+
+#[test]
+fn string_literals_are_not_interpolated() {
+    // Strings have no interpolated segments: braces are plain characters and
+    // the literal is emitted as a single constant.
+    let src = r#"
+        use aiken/builtin
+
+        test braces() {
+          let name = @"world"
+          let greeting = @"hello {name}"
+          greeting != builtin.append_string(@"hello ", name) && builtin.length_of_bytearray(
+            builtin.encode_utf8(greeting),
+          ) == 12
+        }
+    "#;
+
+    assert_test(src, "braces");
+
+    let program = compile_unoptimized_test(src, "braces");
+
+    assert!(program
+        .to_pretty()
+        .contains(r#"(con string "hello {name}")"#));
+}
+
+#[test]
+fn data_type_with_many_constructors() {
+    let constructors = (0..300)
+        .map(|i| format!("C{i}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let src = format!(
+        r#"
+        use aiken/builtin
+
+        type Big {{
+          {constructors}
+          Last(Int)
+        }}
+
+        fn as_data(d: Data) -> Data {{
+          d
+        }}
+
+        fn index(big: Big) -> Int {{
+          when big is {{
+            C0 -> 0
+            C299 -> 299
+            Last(n) -> n
+            _ -> -1
+          }}
+        }}
+
+        test zero_fields() {{
+          let (tag, fields) = builtin.un_constr_data(as_data(C299))
+          tag == 299 && fields == []
+        }}
+
+        test constructor_as_function() {{
+          let last = Last
+          let (tag, fields) = builtin.un_constr_data(as_data(last(42)))
+          tag == 300 && fields == [as_data(42)]
+        }}
+
+        test matches_high_tags() {{
+          index(C0) == 0 && index(C299) == 299 && index(Last(300)) == 300 && index(C150) == -1
+        }}
+    "#
+    );
+
+    assert_test(&src, "zero_fields");
+    assert_test(&src, "constructor_as_function");
+    assert_test(&src, "matches_high_tags");
+}
+
+#[test]
+fn data_builtins_round_trip() {
+    let src = r#"
+        use aiken/builtin
+
+        test serialise_integer() {
+          builtin.serialise_data(builtin.i_data(42)) == #"182a"
+        }
+
+        test round_trips() {
+          let int = builtin.i_data(42)
+          let bytes = builtin.b_data(#"cafe")
+          let list = builtin.list_data([int, bytes])
+          let map = builtin.map_data([(int, bytes)])
+          let constr = builtin.constr_data(1, [int])
+          let (tag, fields) = builtin.un_constr_data(constr)
+          builtin.un_i_data(int) == 42 && builtin.un_b_data(bytes) == #"cafe" && builtin.un_list_data(
+            list,
+          ) == [int, bytes] && builtin.un_map_data(map) == [(int, bytes)] && tag == 1 && fields == [
+            int,
+          ] && builtin.serialise_data(constr) == #"d87a9f182aff"
+        }
+    "#;
+
+    assert_test(src, "serialise_integer");
+    assert_test(src, "round_trips");
+}
+
+#[test]
+fn partially_applied_functions() {
+    // Calls must be saturated; partial application goes through a capture,
+    // which the type checker turns into a lambda over the missing argument.
+    let src = r#"
+        fn add(a: Int, b: Int) -> Int {
+          a + b
+        }
+
+        fn sub(a: Int, b: Int) -> Int {
+          a - b
+        }
+
+        fn apply(f: fn(Int) -> Int, n: Int) -> Int {
+          f(n)
+        }
+
+        test partial() {
+          let add5 = add(5, _)
+          let minus1 = sub(_, 1)
+          add5(2) == 7 && minus1(10) == 9 && apply(add5, apply(minus1, 3)) == 7 && apply(
+            add(1, _),
+            1,
+          ) == 2
+        }
+    "#;
+
+    assert_test(src, "partial");
+}
+
+#[test]
+fn function_bodies_are_not_wrapped() {
+    // Only validators are wrapped to return unit or fail; any other
+    // expression compiles to the value it evaluates to.
+    let src = r#"
+        fn double(n: Int) -> Int {
+          n * 2
+        }
+
+        fn answer() -> Int {
+          double(21)
+        }
+    "#;
+
+    let program = with_generator(src, |mut generator, modules| {
+        generator.generate_test(find_body(modules, "answer"))
+    });
+
+    let (term, logs) = gen_uplc::eval(&program).unwrap();
+
+    assert_eq!(term, Term::integer(42.into()));
+    assert!(logs.is_empty(), "{logs:?}");
+}
+
+#[test]
+fn when_guarded_integer_clauses() {
+    let src = r#"
+        fn sign(n: Int, strict: Bool) -> Int {
+          when n is {
+            0 if strict -> 100
+            0 -> 0
+            x if x > 100 && strict -> 100
+            x if x > 0 -> 1
+            _ -> -1
+          }
+        }
+
+        test guards() {
+          sign(0, True) == 100 && sign(0, False) == 0 && sign(500, True) == 100 && sign(
+            500,
+            False,
+          ) == 1 && sign(5, True) == 1 && sign(-3, True) == -1
+        }
+
+        fn size(opt: Option<Int>, big: Int) -> Int {
+          when opt is {
+            Some(n) if n > big -> 2
+            _ if big == 0 -> 0
+            Some(_) -> 1
+            None -> -1
+          }
+        }
+
+        test constructor_guards() {
+          size(Some(10), 5) == 2 && size(Some(1), 5) == 1 && size(None, 0) == 0 && size(
+            None,
+            5,
+          ) == -1 && size(Some(10), 0) == 2
+        }
+
+        fn positive(n: Int) -> Int {
+          when n is {
+            0 -> 0
+            x if x > 0 -> x
+          }
+        }
+
+        test unmatched() {
+          positive(-1) == -1
+        }
+    "#;
+
+    assert_test(src, "guards");
+    assert_test(src, "constructor_guards");
+
+    let mut unmatched = eval_test_with_dependencies(&[], src, "unmatched");
+
+    assert!(unmatched.failed());
+    assert_eq!(unmatched.logs(), vec!["unmatched pattern".to_string()]);
+}
+
+#[test]
+fn when_clauses_reference_list_subject() {
+    let src = r#"
+        fn length(xs: List<a>) -> Int {
+          when xs is {
+            [] -> 0
+            [_, ..rest] -> 1 + length(rest)
+          }
+        }
+
+        fn numbers(n: Int) -> List<Int> {
+          trace @"numbers"
+          if n == 0 {
+            []
+          } else {
+            [n, ..numbers(n - 1)]
+          }
+        }
+
+        fn describe(n: Int) -> Int {
+          when numbers(n) is {
+            [] -> 0
+            [x] as one -> x + length(one) * 10
+            [x, y, ..] as many -> x + y + length(many) * 100
+          }
+        }
+
+        fn total(pair: (Int, List<Int>)) -> Int {
+          when pair is {
+            (0, _) as whole -> whole.1st
+            (_, [x, ..]) as whole -> x + whole.1st
+            whole -> whole.1st - 1
+          }
+        }
+
+        test lists() {
+          describe(0) == 0 && describe(1) == 11 && describe(3) == 305
+        }
+
+        test tuples() {
+          total((0, [])) == 0 && total((4, [1])) == 5 && total((4, [])) == 3
+        }
+    "#;
+
+    assert_eq!(assert_test(src, "lists").len(), 1 + 2 + 4);
+    assert_test(src, "tuples");
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aiken_lang::{
+    ast::{ModuleKind, Tracing, TypedDataType, TypedFunction},
+    builtins,
+    gen_uplc::builder::{DataTypeKey, FunctionAccessKey},
+    parser,
+    tipo::TypeInfo,
+    IdGenerator,
+};
+use indexmap::IndexMap;
+
+use tempfile::TempDir;
+
+use crate::{
+    config::Config,
+    module::{content_hash, CheckedModule, ParsedModule},
+    package_name::PackageName,
+    telemetry::EventListener,
+    Project,
+};
+
+mod corpus;
+mod gen_uplc;
+mod module;
+
+// TODO: Possible refactor this out of the module and have it used by `Project`. The idea would
+// be to make this struct below the actual project, and wrap it in another metadata struct
+// which contains all the config and I/O stuff regarding the project.
+pub struct TestProject {
+    pub package: PackageName,
+    pub id_gen: IdGenerator,
+    pub module_types: HashMap<String, TypeInfo>,
+    pub functions: IndexMap<FunctionAccessKey, TypedFunction>,
+    pub data_types: IndexMap<DataTypeKey, TypedDataType>,
+}
+
+impl TestProject {
+    pub fn new() -> Self {
+        let id_gen = IdGenerator::new();
+
+        let package = package_name();
+
+        let mut module_types = HashMap::new();
+        module_types.insert("aiken".to_string(), builtins::prelude(&id_gen));
+        module_types.insert("aiken/builtin".to_string(), builtins::plutus(&id_gen));
+
+        let functions = builtins::prelude_functions(&id_gen);
+        let data_types = builtins::prelude_data_types(&id_gen);
+
+        TestProject {
+            package,
+            id_gen,
+            module_types,
+            functions,
+            data_types,
+        }
+    }
+
+    pub fn parse(&self, source_code: &str) -> ParsedModule {
+        self.parse_module("test_module", ModuleKind::Validator, source_code)
+    }
+
+    pub fn parse_module(&self, name: &str, kind: ModuleKind, source_code: &str) -> ParsedModule {
+        let name = name.to_owned();
+        let (mut ast, extra) = parser::module(source_code, kind).expect("Failed to parse module");
+        ast.name = name.clone();
+
+        ParsedModule {
+            kind,
+            ast,
+            hash: content_hash(source_code),
+            code: source_code.to_string(),
+            name,
+            path: PathBuf::new(),
+            extra,
+            package: self.package.to_string(),
+        }
+    }
+
+    pub fn check(&mut self, module: ParsedModule) -> CheckedModule {
+        self.check_with_tracing(module, Tracing::NoTraces)
+    }
+
+    pub fn check_with_tracing(&mut self, module: ParsedModule, tracing: Tracing) -> CheckedModule {
+        let mut warnings = vec![];
+
+        let ast = module
+            .ast
+            .infer(
+                &self.id_gen,
+                module.kind,
+                &self.package.to_string(),
+                &self.module_types,
+                tracing,
+                &mut warnings,
+            )
+            .expect("Failed to type-check module");
+
+        self.module_types
+            .insert(module.name.clone(), ast.type_info.clone());
+
+        let mut checked_module = CheckedModule {
+            kind: module.kind,
+            extra: module.extra,
+            name: module.name,
+            code: module.code,
+            package: module.package,
+            input_path: module.path,
+            hash: module.hash,
+            ast,
+        };
+
+        checked_module.attach_doc_and_module_comments();
+
+        checked_module
+    }
+}
+
+/// A project laid out on disk, in a temporary directory removed once dropped.
+pub struct TestProjectDir {
+    dir: TempDir,
+}
+
+impl TestProjectDir {
+    /// Write each `(path, code)` below the project root.
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let dir = tempfile::tempdir().expect("Failed to create a temporary directory");
+
+        for (path, code) in files {
+            let path = dir.path().join(path);
+
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, code).unwrap();
+        }
+
+        TestProjectDir { dir }
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// A project without dependencies, so that nothing is fetched.
+    pub fn project<T: EventListener>(&self, event_listener: T) -> Project<T> {
+        let mut config = Config::default(&package_name());
+        config.dependencies = vec![];
+
+        Project::new_with_config(config, self.root().to_path_buf(), event_listener)
+    }
+}
+
+pub fn package_name() -> PackageName {
+    PackageName {
+        owner: "test".to_owned(),
+        repo: "project".to_owned(),
+    }
+}
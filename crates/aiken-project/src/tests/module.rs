@@ -0,0 +1,811 @@
+use std::{cell::RefCell, collections::HashMap, fs, path::PathBuf};
+
+use aiken_lang::{
+    ast::{Definition, ModuleKind, Tracing},
+    parser,
+};
+
+use crate::{
+    config::Config,
+    error::GetSource,
+    module::{CheckedModules, ParsedModules, Stats},
+    options::CheckOptions,
+    package_name::PackageName,
+    telemetry::{Event, EventListener, Silent},
+    Error, Project, Source,
+};
+
+use uplc::machine::cost_model::ExBudget;
+
+use super::{package_name, TestProject, TestProjectDir};
+
+#[test]
+fn source_span_of_known_and_unknown_names() {
+    let src = r#"
+fn double(n: Int) -> Int {
+  n * 2
+}
+
+type Choice {
+  A
+  B
+}
+"#;
+
+    let mut project = TestProject::new();
+
+    let module = project.check(project.parse(src));
+
+    let span = module.source_span_of("double").expect("double is defined");
+
+    assert_eq!(&src[span.start..span.end], "fn double(n: Int) -> Int");
+
+    assert_eq!(
+        module.source_span_of("Choice").map(|span| span.start),
+        src.find("type Choice")
+    );
+
+    assert_eq!(module.source_span_of("triple"), None);
+}
+
+#[test]
+fn dependencies_with_spans_point_at_imports() {
+    let src = r#"
+use aiken/list
+use aiken/transaction.{ScriptContext}
+
+fn foo() {
+  1
+}
+"#;
+
+    let project = TestProject::new();
+
+    let module = project.parse(src);
+
+    let dependencies = module
+        .dependencies_with_spans()
+        .into_iter()
+        .map(|(name, span)| (name, &src[span.start..span.end]))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        dependencies,
+        vec![
+            ("aiken/list".to_string(), "use aiken/list"),
+            (
+                "aiken/transaction".to_string(),
+                "use aiken/transaction.{ScriptContext}"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn stats_across_modules() {
+    let mut project = TestProject::new();
+
+    let lib = project.check(project.parse_module(
+        "foo/lib",
+        ModuleKind::Lib,
+        r#"
+pub type Choice {
+  A
+  B
+}
+
+type Count =
+  Int
+
+pub fn double(n: Int) -> Int {
+  n * 2
+}
+
+fn triple(n: Int) -> Int {
+  n * 3
+}
+
+test double_two() {
+  double(2) == 4
+}
+"#,
+    ));
+
+    let validators = project.check(project.parse(
+        r#"
+validator {
+  fn spend(_datum: Void, _redeemer: Void, _ctx: Void) -> Bool {
+    True
+  }
+}
+
+validator {
+  fn mint(_redeemer: Void, _ctx: Void) -> Bool {
+    True
+  }
+}
+
+pub fn always() -> Bool {
+  True
+}
+
+test always_true() {
+  always()
+}
+
+test always_not_false() {
+  always() != False
+}
+"#,
+    ));
+
+    let mut modules = CheckedModules::singleton(lib);
+    modules.insert(validators.name.clone(), validators);
+
+    assert_eq!(modules.total_validators(), 2);
+
+    assert_eq!(
+        modules.stats(),
+        Stats {
+            validators: 2,
+            tests: 3,
+            public_functions: 2,
+            types: 2,
+        }
+    );
+}
+
+#[test]
+fn validators_include_publish() {
+    let mut project = TestProject::new();
+
+    let modules = CheckedModules::singleton(project.check(project.parse(
+        r#"
+validator {
+  fn publish(_redeemer: Data, _ctx: Data) -> Bool {
+    True
+  }
+}
+"#,
+    )));
+
+    let names = modules
+        .validators()
+        .map(|(_, validator)| validator.fun.name.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["publish"]);
+}
+
+#[test]
+fn check_validator_arity_by_purpose() {
+    let check = |src: &str| {
+        let mut project = TestProject::new();
+
+        CheckedModules::singleton(project.check(project.parse(src))).check_validator_arity()
+    };
+
+    assert!(check(
+        r#"
+validator {
+  fn spend(_datum: Data, _redeemer: Data, _ctx: Data) -> Bool {
+    True
+  }
+}
+"#
+    )
+    .is_ok());
+
+    let error = check(
+        r#"
+validator {
+  fn spend(_redeemer: Data, _ctx: Data) -> Bool {
+    True
+  }
+}
+"#,
+    )
+    .unwrap_err();
+
+    assert!(
+        matches!(
+            error,
+            Error::MalformedValidator {
+                ref purpose,
+                expected: 3,
+                found: 2,
+                ..
+            } if purpose == "spend"
+        ),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn validator_functions_with_purpose() {
+    let mut project = TestProject::new();
+
+    let module = project.check(project.parse(
+        r#"
+fn helper() -> Bool {
+  True
+}
+
+validator {
+  fn spend(_datum: Data, _redeemer: Data, _ctx: Data) -> Bool {
+    helper()
+  }
+
+  fn mint(_redeemer: Data, _ctx: Data) -> Bool {
+    True
+  }
+}
+
+validator {
+  fn check(_redeemer: Data, _ctx: Data) -> Bool {
+    True
+  }
+}
+"#,
+    ));
+
+    let functions = module
+        .validator_functions()
+        .into_iter()
+        .map(|(purpose, fun)| (purpose, fun.name.as_str(), fun.arguments.len()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(functions, vec![("spend", "spend", 3), ("mint", "mint", 2)]);
+}
+
+#[test]
+fn import_cycle_is_reported_in_order() {
+    let project = TestProject::new();
+
+    let modules: HashMap<_, _> = [
+        ("foo/b", "use foo/c\n"),
+        ("foo/a", "use foo/b\n"),
+        ("foo/c", "use foo/a\n"),
+    ]
+    .into_iter()
+    .map(|(name, src)| {
+        (
+            name.to_string(),
+            project.parse_module(name, ModuleKind::Lib, src),
+        )
+    })
+    .collect();
+
+    let error = ParsedModules::from(modules).sequence().unwrap_err();
+
+    assert!(
+        matches!(
+            error,
+            Error::ImportCycle { ref modules }
+                if modules == &["foo/a", "foo/b", "foo/c", "foo/a"]
+        ),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn duplicate_module_names_are_rejected() {
+    let dir = TestProjectDir::new(&[
+        ("lib/foo.ak", "pub fn one() -> Int { 1 }\n"),
+        ("validators/foo.ak", "pub fn one() -> Int { 1 }\n"),
+    ]);
+
+    let mut project = dir.project(Silent);
+
+    project.read_source_files().unwrap();
+
+    let errors = project
+        .parse_sources(package_name())
+        .err()
+        .expect("duplicate modules were accepted");
+
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [Error::DuplicateModule { module, first, second }]
+                if module == "foo" && first != second
+        ),
+        "{errors:?}"
+    );
+}
+
+#[test]
+fn unused_modules_are_those_no_validator_nor_test_needs() {
+    let project = TestProject::new();
+
+    let modules: HashMap<_, _> = [
+        ("spend", ModuleKind::Validator, "use foo/used\n"),
+        ("foo/used", ModuleKind::Lib, "use foo/deep\n"),
+        ("foo/deep", ModuleKind::Lib, ""),
+        ("foo/orphan", ModuleKind::Lib, "use foo/deep\n"),
+        (
+            "foo/tested",
+            ModuleKind::Lib,
+            "use foo/helper\nuse foo/idle\n\ntest foo() {\n  helper.check()\n}\n",
+        ),
+        (
+            "foo/helper",
+            ModuleKind::Lib,
+            "use foo/deep\n\npub fn check() {\n  True\n}\n",
+        ),
+        ("foo/idle", ModuleKind::Lib, ""),
+        (
+            "foo/unqualified",
+            ModuleKind::Lib,
+            "use foo/shown.{show}\n\ntest bar() {\n  show()\n}\n",
+        ),
+        ("foo/shown", ModuleKind::Lib, "pub fn show() {\n  True\n}\n"),
+        (
+            "foo/own",
+            ModuleKind::Lib,
+            "fn own() {\n  True\n}\n\ntest baz() {\n  own()\n}\n",
+        ),
+        (
+            "foo/shadowing",
+            ModuleKind::Lib,
+            "use foo/shadowed.{value}\n\ntest qux() {\n  let value = 1\n  value == 1\n}\n",
+        ),
+        ("foo/shadowed", ModuleKind::Lib, "pub const value = 1\n"),
+    ]
+    .into_iter()
+    .map(|(name, kind, src)| (name.to_string(), project.parse_module(name, kind, src)))
+    .collect();
+
+    let modules = ParsedModules::from(modules);
+
+    assert_eq!(
+        modules.unused_modules(&["spend".to_string()]),
+        vec![
+            "foo/idle".to_string(),
+            "foo/orphan".to_string(),
+            "foo/own".to_string(),
+            "foo/shadowed".to_string(),
+            "foo/shadowing".to_string(),
+            "foo/tested".to_string(),
+            "foo/unqualified".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn same_module_name_in_two_packages_is_rejected() {
+    let name = package_name();
+
+    let mut project = Project::new_with_config(Config::default(&name), PathBuf::new(), Silent);
+
+    let source = |package: &str| Source {
+        path: PathBuf::from(format!("build/packages/{package}/lib/shared/types.ak")),
+        name: "shared/types".to_string(),
+        code: "pub type Choice {\n  Yes\n  No\n}\n".to_string(),
+        kind: ModuleKind::Lib,
+    };
+
+    project.sources = vec![source("test-dep")];
+
+    let dependency = PackageName {
+        owner: "test".to_string(),
+        repo: "dep".to_string(),
+    };
+
+    assert!(project.parse_sources(dependency).is_ok());
+
+    project.sources = vec![source("test-other")];
+
+    let errors = project
+        .parse_sources(name)
+        .err()
+        .expect("duplicate modules were accepted");
+
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [Error::DuplicateModule { module, .. }] if module == "shared/types"
+        ),
+        "{errors:?}"
+    );
+}
+
+/// Parse and type-check the given `(module, code)` sources, and return the
+/// names of the modules that were parsed, then of those that were checked.
+fn parse_and_check(
+    project: &mut Project<Silent>,
+    sources: &[(&str, &str)],
+    tracing: Tracing,
+) -> (Vec<String>, Vec<String>) {
+    let checkpoint = project.checkpoint();
+
+    project.sources = sources
+        .iter()
+        .map(|(module, code)| Source {
+            path: PathBuf::from(format!("lib/{module}.ak")),
+            name: module.to_string(),
+            code: code.to_string(),
+            kind: ModuleKind::Lib,
+        })
+        .collect();
+
+    let parsed_modules = project.parse_sources(package_name()).unwrap();
+
+    let mut parsed: Vec<String> = parsed_modules.keys().cloned().collect();
+
+    let mut checked = project.type_check(parsed_modules, tracing, true).unwrap();
+
+    project.restore(checkpoint);
+
+    parsed.sort();
+    checked.sort();
+
+    (parsed, checked)
+}
+
+#[test]
+fn type_check_reuses_unchanged_modules() {
+    let dir = TestProjectDir::new(&[]);
+
+    let mut project = dir.project(Silent);
+
+    let mut check =
+        |sources: &[(&str, &str)]| parse_and_check(&mut project, sources, Tracing::NoTraces);
+
+    let a = |n: usize| format!("pub fn a() -> Int {{\n  {n}\n}}\n");
+    let b = "use foo/a\n\npub fn b() -> Int {\n  a.a() + 1\n}\n";
+    let c = |n: usize| format!("pub fn c() -> Int {{\n  {n}\n}}\n");
+
+    assert_eq!(
+        check(&[("foo/a", &a(1)), ("foo/b", b), ("foo/c", &c(3))]),
+        (
+            vec![
+                "foo/a".to_string(),
+                "foo/b".to_string(),
+                "foo/c".to_string()
+            ],
+            vec![
+                "foo/a".to_string(),
+                "foo/b".to_string(),
+                "foo/c".to_string()
+            ]
+        )
+    );
+
+    // unchanged modules aren't even parsed again
+    assert_eq!(
+        check(&[("foo/a", &a(1)), ("foo/b", b), ("foo/c", &c(3))]),
+        (vec![], vec![])
+    );
+
+    // only the changed module is parsed, and it's checked again along with
+    // those importing it
+    assert_eq!(
+        check(&[("foo/a", &a(2)), ("foo/b", b), ("foo/c", &c(3))]),
+        (
+            vec!["foo/a".to_string()],
+            vec!["foo/a".to_string(), "foo/b".to_string()]
+        )
+    );
+
+    assert_eq!(
+        check(&[("foo/a", &a(2)), ("foo/b", b), ("foo/c", &c(4))]),
+        (vec!["foo/c".to_string()], vec!["foo/c".to_string()])
+    );
+
+    let mut modules: Vec<_> = project.checked_modules.keys().cloned().collect();
+    modules.sort();
+    assert_eq!(modules, vec!["foo/a", "foo/b", "foo/c"]);
+}
+
+#[test]
+fn type_check_reuses_modules_checked_by_a_previous_run() {
+    let dir = TestProjectDir::new(&[]);
+
+    let a = "pub fn a() -> Int {\n  1\n}\n";
+    let b = "use foo/a\n\npub fn b() -> Int {\n  let unused = 1\n  a.a()\n}\n";
+
+    let (_, checked) = parse_and_check(
+        &mut dir.project(Silent),
+        &[("foo/a", a), ("foo/b", b)],
+        Tracing::NoTraces,
+    );
+    assert_eq!(checked, vec!["foo/a", "foo/b"]);
+
+    assert!(dir
+        .root()
+        .join("build/cache/test/project/foo/b.json")
+        .exists());
+
+    // a new project picks up what was checked from `build/`
+    let mut project = dir.project(Silent);
+    assert_eq!(
+        parse_and_check(
+            &mut project,
+            &[("foo/a", a), ("foo/b", b)],
+            Tracing::NoTraces
+        ),
+        (vec![], vec![])
+    );
+    assert!(project.checked_modules.contains_key("foo/b"));
+    assert_eq!(project.warnings().len(), 1);
+
+    // other options check everything again, parsing the skipped modules back
+    // from the source they were checked from
+    let mut project = dir.project(Silent);
+    assert_eq!(
+        parse_and_check(
+            &mut project,
+            &[("foo/a", a), ("foo/b", b)],
+            Tracing::KeepTraces
+        ),
+        (vec![], vec!["foo/a".to_string(), "foo/b".to_string()])
+    );
+    assert_eq!(project.warnings().len(), 1);
+}
+
+#[test]
+fn parse_sources_matches_serial_parsing() {
+    let name = package_name();
+
+    let mut project = Project::new_with_config(Config::default(&name), PathBuf::new(), Silent);
+
+    let sources: Vec<Source> = (0..32)
+        .map(|i| Source {
+            path: PathBuf::from(format!("lib/foo/m{i}.ak")),
+            name: format!("foo/m{i}"),
+            code: format!("pub fn f{i}(n: Int) -> Int {{\n  n + {i}\n}}\n"),
+            kind: ModuleKind::Lib,
+        })
+        .collect();
+
+    let expected: HashMap<_, _> = sources
+        .iter()
+        .map(|source| {
+            let (mut ast, extra) = parser::module(&source.code, source.kind).unwrap();
+            ast.name = source.name.clone();
+            (source.name.clone(), (ast, extra))
+        })
+        .collect();
+
+    project.sources = sources;
+
+    let modules = project.parse_sources(name).unwrap();
+
+    assert_eq!(modules.len(), expected.len());
+
+    for (name, (ast, extra)) in expected {
+        let module = &modules[&name];
+        assert_eq!(module.ast, ast);
+        assert_eq!(module.extra, extra);
+    }
+}
+
+#[test]
+fn parse_errors_are_sorted_by_path() {
+    let name = package_name();
+
+    let mut project = Project::new_with_config(Config::default(&name), PathBuf::new(), Silent);
+
+    project.sources = ["c", "a", "b"]
+        .into_iter()
+        .map(|module| Source {
+            path: PathBuf::from(format!("lib/{module}.ak")),
+            name: module.to_string(),
+            code: "fn broken( {".to_string(),
+            kind: ModuleKind::Lib,
+        })
+        .collect();
+
+    let errors = project.parse_sources(name).err().unwrap();
+
+    let paths: Vec<_> = errors.iter().filter_map(|error| error.path()).collect();
+
+    let mut sorted = paths.clone();
+    sorted.sort();
+
+    assert!(!paths.is_empty());
+    assert_eq!(paths, sorted);
+}
+
+#[test]
+fn doc_comments_attach_across_many_definitions() {
+    let mut src = String::from("//// The modulé\n\n");
+
+    for i in 0..200 {
+        src.push_str(&format!(
+            "/// Function {i}\npub fn f{i}(n: Int) -> Int {{\n  // not a doc\n  n + {i}\n}}\n\n"
+        ));
+        src.push_str(&format!(
+            "/// Type {i}\npub type T{i} {{\n  /// Constructor {i}\n  C{i} {{\n    /// Field {i}\n    x: Int,\n  }}\n}}\n\n"
+        ));
+    }
+
+    let mut project = TestProject::new();
+
+    let module = project.check(project.parse_module("foo", ModuleKind::Lib, &src));
+
+    assert_eq!(module.ast.docs, vec![" The modulé".to_string()]);
+
+    let mut functions = 0;
+    let mut types = 0;
+
+    for def in module.ast.definitions() {
+        match def {
+            Definition::Fn(func) => {
+                let i = &func.name[1..];
+                assert_eq!(func.doc.as_deref(), Some(format!(" Function {i}").as_str()));
+                functions += 1;
+            }
+            Definition::DataType(data_type) => {
+                let i = &data_type.name[1..];
+                assert_eq!(
+                    data_type.doc.as_deref(),
+                    Some(format!(" Type {i}").as_str())
+                );
+
+                let constructor = &data_type.constructors[0];
+                assert_eq!(
+                    constructor.doc.as_deref(),
+                    Some(format!(" Constructor {i}").as_str())
+                );
+                assert_eq!(
+                    constructor.arguments[0].doc.as_deref(),
+                    Some(format!(" Field {i}").as_str())
+                );
+                types += 1;
+            }
+            _ => {}
+        }
+    }
+
+    assert_eq!((functions, types), (200, 200));
+}
+
+#[test]
+fn doc_comments_attach_to_type_aliases() {
+    let mut project = TestProject::new();
+
+    let module = project.check(project.parse_module(
+        "foo",
+        ModuleKind::Lib,
+        r#"
+/// A number of lovelaces
+pub type Lovelace =
+  Int
+"#,
+    ));
+
+    assert_eq!(
+        module.ast.definitions().find_map(|def| match def {
+            Definition::TypeAlias(alias) => alias.doc.clone(),
+            _ => None,
+        }),
+        Some(" A number of lovelaces".to_string())
+    );
+}
+
+#[test]
+fn build_dumps_uplc_into_build_uplc_or_given_directory() {
+    let dir = TestProjectDir::new(&[(
+        "validators/always_true.ak",
+        r#"
+validator {
+  fn spend(_datum: Data, _redeemer: Data, _ctx: Data) -> Bool {
+    True
+  }
+}
+"#,
+    )]);
+
+    for (uplc_dir, expected) in [(None, "build/uplc"), (Some("out"), "out")] {
+        let mut project = dir.project(Silent);
+
+        let result = project.build(
+            true,
+            false,
+            false,
+            uplc_dir.map(PathBuf::from),
+            Tracing::NoTraces,
+        );
+
+        assert!(result.is_ok(), "{result:?}");
+
+        let dumped =
+            fs::read_to_string(dir.root().join(expected).join("always_true.spend.uplc")).unwrap();
+
+        let program = uplc::parser::program(&dumped).expect("dumped uplc doesn't parse");
+
+        assert_eq!(program.version, (1, 0, 0));
+    }
+}
+
+#[test]
+fn check_runs_tests_and_reports_failures() {
+    let dir = TestProjectDir::new(&[
+        ("lib/passing.ak", "test one_plus_one() {\n  1 + 1 == 2\n}\n"),
+        (
+            "lib/failing.ak",
+            "test one_plus_two() {\n  trace \"about to fail\"\n  1 + 2 == 4\n}\n",
+        ),
+    ]);
+
+    let check = |match_tests: Option<Vec<String>>| {
+        dir.project(Silent).check(CheckOptions {
+            match_tests,
+            ..CheckOptions::default()
+        })
+    };
+
+    let all = check(None);
+    let only_passing = check(Some(vec!["one_plus_one".to_string()]));
+
+    let errors = all.expect_err("a failing test was reported as passing");
+
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [Error::TestFailure { name, .. }] if name == "one_plus_two"
+        ),
+        "{errors:?}"
+    );
+
+    assert!(only_passing.is_ok(), "{only_passing:?}");
+}
+
+#[derive(Default)]
+struct Budgets(RefCell<Vec<ExBudget>>);
+
+impl EventListener for Budgets {
+    fn handle_event(&self, event: Event) {
+        if let Event::FinishedTests { tests, .. } = event {
+            self.0
+                .borrow_mut()
+                .extend(tests.iter().map(|test| test.spent_budget));
+        }
+    }
+}
+
+#[test]
+fn check_reports_and_limits_test_budgets() {
+    let dir =
+        TestProjectDir::new(&[("lib/trivial.ak", "test one_plus_one() {\n  1 + 1 == 2\n}\n")]);
+
+    let check = |max_mem: Option<i64>, max_cpu: Option<i64>| {
+        let mut project = dir.project(Budgets::default());
+
+        let result = project.check(CheckOptions {
+            max_mem,
+            max_cpu,
+            ..CheckOptions::default()
+        });
+
+        (result, project.event_listener.0.into_inner())
+    };
+
+    let (unbounded, budgets) = check(None, None);
+    let (within, _) = check(Some(1_000_000), Some(1_000_000_000));
+    let (over, _) = check(None, Some(1));
+
+    assert!(unbounded.is_ok(), "{unbounded:?}");
+
+    match budgets.as_slice() {
+        [budget] => {
+            assert!(budget.mem > 0 && budget.mem < 10_000, "{budget:?}");
+            assert!(budget.cpu > 0 && budget.cpu < 10_000_000, "{budget:?}");
+        }
+        _ => panic!("expected a single test, got {budgets:?}"),
+    }
+
+    assert!(within.is_ok(), "{within:?}");
+
+    let errors = over.expect_err("a test over budget was reported as passing");
+
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [Error::TestOverBudget { name, max_cpu: Some(1), .. }] if name == "one_plus_one"
+        ),
+        "{errors:?}"
+    );
+}
@@ -36,7 +36,7 @@ pub fn exec(
 ) -> miette::Result<()> {
     with_project(directory, |p| {
         if rebuild {
-            p.build(false, Tracing::NoTraces)?;
+            p.build(false, false, false, None, Tracing::NoTraces)?;
         }
 
         let title = module.as_ref().map(|m| {
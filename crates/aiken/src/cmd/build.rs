@@ -10,6 +10,20 @@ pub struct Args {
     #[clap(short, long)]
     uplc: bool,
 
+    /// Directory to dump uplc into, relative to the project [default: build/uplc]
+    ///
+    /// Implies --uplc
+    #[clap(long, value_name = "DIR")]
+    uplc_dir: Option<PathBuf>,
+
+    /// Also dump uplc as structured JSON
+    #[clap(long)]
+    emit_uplc_json: bool,
+
+    /// Also dump both optimized and unoptimized uplc, for comparison
+    #[clap(long)]
+    dual: bool,
+
     /// Do not remove traces when generating code
     #[clap(short, long)]
     keep_traces: bool,
@@ -19,8 +33,19 @@ pub fn exec(
     Args {
         directory,
         uplc,
+        uplc_dir,
+        emit_uplc_json,
+        dual,
         keep_traces,
     }: Args,
 ) -> miette::Result<()> {
-    crate::with_project(directory, |p| p.build(uplc, keep_traces.into()))
+    crate::with_project(directory, |p| {
+        p.build(
+            uplc || uplc_dir.is_some(),
+            emit_uplc_json,
+            dual,
+            uplc_dir.clone(),
+            keep_traces.into(),
+        )
+    })
 }
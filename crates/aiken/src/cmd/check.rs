@@ -1,3 +1,4 @@
+use aiken_project::options::CheckOptions;
 use std::path::PathBuf;
 
 #[derive(clap::Args)]
@@ -14,6 +15,10 @@ pub struct Args {
     #[clap(long)]
     debug: bool,
 
+    /// Show the traces of every test, not only those of failing tests
+    #[clap(short, long)]
+    verbose: bool,
+
     /// Only run tests if they match any of these strings.
     /// You can match a module with `-m aiken/list` or `-m list`.
     /// You can match a test with `-m "aiken/list.{map}"` or `-m "aiken/option.{flatten_1}"`
@@ -28,6 +33,14 @@ pub struct Args {
     /// Remove traces when generating code (including tests)
     #[clap(long)]
     no_traces: bool,
+
+    /// Fail tests which spend more than this many memory units
+    #[clap(long)]
+    max_mem: Option<i64>,
+
+    /// Fail tests which spend more than this many CPU units
+    #[clap(long)]
+    max_cpu: Option<i64>,
 }
 
 pub fn exec(
@@ -35,18 +48,24 @@ pub fn exec(
         directory,
         skip_tests,
         debug,
+        verbose,
         match_tests,
         exact_match,
         no_traces,
+        max_mem,
+        max_cpu,
     }: Args,
 ) -> miette::Result<()> {
     crate::with_project(directory, |p| {
-        p.check(
+        p.check(CheckOptions {
             skip_tests,
-            match_tests.clone(),
+            match_tests: match_tests.clone(),
             debug,
+            verbose,
             exact_match,
-            (!no_traces).into(),
-        )
+            max_mem,
+            max_cpu,
+            tracing: (!no_traces).into(),
+        })
     })
 }
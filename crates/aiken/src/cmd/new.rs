@@ -2,13 +2,13 @@ use aiken_project::{
     config::Config,
     package_name::{self, PackageName},
 };
+use clap::ValueEnum;
 use indoc::{formatdoc, indoc};
 use miette::IntoDiagnostic;
 use owo_colors::{OwoColorize, Stream::Stderr};
 use std::{
     fs,
     path::{Path, PathBuf},
-    str::FromStr,
 };
 
 #[derive(clap::Args)]
@@ -19,37 +19,462 @@ pub struct Args {
     /// Library only
     #[clap(long)]
     lib: bool,
+    /// Start from a validator template
+    #[clap(long, conflicts_with = "lib")]
+    template: Option<Template>,
+    /// License of the project, as an SPDX identifier
+    #[clap(long, default_value = "Apache-2.0")]
+    license: License,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum License {
+    #[value(name = "Apache-2.0")]
+    Apache2,
+    #[value(name = "MIT")]
+    Mit,
+    #[value(name = "MPL-2.0")]
+    Mpl2,
+}
+
+impl License {
+    fn spdx(&self) -> &'static str {
+        match self {
+            License::Apache2 => "Apache-2.0",
+            License::Mit => "MIT",
+            License::Mpl2 => "MPL-2.0",
+        }
+    }
+
+    fn text(&self, holder: &str) -> String {
+        match self {
+            License::Apache2 => include_str!("../../templates/licenses/Apache-2.0").to_string(),
+            License::Mit => {
+                include_str!("../../templates/licenses/MIT").replace("{holder}", holder)
+            }
+            License::Mpl2 => include_str!("../../templates/licenses/MPL-2.0").to_string(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Template {
+    Spend,
+    Mint,
+    GiftCard,
+}
+
+impl Template {
+    /// Name of the validator module created from the template.
+    fn module(&self) -> &'static str {
+        match self {
+            Template::Spend => "spend",
+            Template::Mint => "mint",
+            Template::GiftCard => "gift_card",
+        }
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Template::Spend => indoc! {
+                r#"
+                    /// The part of the script context this validator looks at. The standard
+                    /// library defines it in full, in `aiken/transaction`.
+                    type ScriptContext {
+                      transaction: Transaction,
+                      purpose: Data,
+                    }
+
+                    type Transaction {
+                      inputs: Data,
+                      reference_inputs: Data,
+                      outputs: Data,
+                      fee: Data,
+                      mint: Data,
+                      certificates: Data,
+                      withdrawals: Data,
+                      validity_range: Data,
+                      extra_signatories: List<ByteArray>,
+                      redeemers: Data,
+                      datums: Data,
+                      id: Data,
+                    }
+
+                    type Datum {
+                      /// Hash of the verification key of the owner, who must sign the
+                      /// transaction spending the output.
+                      owner: ByteArray,
+                    }
+
+                    type Redeemer {
+                      message: ByteArray,
+                    }
+
+                    fn has(signatories: List<ByteArray>, key: ByteArray) -> Bool {
+                      when signatories is {
+                        [] -> False
+                        [signatory, ..rest] -> signatory == key || has(rest, key)
+                      }
+                    }
+
+                    fn can_spend(datum: Datum, redeemer: Redeemer, context: ScriptContext) -> Bool {
+                      let must_say_hello = redeemer.message == "Hello, World!"
+                      let must_be_signed = has(context.transaction.extra_signatories, datum.owner)
+                      must_say_hello && must_be_signed
+                    }
+
+                    validator {
+                      fn spend(datum: Datum, redeemer: Redeemer, context: ScriptContext) -> Bool {
+                        can_spend(datum, redeemer, context)
+                      }
+                    }
+
+                    fn signed_by(signatories: List<ByteArray>) -> ScriptContext {
+                      let nothing: Data = Void
+                      ScriptContext {
+                        transaction: Transaction {
+                          inputs: nothing,
+                          reference_inputs: nothing,
+                          outputs: nothing,
+                          fee: nothing,
+                          mint: nothing,
+                          certificates: nothing,
+                          withdrawals: nothing,
+                          validity_range: nothing,
+                          extra_signatories: signatories,
+                          redeemers: nothing,
+                          datums: nothing,
+                          id: nothing,
+                        },
+                        purpose: nothing,
+                      }
+                    }
+
+                    test can_spend_when_signed_by_owner() {
+                      let datum = Datum { owner: #"00" }
+                      let redeemer = Redeemer { message: "Hello, World!" }
+                      can_spend(datum, redeemer, signed_by([#"01", #"00"]))
+                    }
+
+                    test cannot_spend_without_owner_signature() {
+                      let datum = Datum { owner: #"00" }
+                      let redeemer = Redeemer { message: "Hello, World!" }
+                      !can_spend(datum, redeemer, signed_by([#"01"]))
+                    }
+                "#
+            },
+            Template::Mint => indoc! {
+                r#"
+                    /// The part of the script context this validator looks at. The standard
+                    /// library defines it in full, in `aiken/transaction`.
+                    type ScriptContext {
+                      transaction: Transaction,
+                      purpose: Data,
+                    }
+
+                    type Transaction {
+                      inputs: List<Input>,
+                      reference_inputs: Data,
+                      outputs: Data,
+                      fee: Data,
+                      mint: Data,
+                      certificates: Data,
+                      withdrawals: Data,
+                      validity_range: Data,
+                      extra_signatories: Data,
+                      redeemers: Data,
+                      datums: Data,
+                      id: Data,
+                    }
+
+                    type Input {
+                      output_reference: OutputReference,
+                      output: Data,
+                    }
+
+                    type OutputReference {
+                      transaction_id: TransactionId,
+                      output_index: Int,
+                    }
+
+                    type TransactionId {
+                      hash: ByteArray,
+                    }
+
+                    fn spends(inputs: List<Input>, utxo_ref: OutputReference) -> Bool {
+                      when inputs is {
+                        [] -> False
+                        [input, ..rest] ->
+                          input.output_reference == utxo_ref || spends(rest, utxo_ref)
+                      }
+                    }
+
+                    fn can_mint(utxo_ref: OutputReference, context: ScriptContext) -> Bool {
+                      spends(context.transaction.inputs, utxo_ref)
+                    }
+
+                    /// Tokens can only be minted by the transaction that spends `utxo_ref`. An
+                    /// output can't be spent twice, so the supply is fixed once minted.
+                    validator(utxo_ref: OutputReference) {
+                      fn mint(_redeemer: Data, context: ScriptContext) -> Bool {
+                        can_mint(utxo_ref, context)
+                      }
+                    }
+
+                    fn spending(inputs: List<Input>) -> ScriptContext {
+                      let nothing: Data = Void
+                      ScriptContext {
+                        transaction: Transaction {
+                          inputs: inputs,
+                          reference_inputs: nothing,
+                          outputs: nothing,
+                          fee: nothing,
+                          mint: nothing,
+                          certificates: nothing,
+                          withdrawals: nothing,
+                          validity_range: nothing,
+                          extra_signatories: nothing,
+                          redeemers: nothing,
+                          datums: nothing,
+                          id: nothing,
+                        },
+                        purpose: nothing,
+                      }
+                    }
+
+                    fn input(output_reference: OutputReference) -> Input {
+                      let output: Data = Void
+                      Input { output_reference, output }
+                    }
+
+                    fn utxo(index: Int) -> OutputReference {
+                      OutputReference { transaction_id: TransactionId { hash: #"00" }, output_index: index }
+                    }
+
+                    test can_mint_when_spending_utxo_ref() {
+                      can_mint(utxo(0), spending([input(utxo(1)), input(utxo(0))]))
+                    }
+
+                    test cannot_mint_without_spending_utxo_ref() {
+                      !can_mint(utxo(0), spending([input(utxo(1))]))
+                    }
+                "#
+            },
+            Template::GiftCard => indoc! {
+                r#"
+                    /// The part of the script context these validators look at. The standard
+                    /// library defines it in full, in `aiken/transaction`.
+                    type ScriptContext {
+                      transaction: Transaction,
+                      purpose: Data,
+                    }
+
+                    /// The purpose of a minting script: the policy of the tokens it mints.
+                    type Minting {
+                      policy_id: ByteArray,
+                    }
+
+                    type Transaction {
+                      inputs: List<Input>,
+                      reference_inputs: Data,
+                      outputs: Data,
+                      fee: Data,
+                      mint: List<(ByteArray, List<(ByteArray, Int)>)>,
+                      certificates: Data,
+                      withdrawals: Data,
+                      validity_range: Data,
+                      extra_signatories: Data,
+                      redeemers: Data,
+                      datums: Data,
+                      id: Data,
+                    }
+
+                    type Input {
+                      output_reference: OutputReference,
+                      output: Data,
+                    }
+
+                    type OutputReference {
+                      transaction_id: TransactionId,
+                      output_index: Int,
+                    }
+
+                    type TransactionId {
+                      hash: ByteArray,
+                    }
+
+                    /// A gift card is a token named `token_name`, minted once when locking a
+                    /// gift and burned to redeem it.
+                    type Action {
+                      Mint
+                      Burn
+                    }
+
+                    fn spends(inputs: List<Input>, utxo_ref: OutputReference) -> Bool {
+                      when inputs is {
+                        [] -> False
+                        [input, ..rest] ->
+                          input.output_reference == utxo_ref || spends(rest, utxo_ref)
+                      }
+                    }
+
+                    /// How many tokens of the given policy and name are minted, or burned when
+                    /// negative.
+                    fn quantity_of(
+                      mint: List<(ByteArray, List<(ByteArray, Int)>)>,
+                      policy_id: ByteArray,
+                      token_name: ByteArray,
+                    ) -> Int {
+                      when mint is {
+                        [] -> 0
+                        [policy, ..rest] ->
+                          if policy.1st == policy_id {
+                            token_quantity_of(policy.2nd, token_name)
+                          } else {
+                            quantity_of(rest, policy_id, token_name)
+                          }
+                      }
+                    }
+
+                    fn token_quantity_of(tokens: List<(ByteArray, Int)>, token_name: ByteArray) -> Int {
+                      when tokens is {
+                        [] -> 0
+                        [token, ..rest] ->
+                          if token.1st == token_name {
+                            token.2nd
+                          } else {
+                            token_quantity_of(rest, token_name)
+                          }
+                      }
+                    }
+
+                    fn can_mint(
+                      token_name: ByteArray,
+                      utxo_ref: OutputReference,
+                      action: Action,
+                      context: ScriptContext,
+                    ) -> Bool {
+                      expect Minting { policy_id }: Minting = context.purpose
+                      let quantity = quantity_of(context.transaction.mint, policy_id, token_name)
+                      when action is {
+                        Mint -> quantity == 1 && spends(context.transaction.inputs, utxo_ref)
+                        Burn -> quantity == -1
+                      }
+                    }
+
+                    fn can_redeem(token_name: ByteArray, policy_id: ByteArray, context: ScriptContext) -> Bool {
+                      quantity_of(context.transaction.mint, policy_id, token_name) == -1
+                    }
+
+                    /// The card can only be minted by the transaction that spends `utxo_ref`,
+                    /// which makes it unique.
+                    validator(token_name: ByteArray, utxo_ref: OutputReference) {
+                      fn mint(redeemer: Action, context: ScriptContext) -> Bool {
+                        can_mint(token_name, utxo_ref, redeemer, context)
+                      }
+                    }
+
+                    /// The gift, locked with the `policy_id` of the card, is released when the
+                    /// card is burned.
+                    validator(token_name: ByteArray, policy_id: ByteArray) {
+                      fn spend(_datum: Data, _redeemer: Data, context: ScriptContext) -> Bool {
+                        can_redeem(token_name, policy_id, context)
+                      }
+                    }
+
+                    /// A transaction spending `inputs` and minting `quantity` tokens named
+                    /// `token_name` of the policy `policy_id`.
+                    fn transaction(
+                      purpose: Data,
+                      inputs: List<Input>,
+                      policy_id: ByteArray,
+                      token_name: ByteArray,
+                      quantity: Int,
+                    ) -> ScriptContext {
+                      let nothing: Data = Void
+                      ScriptContext {
+                        transaction: Transaction {
+                          inputs: inputs,
+                          reference_inputs: nothing,
+                          outputs: nothing,
+                          fee: nothing,
+                          mint: [(policy_id, [(token_name, quantity)])],
+                          certificates: nothing,
+                          withdrawals: nothing,
+                          validity_range: nothing,
+                          extra_signatories: nothing,
+                          redeemers: nothing,
+                          datums: nothing,
+                          id: nothing,
+                        },
+                        purpose: purpose,
+                      }
+                    }
+
+                    fn utxo(index: Int) -> OutputReference {
+                      OutputReference { transaction_id: TransactionId { hash: #"00" }, output_index: index }
+                    }
+
+                    fn input(output_reference: OutputReference) -> Input {
+                      let output: Data = Void
+                      Input { output_reference, output }
+                    }
+
+                    test can_mint_card_by_spending_utxo_ref() {
+                      let minting: Data = Minting { policy_id: #"01" }
+                      let spending = transaction(minting, [input(utxo(0))], #"01", "card", 1)
+                      let not_spending = transaction(minting, [input(utxo(1))], #"01", "card", 1)
+                      can_mint("card", utxo(0), Mint, spending) && !can_mint("card", utxo(0), Mint, not_spending)
+                    }
+
+                    test can_redeem_by_burning_card() {
+                      let spending: Data = utxo(2)
+                      let burning = transaction(spending, [input(utxo(2))], #"01", "card", -1)
+                      let minting = transaction(spending, [input(utxo(2))], #"01", "card", 1)
+                      can_redeem("card", #"01", burning) && !can_redeem("card", #"01", minting)
+                    }
+                "#
+            },
+        }
+    }
 }
 
 pub fn exec(args: Args) -> miette::Result<()> {
-    let package_name = PackageName::from_str(&args.name).into_diagnostic()?;
+    let package_name = PackageName::parse_project_name(&args.name).into_diagnostic()?;
     create_project(args, &package_name)?;
     print_success_message(&package_name);
     Ok(())
 }
 
 fn create_project(args: Args, package_name: &PackageName) -> miette::Result<()> {
-    let root = PathBuf::from(&package_name.repo);
+    create_project_in(&PathBuf::from(&package_name.repo), args, package_name)
+}
 
+fn create_project_in(root: &Path, args: Args, package_name: &PackageName) -> miette::Result<()> {
     if root.exists() {
         Err(package_name::Error::ProjectExists {
             name: package_name.repo.clone(),
         })?;
     }
 
-    create_lib_folder(&root, package_name)?;
+    create_lib_folder(root, package_name)?;
 
     if !args.lib {
-        create_validators_folder(&root)?;
+        create_validators_folder(root, args.template)?;
     }
 
-    readme(&root, &package_name.repo)?;
+    readme(root, &package_name.repo)?;
 
-    Config::default(package_name)
-        .save(&root)
-        .into_diagnostic()?;
+    license(root, args.license, package_name)?;
 
-    gitignore(&root)?;
+    Config {
+        license: Some(args.license.spdx().to_string()),
+        ..Config::default(package_name)
+    }
+    .save(root)
+    .into_diagnostic()?;
+
+    gitignore(root)?;
 
     Ok(())
 }
@@ -87,9 +512,18 @@ fn create_lib_folder(root: &Path, package_name: &PackageName) -> miette::Result<
     Ok(())
 }
 
-fn create_validators_folder(root: &Path) -> miette::Result<()> {
+fn create_validators_folder(root: &Path, template: Option<Template>) -> miette::Result<()> {
     let validators = root.join("validators");
-    fs::create_dir_all(validators).into_diagnostic()?;
+    fs::create_dir_all(&validators).into_diagnostic()?;
+
+    if let Some(template) = template {
+        fs::write(
+            validators.join(format!("{}.ak", template.module())),
+            template.source(),
+        )
+        .into_diagnostic()?;
+    }
+
     Ok(())
 }
 
@@ -114,10 +548,8 @@ fn readme(root: &Path, project_name: &str) -> miette::Result<()> {
 
                 Validators are named after their purpose, so one of:
 
-                - `spent`
-                - `mint`
-                - `withdraw`
-                - `publish`
+                - `spend`, taking a datum, a redeemer and a context
+                - `mint`, `withdraw` or `publish`, taking a redeemer and a context
 
                 ## Building
 
@@ -166,6 +598,16 @@ fn readme(root: &Path, project_name: &str) -> miette::Result<()> {
     ).into_diagnostic()
 }
 
+fn license(root: &Path, license: License, package_name: &PackageName) -> miette::Result<()> {
+    let holder = if package_name.owner.is_empty() {
+        &package_name.repo
+    } else {
+        &package_name.owner
+    };
+
+    fs::write(root.join("LICENSE"), license.text(holder)).into_diagnostic()
+}
+
 fn gitignore(root: &Path) -> miette::Result<()> {
     let gitignore_path = root.join(".gitignore");
 
@@ -186,3 +628,129 @@ fn gitignore(root: &Path) -> miette::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aiken_project::{options::CheckOptions, telemetry::Silent, Project};
+    use clap::Parser;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        args: Args,
+    }
+
+    fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+        Cli::try_parse_from(std::iter::once("new").chain(args.iter().copied())).map(|cli| cli.args)
+    }
+
+    /// Where to create a project, in a temporary directory removed once
+    /// dropped. The project root itself doesn't exist yet.
+    fn temp_root() -> (TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+
+        (dir, root)
+    }
+
+    #[test]
+    fn new_with_mint_template_creates_mint_validator() {
+        let package_name = PackageName::from_str("aiken-lang/minting").unwrap();
+        let (_dir, root) = temp_root();
+
+        let created = create_project_in(
+            &root,
+            parse(&["aiken-lang/minting", "--template", "mint"]).unwrap(),
+            &package_name,
+        );
+
+        assert!(created.is_ok(), "{created:?}");
+
+        let validator = fs::read_to_string(root.join("validators").join("mint.ak")).unwrap();
+
+        assert!(validator.contains("fn mint("));
+    }
+
+    #[test]
+    fn new_with_and_without_owner() {
+        for (name, owner) in [("project", ""), ("owner/project", "owner")] {
+            let package_name = PackageName::parse_project_name(name).unwrap();
+            let (_dir, root) = temp_root();
+
+            let created = create_project_in(&root, parse(&[name]).unwrap(), &package_name);
+
+            assert!(created.is_ok(), "{created:?}");
+
+            let config = Config::load(&root).unwrap();
+
+            assert_eq!(config.name.owner, owner);
+            assert_eq!(config.name.repo, "project");
+            assert_eq!(config.name.to_string(), name);
+        }
+    }
+
+    #[test]
+    fn new_with_license_writes_license_file() {
+        let package_name = PackageName::from_str("aiken-lang/licensed").unwrap();
+        let (_dir, root) = temp_root();
+
+        let created = create_project_in(
+            &root,
+            parse(&["aiken-lang/licensed", "--license", "MIT"]).unwrap(),
+            &package_name,
+        );
+
+        assert!(created.is_ok(), "{created:?}");
+
+        let license = fs::read_to_string(root.join("LICENSE")).unwrap();
+        let config = Config::load(&root).unwrap();
+
+        assert!(license.contains("MIT License"), "{license}");
+        assert!(license.contains("Copyright (c) aiken-lang"), "{license}");
+        assert_eq!(config.license.as_deref(), Some("MIT"));
+    }
+
+    #[test]
+    fn license_defaults_to_apache_and_must_be_known() {
+        assert_eq!(parse(&["foo/bar"]).unwrap().license, License::Apache2);
+        assert_eq!(
+            parse(&["foo/bar", "--license", "MPL-2.0"]).unwrap().license,
+            License::Mpl2
+        );
+        assert!(parse(&["foo/bar", "--license", "GPL-3.0"]).is_err());
+    }
+
+    #[test]
+    fn templates_check_and_pass_their_tests() {
+        for template in Template::value_variants() {
+            let package_name = PackageName::from_str("aiken-lang/template").unwrap();
+            let (_dir, root) = temp_root();
+
+            fs::create_dir_all(root.join("validators")).unwrap();
+            fs::write(
+                root.join("validators")
+                    .join(format!("{}.ak", template.module())),
+                template.source(),
+            )
+            .unwrap();
+
+            let mut config = Config::default(&package_name);
+            config.dependencies = vec![];
+
+            let result =
+                Project::new_with_config(config, root, Silent).check(CheckOptions::default());
+
+            assert!(result.is_ok(), "{template:?}: {result:?}");
+        }
+    }
+
+    #[test]
+    fn template_is_exclusive_with_lib_and_must_be_known() {
+        assert!(parse(&["foo/bar", "--template", "gift-card"]).is_ok());
+        assert!(parse(&["foo/bar", "--lib", "--template", "spend"]).is_err());
+        assert!(parse(&["foo/bar", "--template", "vesting"]).is_err());
+    }
+}
@@ -142,6 +142,17 @@ impl telemetry::EventListener for Terminal {
                         .if_supports_color(Stderr, |s| s.bright_blue())
                 );
             }
+            telemetry::Event::DumpingUPLCJson { path } => {
+                eprintln!(
+                    "{} {} ({})",
+                    "    Exporting"
+                        .if_supports_color(Stderr, |s| s.bold())
+                        .if_supports_color(Stderr, |s| s.purple()),
+                    "UPLC JSON".if_supports_color(Stderr, |s| s.bold()),
+                    path.display()
+                        .if_supports_color(Stderr, |s| s.bright_blue())
+                );
+            }
             telemetry::Event::GeneratingBlueprint { path } => {
                 eprintln!(
                     "{} {} ({})",
@@ -153,6 +164,19 @@ impl telemetry::EventListener for Terminal {
                         .if_supports_color(Stderr, |s| s.bright_blue())
                 );
             }
+            telemetry::Event::ReportingValidatorSizes { validators } => {
+                for validator in validators {
+                    eprintln!(
+                        "{} {} ({} bytes, {} terms)",
+                        "         Size"
+                            .if_supports_color(Stderr, |s| s.bold())
+                            .if_supports_color(Stderr, |s| s.purple()),
+                        validator.title.if_supports_color(Stderr, |s| s.bold()),
+                        validator.serialized_size,
+                        validator.term_size,
+                    );
+                }
+            }
             telemetry::Event::GeneratingDocFiles { output_path } => {
                 eprintln!(
                     "{} in {}",
@@ -200,7 +224,7 @@ impl telemetry::EventListener for Terminal {
                     "...".if_supports_color(Stderr, |s| s.bold())
                 );
             }
-            telemetry::Event::FinishedTests { tests } => {
+            telemetry::Event::FinishedTests { tests, verbose } => {
                 let (max_mem, max_cpu) = find_max_execution_units(&tests);
 
                 for (module, infos) in &group_by_module(&tests) {
@@ -211,7 +235,7 @@ impl telemetry::EventListener for Terminal {
 
                     let tests = infos
                         .iter()
-                        .map(|eval_info| fmt_test(eval_info, max_mem, max_cpu, true))
+                        .map(|eval_info| fmt_test(eval_info, max_mem, max_cpu, verbose, true))
                         .collect::<Vec<String>>()
                         .join("\n");
 
@@ -265,7 +289,15 @@ impl telemetry::EventListener for Terminal {
     }
 }
 
-fn fmt_test(eval_info: &EvalInfo, max_mem: usize, max_cpu: usize, styled: bool) -> String {
+/// Traces of passing tests are only shown when `verbose` is set, whereas
+/// failing tests always show them.
+fn fmt_test(
+    eval_info: &EvalInfo,
+    max_mem: usize,
+    max_cpu: usize,
+    verbose: bool,
+    styled: bool,
+) -> String {
     let EvalInfo {
         success,
         script,
@@ -304,7 +336,7 @@ fn fmt_test(eval_info: &EvalInfo, max_mem: usize, max_cpu: usize, styled: bool)
             .to_string()),
     );
 
-    let logs = if logs.is_empty() {
+    let logs = if logs.is_empty() || (*success && !verbose) {
         String::new()
     } else {
         logs.iter()
@@ -402,3 +434,62 @@ fn find_max_execution_units(xs: &[EvalInfo]) -> (usize, usize) {
 
     (max_mem.to_string().len(), max_cpu.to_string().len())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aiken_project::script::Script;
+    use uplc::{
+        ast::{NamedDeBruijn, Program},
+        parser,
+    };
+
+    fn traced_test() -> EvalInfo {
+        let program: Program<NamedDeBruijn> = parser::program(
+            r#"(program 1.0.0 [ [ (force (builtin trace)) (con string "hello") ] (con bool True) ])"#,
+        )
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+        let mut eval_result = program.eval(ExBudget::default());
+
+        EvalInfo {
+            success: !eval_result.failed(),
+            spent_budget: eval_result.cost(),
+            logs: eval_result.logs(),
+            output: eval_result.result().ok(),
+            script: Script::new(
+                PathBuf::from("lib/foo.ak"),
+                "foo".to_string(),
+                "traced".to_string(),
+                program,
+                None,
+            ),
+        }
+    }
+
+    #[test]
+    fn verbose_test_report_shows_traces_and_budget() {
+        let info = traced_test();
+
+        let ExBudget { mem, cpu } = info.spent_budget;
+
+        let report = fmt_test(&info, 0, 0, true, false);
+
+        assert!(info.success);
+        assert!(
+            report.contains(&format!("[mem: {mem}, cpu: {cpu}]")),
+            "{report}"
+        );
+        assert!(report.contains("↳ hello"), "{report}");
+    }
+
+    #[test]
+    fn passing_test_report_hides_traces_unless_verbose() {
+        let report = fmt_test(&traced_test(), 0, 0, false, false);
+
+        assert!(report.contains("[mem: "), "{report}");
+        assert!(!report.contains("hello"), "{report}");
+    }
+}
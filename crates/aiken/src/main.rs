@@ -12,6 +12,8 @@ use clap::Parser;
 #[clap(propagate_version = true)]
 pub enum Cmd {
     New(new::Args),
+
+    #[clap(alias = "format")]
     Fmt(fmt::Args),
     Build(build::Args),
     Address(address::Args),
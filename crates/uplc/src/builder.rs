@@ -8,6 +8,7 @@ pub const CONSTR_FIELDS_EXPOSER: &str = "__constr_fields_exposer";
 pub const CONSTR_INDEX_EXPOSER: &str = "__constr_index_exposer";
 pub const CONSTR_GET_FIELD: &str = "__constr_get_field";
 pub const EXPECT_ON_LIST: &str = "__expect_on_list";
+pub const LIST_EQUALS: &str = "__list_equals";
 
 impl<T> Term<T> {
     pub fn apply(self, arg: Self) -> Self {
@@ -310,4 +311,65 @@ impl Term<Name> {
                     .lambda(EXPECT_ON_LIST),
             )
     }
+
+    /// Binds `__list_equals`, which takes an element equality and two lists.
+    /// Lists of different lengths are unequal; otherwise elements are
+    /// compared pairwise.
+    pub fn list_equals(self) -> Self {
+        let list_length = Term::var("__list")
+            .delayed_choose_list(
+                Term::integer(0.into()),
+                Term::add_integer().apply(Term::integer(1.into())).apply(
+                    Term::var("__list_length")
+                        .apply(Term::var("__list_length"))
+                        .apply(Term::tail_list().apply(Term::var("__list"))),
+                ),
+            )
+            .lambda("__list")
+            .lambda("__list_length");
+
+        let walk = Term::var("__left_rest")
+            .delayed_choose_list(
+                Term::bool(true),
+                Term::var("__elem_equals")
+                    .apply(Term::head_list().apply(Term::var("__left_rest")))
+                    .apply(Term::head_list().apply(Term::var("__right_rest")))
+                    .delayed_if_else(
+                        Term::var("__walk")
+                            .apply(Term::var("__walk"))
+                            .apply(Term::tail_list().apply(Term::var("__left_rest")))
+                            .apply(Term::tail_list().apply(Term::var("__right_rest"))),
+                        Term::bool(false),
+                    ),
+            )
+            .lambda("__right_rest")
+            .lambda("__left_rest")
+            .lambda("__walk");
+
+        let length_of = |list: &str| {
+            Term::var("__list_length")
+                .apply(Term::var("__list_length"))
+                .apply(Term::var(list))
+        };
+
+        self.lambda(LIST_EQUALS).apply(
+            Term::equals_integer()
+                .apply(length_of("__left"))
+                .apply(length_of("__right"))
+                .delayed_if_else(
+                    Term::var("__walk")
+                        .apply(Term::var("__walk"))
+                        .apply(Term::var("__left"))
+                        .apply(Term::var("__right"))
+                        .lambda("__walk")
+                        .apply(walk),
+                    Term::bool(false),
+                )
+                .lambda("__right")
+                .lambda("__left")
+                .lambda("__elem_equals")
+                .lambda("__list_length")
+                .apply(list_length),
+        )
+    }
 }
@@ -1,5 +1,6 @@
 use std::{fmt::Display, rc::Rc, str::FromStr};
 
+use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 
 use flat_rs::de;
@@ -9,7 +10,7 @@ use crate::ast::Term;
 /// All the possible builtin functions in Untyped Plutus Core.
 #[repr(u8)]
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, PartialEq, Eq, Copy, EnumIter)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, EnumIter, Serialize, Deserialize)]
 pub enum DefaultFunction {
     // Integer functions
     AddInteger = 0,
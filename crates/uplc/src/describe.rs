@@ -0,0 +1,87 @@
+use crate::ast::{Name, Term};
+
+/// A one-line, human-readable summary of a term. This isn't valid source
+/// and is only meant to make generated code easy to compare in tests.
+///
+/// Applications are flattened, so `[[f a] b]` reads `apply f to (a, b)`.
+pub fn describe_term(term: &Term<Name>) -> String {
+    match term {
+        Term::Var(name) => name.text.clone(),
+        Term::Delay(body) => format!("delay {}", describe_operand(body)),
+        Term::Force(body) => format!("force {}", describe_operand(body)),
+        Term::Lambda {
+            parameter_name,
+            body,
+        } => format!("lambda {} -> {}", parameter_name.text, describe_term(body)),
+        Term::Apply { .. } => {
+            let mut function = term;
+            let mut arguments = vec![];
+
+            while let Term::Apply {
+                function: inner,
+                argument,
+            } = function
+            {
+                arguments.push(describe_term(argument));
+                function = inner;
+            }
+
+            arguments.reverse();
+
+            format!(
+                "apply {} to ({})",
+                describe_operand(function),
+                arguments.join(", ")
+            )
+        }
+        Term::Constant(constant) => constant.to_pretty().lines().collect::<Vec<_>>().join(" "),
+        Term::Error => "error".to_string(),
+        Term::Builtin(builtin) => format!("{builtin:?}"),
+    }
+}
+
+fn describe_operand(term: &Term<Name>) -> String {
+    match term {
+        Term::Lambda { .. } | Term::Apply { .. } | Term::Delay { .. } | Term::Force { .. } => {
+            format!("({})", describe_term(term))
+        }
+        _ => describe_term(term),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::describe_term;
+    use crate::parser;
+
+    fn describe(src: &str) -> String {
+        describe_term(&parser::program(src).unwrap().term)
+    }
+
+    #[test]
+    fn describe_lambda_and_application() {
+        assert_eq!(
+            describe("(program 1.0.0 (lam x [(builtin addInteger) x (con integer 1)]))"),
+            "lambda x -> apply AddInteger to (x, integer 1)",
+        );
+    }
+
+    #[test]
+    fn describe_nested_terms() {
+        assert_eq!(
+            describe(
+                r#"
+                (program 1.0.0
+                  [
+                    (force (builtin ifThenElse))
+                    [(lam y y) (con bool True)]
+                    (delay (con bytestring #00ff))
+                    (delay (error))
+                  ]
+                )
+                "#
+            ),
+            "apply (force IfThenElse) to (apply (lambda y -> y) to (bool True), delay bytestring #00ff, delay error)",
+        );
+    }
+}
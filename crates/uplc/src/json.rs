@@ -0,0 +1,333 @@
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use serde_json::{json, Map, Value};
+use strum::IntoEnumIterator;
+
+use crate::{
+    ast::{Constant, Name, Program, Term, Type, Unique},
+    builtins::DefaultFunction,
+    plutus_data, plutus_data_to_bytes,
+};
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("Expected {expected} but found: {found}")]
+    Unexpected { expected: String, found: Value },
+    #[error("Unknown term: {0}")]
+    UnknownTerm(String),
+    #[error("Unknown type: {0}")]
+    UnknownType(String),
+    #[error("Unknown builtin: {0}")]
+    UnknownBuiltin(String),
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+}
+
+impl Program<Name> {
+    /// Structured representation of the program, where each term is
+    /// a JSON object tagged by its kind. This is meant for tooling and
+    /// can be read back with [`Program::from_json`].
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": format!("{}.{}.{}", self.version.0, self.version.1, self.version.2),
+            "term": self.term.to_json(),
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, Error> {
+        let version = as_str(field(value, "version")?)?;
+
+        let version = match version
+            .split('.')
+            .map(|n| n.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .as_deref()
+        {
+            Ok([major, minor, patch]) => (*major, *minor, *patch),
+            _ => return Err(unexpected("a version 'major.minor.patch'", &json!(version))),
+        };
+
+        let term = Term::from_json(field(value, "term")?)?;
+
+        Ok(Program { version, term })
+    }
+}
+
+impl Term<Name> {
+    pub fn to_json(&self) -> Value {
+        match self {
+            Term::Var(name) => json!({ "term": "var", "name": name_to_json(name) }),
+            Term::Delay(term) => json!({ "term": "delay", "body": term.to_json() }),
+            Term::Lambda {
+                parameter_name,
+                body,
+            } => json!({
+                "term": "lambda",
+                "parameter": name_to_json(parameter_name),
+                "body": body.to_json(),
+            }),
+            Term::Apply { function, argument } => json!({
+                "term": "apply",
+                "function": function.to_json(),
+                "argument": argument.to_json(),
+            }),
+            Term::Constant(constant) => json!({
+                "term": "constant",
+                "type": type_to_json(&constant_type(constant)),
+                "value": constant_to_json(constant),
+            }),
+            Term::Force(term) => json!({ "term": "force", "body": term.to_json() }),
+            Term::Error => json!({ "term": "error" }),
+            Term::Builtin(builtin) => json!({ "term": "builtin", "name": builtin.to_string() }),
+        }
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, Error> {
+        let body = |key| Term::from_json(field(value, key)?).map(Rc::new);
+
+        match as_str(field(value, "term")?)? {
+            "var" => Ok(Term::Var(name_from_json(field(value, "name")?)?.into())),
+            "delay" => Ok(Term::Delay(body("body")?)),
+            "lambda" => Ok(Term::Lambda {
+                parameter_name: name_from_json(field(value, "parameter")?)?.into(),
+                body: body("body")?,
+            }),
+            "apply" => Ok(Term::Apply {
+                function: body("function")?,
+                argument: body("argument")?,
+            }),
+            "constant" => {
+                let tipo = type_from_json(field(value, "type")?)?;
+
+                let constant = constant_from_json(&tipo, field(value, "value")?)?;
+
+                Ok(Term::Constant(constant.into()))
+            }
+            "force" => Ok(Term::Force(body("body")?)),
+            "error" => Ok(Term::Error),
+            "builtin" => {
+                let name = as_str(field(value, "name")?)?;
+
+                DefaultFunction::iter()
+                    .find(|builtin| builtin.to_string() == name)
+                    .map(Term::Builtin)
+                    .ok_or_else(|| Error::UnknownBuiltin(name.to_string()))
+            }
+            other => Err(Error::UnknownTerm(other.to_string())),
+        }
+    }
+}
+
+fn name_to_json(name: &Name) -> Value {
+    json!({ "text": name.text, "unique": isize::from(name.unique) })
+}
+
+fn name_from_json(value: &Value) -> Result<Name, Error> {
+    let text = as_str(field(value, "text")?)?.to_string();
+
+    let unique = field(value, "unique")?;
+
+    let unique = unique
+        .as_i64()
+        .ok_or_else(|| unexpected("an integer", unique))?;
+
+    Ok(Name {
+        text,
+        unique: Unique::new(unique as isize),
+    })
+}
+
+fn constant_type(constant: &Constant) -> Type {
+    match constant {
+        Constant::Integer(_) => Type::Integer,
+        Constant::ByteString(_) => Type::ByteString,
+        Constant::String(_) => Type::String,
+        Constant::Unit => Type::Unit,
+        Constant::Bool(_) => Type::Bool,
+        Constant::ProtoList(tipo, _) => Type::List(tipo.clone().into()),
+        Constant::ProtoPair(left, right, _, _) => {
+            Type::Pair(left.clone().into(), right.clone().into())
+        }
+        Constant::Data(_) => Type::Data,
+    }
+}
+
+fn type_to_json(tipo: &Type) -> Value {
+    match tipo {
+        Type::List(elem) => json!({ "list": type_to_json(elem) }),
+        Type::Pair(left, right) => json!({ "pair": [type_to_json(left), type_to_json(right)] }),
+        simple => json!(simple.to_string()),
+    }
+}
+
+fn type_from_json(value: &Value) -> Result<Type, Error> {
+    match value {
+        Value::String(tipo) => match tipo.as_str() {
+            "bool" => Ok(Type::Bool),
+            "integer" => Ok(Type::Integer),
+            "string" => Ok(Type::String),
+            "bytestring" => Ok(Type::ByteString),
+            "unit" => Ok(Type::Unit),
+            "data" => Ok(Type::Data),
+            other => Err(Error::UnknownType(other.to_string())),
+        },
+        Value::Object(map) if map.contains_key("list") => {
+            Ok(Type::List(type_from_json(&map["list"])?.into()))
+        }
+        Value::Object(map) if map.contains_key("pair") => match as_array(&map["pair"])? {
+            [left, right] => Ok(Type::Pair(
+                type_from_json(left)?.into(),
+                type_from_json(right)?.into(),
+            )),
+            _ => Err(unexpected("a pair of types", &map["pair"])),
+        },
+        other => Err(unexpected("a type", other)),
+    }
+}
+
+/// Integers are kept as strings, as they are arbitrarily large and would
+/// otherwise lose precision in most JSON parsers. Bytestrings and data are
+/// base16-encoded, the latter using its CBOR serialisation.
+fn constant_to_json(constant: &Constant) -> Value {
+    match constant {
+        Constant::Integer(i) => json!(i.to_string()),
+        Constant::ByteString(bytes) => json!(hex::encode(bytes)),
+        Constant::String(s) => json!(s),
+        Constant::Unit => Value::Null,
+        Constant::Bool(b) => json!(b),
+        Constant::ProtoList(_, items) => Value::Array(items.iter().map(constant_to_json).collect()),
+        Constant::ProtoPair(_, _, left, right) => {
+            json!([constant_to_json(left), constant_to_json(right)])
+        }
+        Constant::Data(data) => json!(hex::encode(plutus_data_to_bytes(data).unwrap())),
+    }
+}
+
+fn constant_from_json(tipo: &Type, value: &Value) -> Result<Constant, Error> {
+    match tipo {
+        Type::Integer => {
+            let i = as_str(value)?;
+
+            i.parse::<BigInt>()
+                .map(Constant::Integer)
+                .map_err(|_| unexpected("an integer", value))
+        }
+        Type::ByteString => hex::decode(as_str(value)?)
+            .map(Constant::ByteString)
+            .map_err(|_| unexpected("a base16-encoded bytestring", value)),
+        Type::String => Ok(Constant::String(as_str(value)?.to_string())),
+        Type::Unit => match value {
+            Value::Null => Ok(Constant::Unit),
+            other => Err(unexpected("null", other)),
+        },
+        Type::Bool => value
+            .as_bool()
+            .map(Constant::Bool)
+            .ok_or_else(|| unexpected("a boolean", value)),
+        Type::List(elem) => {
+            let items = as_array(value)?
+                .iter()
+                .map(|item| constant_from_json(elem, item))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(Constant::ProtoList(elem.as_ref().clone(), items))
+        }
+        Type::Pair(left_type, right_type) => match as_array(value)? {
+            [left, right] => Ok(Constant::ProtoPair(
+                left_type.as_ref().clone(),
+                right_type.as_ref().clone(),
+                constant_from_json(left_type, left)?.into(),
+                constant_from_json(right_type, right)?.into(),
+            )),
+            _ => Err(unexpected("a pair of constants", value)),
+        },
+        Type::Data => {
+            let bytes = hex::decode(as_str(value)?)
+                .map_err(|_| unexpected("base16-encoded CBOR data", value))?;
+
+            plutus_data(&bytes)
+                .map(Constant::Data)
+                .map_err(|e| Error::InvalidData(e.to_string()))
+        }
+    }
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Result<&'a Value, Error> {
+    value
+        .as_object()
+        .and_then(|object: &Map<String, Value>| object.get(key))
+        .ok_or_else(|| unexpected(&format!("an object with a '{key}' field"), value))
+}
+
+fn as_str(value: &Value) -> Result<&str, Error> {
+    value.as_str().ok_or_else(|| unexpected("a string", value))
+}
+
+fn as_array(value: &Value) -> Result<&[Value], Error> {
+    value
+        .as_array()
+        .map(|items| items.as_slice())
+        .ok_or_else(|| unexpected("an array", value))
+}
+
+fn unexpected(expected: &str, found: &Value) -> Error {
+    Error::Unexpected {
+        expected: expected.to_string(),
+        found: found.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{DeBruijn, Name, Program},
+        parser,
+    };
+
+    fn round_trip(src: &str) {
+        let program = parser::program(src).unwrap();
+
+        let json = program.to_json();
+
+        let decoded = Program::<Name>::from_json(&json).unwrap();
+
+        let expected: Program<DeBruijn> = program.try_into().unwrap();
+        let actual: Program<DeBruijn> = decoded.try_into().unwrap();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn round_trip_terms() {
+        round_trip(
+            r#"
+            (program 1.0.0
+              [
+                [
+                  (lam x (lam y [ [ (builtin addInteger) x ] y ]))
+                  (force (delay (con integer 340282366920938463463374607431768211456)))
+                ]
+                (error)
+              ]
+            )
+            "#,
+        );
+    }
+
+    #[test]
+    fn round_trip_constants() {
+        for constant in [
+            "(con integer -42)",
+            "(con bytestring #deadbeef)",
+            "(con string \"hello\")",
+            "(con unit ())",
+            "(con bool True)",
+            "(con list<integer> [1, 2, 3])",
+            "(con pair<integer, list<bool>> [14, [True, False]])",
+            "(con data #d8799f0102ff)",
+        ] {
+            round_trip(&format!("(program 1.0.0 {constant})"));
+        }
+    }
+}
@@ -2,7 +2,9 @@ pub mod ast;
 pub mod builder;
 pub mod builtins;
 mod debruijn;
+pub mod describe;
 mod flat;
+pub mod json;
 pub mod machine;
 pub mod optimize;
 pub mod parser;
@@ -19,7 +19,9 @@ pub fn aiken_optimize_and_intern(program: Program<Name>) -> Program<Name> {
 
     program
         .lambda_reduce()
+        .dead_code_reduce()
         .inline_reduce()
         .lambda_reduce()
         .inline_reduce()
+        .force_delay_reduce()
 }
@@ -65,6 +65,15 @@ impl Program<Name> {
             term,
         }
     }
+
+    pub fn dead_code_reduce(self) -> Program<Name> {
+        let mut term = self.term.clone();
+        dead_code_reduce(&mut term);
+        Program {
+            version: self.version,
+            term,
+        }
+    }
 }
 
 fn builtin_force_reduce(term: &mut Term<Name>, builtin_map: &mut IndexMap<u8, ()>) {
@@ -128,12 +137,23 @@ fn force_delay_reduce(term: &mut Term<Name>) {
 
             if let Term::Delay(body) = f {
                 *term = body.as_ref().clone();
+                force_delay_reduce(term);
             } else {
                 force_delay_reduce(f);
             }
         }
         Term::Delay(d) => {
             let d = Rc::make_mut(d);
+
+            // Only safe for variables, which are already values, so delaying
+            // their force doesn't change when (or whether) anything fails.
+            if let Term::Force(body) = d {
+                if let Term::Var(_) = body.as_ref() {
+                    *term = body.as_ref().clone();
+                    return;
+                }
+            }
+
             force_delay_reduce(d);
         }
         Term::Lambda { body, .. } => {
@@ -196,6 +216,70 @@ fn inline_basic_reduce(term: &mut Term<Name>) {
     }
 }
 
+/// Drops `[(lam x body) arg]` down to `body` when `x` is never used and `arg`
+/// can be discarded without changing the outcome of the program.
+fn dead_code_reduce(term: &mut Term<Name>) {
+    match term {
+        Term::Apply { function, argument } => {
+            let func = Rc::make_mut(function);
+            dead_code_reduce(func);
+
+            let arg = Rc::make_mut(argument);
+            dead_code_reduce(arg);
+
+            if let Term::Lambda {
+                parameter_name,
+                body,
+            } = func
+            {
+                let mut occurrences = 0;
+                var_occurrences(body, parameter_name.clone(), &mut occurrences);
+
+                if occurrences == 0 && is_discardable(arg) {
+                    *term = body.as_ref().clone();
+                }
+            }
+        }
+        Term::Delay(d) => {
+            let d = Rc::make_mut(d);
+            dead_code_reduce(d);
+        }
+        Term::Lambda { body, .. } => {
+            let body = Rc::make_mut(body);
+            dead_code_reduce(body);
+        }
+        Term::Force(f) => {
+            let f = Rc::make_mut(f);
+            dead_code_reduce(f);
+        }
+        _ => {}
+    }
+}
+
+/// Only values are discarded, as applications may fail at runtime. We also
+/// stay away from anything that mentions `error` or `trace`, even when it
+/// wouldn't run.
+fn is_discardable(term: &Term<Name>) -> bool {
+    match term {
+        Term::Var(_) | Term::Constant(_) => true,
+        Term::Builtin(builtin) => *builtin != DefaultFunction::Trace,
+        Term::Force(inner) => matches!(inner.as_ref(), Term::Builtin(_)) && is_discardable(inner),
+        Term::Lambda { body, .. } | Term::Delay(body) => !has_side_effects(body),
+        Term::Apply { .. } | Term::Error => false,
+    }
+}
+
+fn has_side_effects(term: &Term<Name>) -> bool {
+    match term {
+        Term::Error | Term::Builtin(DefaultFunction::Trace) => true,
+        Term::Delay(body) | Term::Force(body) | Term::Lambda { body, .. } => has_side_effects(body),
+        Term::Apply { function, argument } => {
+            has_side_effects(function) || has_side_effects(argument)
+        }
+        Term::Var(_) | Term::Constant(_) | Term::Builtin(_) => false,
+    }
+}
+
 fn var_occurrences(term: &Term<Name>, search_for: Rc<Name>, occurrences: &mut usize) {
     match term {
         Term::Var(name) => {
@@ -323,3 +407,93 @@ fn substitute_term(term: &Term<Name>, original: Rc<Name>, replace_with: &Term<Na
         x => x.clone(),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{NamedDeBruijn, Program},
+        machine::cost_model::ExBudget,
+        parser,
+    };
+
+    #[test]
+    fn dead_code_reduce_removes_unused_pure_binding() {
+        let program = parser::program(
+            r#"
+            (program 1.0.0
+              [ (lam f (con integer 42)) (lam x [ (builtin addInteger) x x ]) ]
+            )
+            "#,
+        )
+        .unwrap();
+
+        let expected = parser::program("(program 1.0.0 (con integer 42))").unwrap();
+
+        assert_eq!(program.dead_code_reduce(), expected);
+    }
+
+    #[test]
+    fn dead_code_reduce_keeps_unused_effectful_binding() {
+        for src in [
+            r#"
+            (program 1.0.0
+              [
+                (lam x (con integer 42))
+                [ [ (force (builtin trace)) (con string "hello") ] (con integer 0) ]
+              ]
+            )
+            "#,
+            r#"
+            (program 1.0.0
+              [ (lam x (con integer 42)) (delay [ (force (builtin trace)) (con string "hello") ]) ]
+            )
+            "#,
+            r#"
+            (program 1.0.0
+              [ (lam x (con integer 42)) (error) ]
+            )
+            "#,
+        ] {
+            let program = parser::program(src).unwrap();
+
+            assert_eq!(program.clone().dead_code_reduce(), program);
+        }
+    }
+
+    #[test]
+    fn force_delay_reduce() {
+        let program = parser::program(
+            r#"
+            (program 1.0.0
+              (force
+                [
+                  (lam x (force (delay (force (delay (delay (force x)))))))
+                  (delay (con integer 42))
+                ]
+              )
+            )
+            "#,
+        )
+        .unwrap();
+
+        let expected = parser::program(
+            r#"
+            (program 1.0.0
+              (force [ (lam x x) (delay (con integer 42)) ])
+            )
+            "#,
+        )
+        .unwrap();
+
+        let reduced = program.clone().force_delay_reduce();
+
+        assert_eq!(reduced, expected);
+
+        let eval = |program: Program<_>| {
+            let program: Program<NamedDeBruijn> = program.try_into().unwrap();
+            program.eval(ExBudget::default()).result().unwrap()
+        };
+
+        assert_eq!(eval(program), eval(reduced));
+    }
+}